@@ -1,5 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
-use knightbot::board::tables::{generate_rank_attack_table, test_attack_mask};
+use knightbot::board::tables::generate_rank_attack_table;
 
 fn benchmark_generate_rank_attack_table(c: &mut Criterion) {
     let mut group = c.benchmark_group("generate_rank_attack_table");
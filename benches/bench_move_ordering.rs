@@ -0,0 +1,54 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use knightbot::board::board::Board;
+use knightbot::search::{SearchLimits, Searcher};
+
+/// A few middlegame positions with plenty of captures and quiet moves to order.
+const POSITIONS: [&str; 4] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N2N2/PP2BPPP/R2QKB1R w KQ - 0 8",
+    "2r3k1/1q1nbppp/r3p3/3pP3/pPpP4/P1Q2N2/2RN1PPP/2R4K b - - 0 23",
+];
+
+const DEPTH: i32 = 5;
+
+/// Searches every position to a fixed depth with a fresh searcher and prints how often the
+/// first move searched caused the beta cutoff, which is the main measure of move ordering quality.
+fn report_first_move_cutoff_rate() {
+    let mut beta_cutoffs = 0;
+    let mut first_move_cutoffs = 0;
+    for fen in POSITIONS {
+        let mut board = Board::from_fen(fen).unwrap();
+        let mut searcher = Searcher::new(16);
+        searcher.search(&mut board, SearchLimits { depth: Some(DEPTH), ..SearchLimits::default() });
+        println!("{fen}: first move cutoff rate {:.1}%", searcher.stats.first_move_cutoff_rate() * 100.0);
+        beta_cutoffs += searcher.stats.beta_cutoffs;
+        first_move_cutoffs += searcher.stats.first_move_cutoffs;
+    }
+    println!(
+        "Total first move cutoff rate at depth {DEPTH}: {:.1}% of {beta_cutoffs} cutoffs",
+        first_move_cutoffs as f64 / beta_cutoffs.max(1) as f64 * 100.0
+    );
+}
+
+fn benchmark_move_ordering(c: &mut Criterion) {
+    report_first_move_cutoff_rate();
+
+    let mut group = c.benchmark_group("move_ordering");
+    group.sample_size(10);
+
+    for (i, fen) in POSITIONS.iter().enumerate() {
+        group.bench_function(BenchmarkId::new("search_depth_5", i), |b| {
+            b.iter(|| {
+                let mut board = Board::from_fen(fen).unwrap();
+                let mut searcher = Searcher::new(16);
+                black_box(searcher.search(&mut board, SearchLimits { depth: Some(DEPTH), ..SearchLimits::default() }))
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_move_ordering);
+criterion_main!(benches);
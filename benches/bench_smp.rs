@@ -0,0 +1,50 @@
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use knightbot::board::board::Board;
+use knightbot::search::{smp::ThreadPool, SearchLimits};
+
+const POSITIONS: [&str; 3] = [
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N2N2/PP2BPPP/R2QKB1R w KQ - 0 8",
+    "2r3k1/1q1nbppp/r3p3/3pP3/pPpP4/P1Q2N2/2RN1PPP/2R4K b - - 0 23",
+];
+
+const DEPTH: i32 = 9;
+
+/// Measures the time it takes to reach a fixed depth with an increasing number of threads. With
+/// lazy SMP the helpers fill the shared transposition table, so the main thread should reach the
+/// depth faster as threads are added (given enough cores).
+fn benchmark_time_to_depth(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lazy_smp_time_to_depth");
+    group.sample_size(10);
+
+    let max_threads = std::thread::available_parallelism().map_or(1, |n| n.get()).min(8);
+    let mut threads = 1;
+    while threads <= max_threads {
+        // The pool and its table are built once; only the searches are timed, each starting from
+        // a cleared table so that earlier iterations don't help later ones.
+        let mut pool = ThreadPool::new(threads, 64);
+        group.bench_function(BenchmarkId::new("threads", threads), |b| {
+            b.iter_custom(|iterations| {
+                let mut elapsed = Duration::ZERO;
+                for _ in 0..iterations {
+                    for fen in POSITIONS {
+                        let board = Board::from_fen(fen).unwrap();
+                        pool.clear();
+                        let start = Instant::now();
+                        pool.search(&board, SearchLimits { depth: Some(DEPTH), ..SearchLimits::default() });
+                        elapsed += start.elapsed();
+                    }
+                }
+                elapsed
+            })
+        });
+        threads *= 2;
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_time_to_depth);
+criterion_main!(benches);
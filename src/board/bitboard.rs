@@ -1,9 +1,9 @@
-use std::{arch::x86_64, num::{NonZeroU64, NonZeroUsize}, ops::{BitAnd, BitOr, Deref, Sub}, path::Iter};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Deref, Not, Shl, Shr, Sub};
 
 use super::square::Square;
 
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct BitBoard(pub u64);
 
 impl BitBoard {
@@ -58,13 +58,35 @@ impl BitBoard {
         self & (self - BitBoard(1)) 
     }
 
+    /// Returns the square of the least significant set bit. The BitBoard must not be empty.
+    pub fn lsb(self) -> Square {
+        Square::from_index(self.0.trailing_zeros() as u8)
+    }
+
+    /// Returns true if no bits are set.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the number of set bits, i.e. the number of squares in the BitBoard.
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// Returns true if the bit for the given square is set.
+    pub fn contains(self, square: Square) -> bool {
+        self.0 & (1 << square as u8) != 0
+    }
+
 
     /// Returns a BitBoard with one bit set at the given square (e.g. "A4").
     /// 
     ///  # Examples
     /// ```
-    /// let A4 = BitBoard::from_square("A4");
-    /// assert_eq!(A4.0, 0x0000000010000000);
+    /// use knightbot::board::bitboard::BitBoard;
+    ///
+    /// let a4 = BitBoard::from_square("A4");
+    /// assert_eq!(a4.0, 0x0000000001000000);
     /// ```
     /// This BitBoard can then be OR'd with other BitBoards to represent a group of squares.
     pub fn from_square(square: &str) -> BitBoard {
@@ -80,6 +102,18 @@ impl BitBoard {
 
 }
 
+impl Default for BitBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Square> for BitBoard {
+    fn from(square: Square) -> Self {
+        BitBoard(1 << square as u8)
+    }
+}
+
 impl Deref for BitBoard {
     type Target = u64;
 
@@ -120,4 +154,52 @@ impl BitAnd for BitBoard {
     }
 }
 
+impl BitXor for BitBoard {
+    type Output = BitBoard;
+
+    fn bitxor(self, rhs: BitBoard) -> Self::Output {
+        BitBoard(self.0 ^ rhs.0)
+    }
+}
+
+impl Not for BitBoard {
+    type Output = BitBoard;
 
+    fn not(self) -> Self::Output {
+        BitBoard(!self.0)
+    }
+}
+
+impl Shl<u8> for BitBoard {
+    type Output = BitBoard;
+
+    fn shl(self, rhs: u8) -> Self::Output {
+        BitBoard(self.0 << rhs)
+    }
+}
+
+impl Shr<u8> for BitBoard {
+    type Output = BitBoard;
+
+    fn shr(self, rhs: u8) -> Self::Output {
+        BitBoard(self.0 >> rhs)
+    }
+}
+
+impl BitOrAssign for BitBoard {
+    fn bitor_assign(&mut self, rhs: BitBoard) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAndAssign for BitBoard {
+    fn bitand_assign(&mut self, rhs: BitBoard) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl BitXorAssign for BitBoard {
+    fn bitxor_assign(&mut self, rhs: BitBoard) {
+        self.0 ^= rhs.0;
+    }
+}
@@ -1,5 +1,81 @@
-use super::bitboard::BitBoard;
+use std::sync::Arc;
 
+use crate::eval::{
+    self,
+    nnue::{Network, Nnue},
+    Score, PHASE_WEIGHTS,
+};
+
+use super::{
+    bitboard::BitBoard,
+    movegen::{bishop_attacks, generate_legal_moves, king_attacks, knight_attacks, pawn_attacks, rook_attacks},
+    moves::Move,
+    piece::{Color, Piece},
+    square::Square,
+    tile::Tile,
+    zobrist::ZOBRIST,
+};
+
+/// The castling rights of both sides, stored as four bit flags. Bit `i` is the right whose rook
+/// starts on `Board::castling_rooks[i]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct CastlingRights(pub u8);
+
+impl CastlingRights {
+    pub const NONE: CastlingRights = CastlingRights(0);
+    pub const WHITE_KINGSIDE: CastlingRights = CastlingRights(1);
+    pub const WHITE_QUEENSIDE: CastlingRights = CastlingRights(2);
+    pub const BLACK_KINGSIDE: CastlingRights = CastlingRights(4);
+    pub const BLACK_QUEENSIDE: CastlingRights = CastlingRights(8);
+    pub const ALL: CastlingRights = CastlingRights(15);
+
+    pub fn contains(self, rights: CastlingRights) -> bool {
+        self.0 & rights.0 == rights.0
+    }
+
+    pub fn insert(&mut self, rights: CastlingRights) {
+        self.0 |= rights.0;
+    }
+
+    pub fn remove(&mut self, rights: CastlingRights) {
+        self.0 &= !rights.0;
+    }
+
+    /// The index of the right to castle on the given side, into `Board::castling_rooks`.
+    pub fn index(color: Color, kingside: bool) -> usize {
+        2 * color.index() + if kingside { 0 } else { 1 }
+    }
+
+    /// The right to castle on the given side.
+    pub fn of(color: Color, kingside: bool) -> CastlingRights {
+        CastlingRights(1 << CastlingRights::index(color, kingside))
+    }
+
+    /// Both castling rights of a color.
+    pub fn both(color: Color) -> CastlingRights {
+        CastlingRights(CastlingRights::of(color, true).0 | CastlingRights::of(color, false).0)
+    }
+}
+
+/// The start squares of the castling rooks in the standard starting position, indexed like
+/// `Board::castling_rooks`.
+pub const STANDARD_CASTLING_ROOKS: [Square; 4] = [Square::H1, Square::A1, Square::H8, Square::A8];
+
+/// The irreversible parts of a position, saved by `make_move` so `unmake_move` can restore them.
+#[derive(Clone, Copy, Debug)]
+pub struct StateInfo {
+    /// The move played, or None for a null move.
+    pub mv: Option<Move>,
+    pub captured: Option<Piece>,
+    pub castling_rights: CastlingRights,
+    pub en_passant: Option<Square>,
+    pub halfmove_clock: u16,
+    pub hash: u64,
+}
+
+/// A chess position and the moves that led to it. The fields are the engine's internal
+/// representation and may change; use the methods to query and update the position.
+#[derive(Clone, Debug)]
 pub struct Board {
     //pub tiles: [[Tile; 8]; 8]
     pub white_pieces: BitBoard,
@@ -17,11 +93,38 @@ pub struct Board {
     pub white_king: BitBoard,
     pub black_king: BitBoard,
     pub all_pieces: BitBoard,
+
+    /// The piece on each square, indexed by `Square::index`. Kept in sync with the bitboards.
+    pub tiles: [Tile; 64],
+    pub side_to_move: Color,
+    pub castling_rights: CastlingRights,
+    /// The start squares of the rooks of the four castling rights, indexed by
+    /// `CastlingRights::index`. Only differs from `STANDARD_CASTLING_ROOKS` in Chess960.
+    pub castling_rooks: [Square; 4],
+    /// Whether castling moves are encoded as the king capturing its own rook, as in Chess960, or as
+    /// the king moving two squares. Set for Chess960 positions and by the UCI_Chess960 option.
+    pub chess960: bool,
+    /// The square a pawn skipped over with a double push, if an enemy pawn can capture onto it.
+    pub en_passant: Option<Square>,
+    pub halfmove_clock: u16,
+    pub fullmove_number: u16,
+    /// The Zobrist hash of the position.
+    pub hash: u64,
+    /// The Zobrist hash of the pawns alone, which keys the pawn hash table.
+    pub pawn_hash: u64,
+    /// The material and piece-square score from white's point of view, see `eval::psqt`.
+    pub psqt: Score,
+    /// The game phase, the sum of `PHASE_WEIGHTS` over all pieces on the board.
+    pub phase: i32,
+    /// The NNUE network and its accumulator for this position, if the engine evaluates with one.
+    pub nnue: Option<Nnue>,
+    /// The state before each move played with `make_move`, most recent last.
+    pub history: Vec<StateInfo>,
 }
 
 impl Board {
     pub fn new() -> Self {
-        Self {
+        let mut board = Self {
             white_pieces: BitBoard(0x000000000000FFFF),
             black_pieces: BitBoard(0xFFFF000000000000),
 
@@ -39,18 +142,603 @@ impl Board {
             black_queens: BitBoard(0x0800000000000000),
             white_king: BitBoard(0x0000000000000010),
             black_king: BitBoard(0x1000000000000000),
-            
+
+            tiles: [Tile::Empty; 64],
+            side_to_move: Color::White,
+            castling_rights: CastlingRights::ALL,
+            castling_rooks: STANDARD_CASTLING_ROOKS,
+            chess960: false,
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            hash: 0,
+            pawn_hash: 0,
+            psqt: Score::ZERO,
+            phase: 0,
+            nnue: None,
+            history: Vec::new(),
+        };
+        board.update();
+        board
+    }
+
+    /// Returns an empty board with white to move, for building positions piece by piece.
+    pub fn empty() -> Self {
+        let mut board = Self::new();
+        for piece in Piece::ALL {
+            *board.pieces_mut(piece, Color::White) = BitBoard::EMPTY;
+            *board.pieces_mut(piece, Color::Black) = BitBoard::EMPTY;
         }
+        board.castling_rights = CastlingRights::NONE;
+        board.update();
+        board
     }
 
-    // updates the different bitboards containing groupings of bitboards
+    // updates the different bitboards containing groupings of bitboards, as well as the tiles, the hash
+    // and the incrementally updated evaluation terms
     pub fn update(&mut self) {
         self.white_pieces = self.white_pawns | self.white_knights | self.white_bishops | self.white_rooks | self.white_queens | self.white_king;
         self.black_pieces = self.black_pawns | self.black_knights | self.black_bishops | self.black_rooks | self.black_queens | self.black_king;
         self.all_pieces = self.white_pieces | self.black_pieces;
 
+        self.tiles = [Tile::Empty; 64];
+        self.psqt = Score::ZERO;
+        self.phase = 0;
+        for color in [Color::White, Color::Black] {
+            for piece in Piece::ALL {
+                for square in self.pieces(piece, color).iter_squares() {
+                    self.tiles[square.index()] = Tile::Occupied { piece, color };
+                    self.psqt += eval::psqt(piece, color, square);
+                    self.phase += PHASE_WEIGHTS[piece.index()];
+                }
+            }
+        }
+        self.hash = self.compute_hash();
+        self.pawn_hash = self.compute_pawn_hash();
+        if let Some(mut nnue) = self.nnue.take() {
+            nnue.refresh(self);
+            self.nnue = Some(nnue);
+        }
+    }
+
+    /// Evaluates the position with the network from now on, or with the handcrafted evaluation
+    /// if there is none.
+    pub fn set_network(&mut self, network: Option<Arc<Network>>) {
+        self.nnue = network.map(|network| Nnue::new(network, self));
+    }
+
+    /// Returns the bitboard of the given piece type and color.
+    pub fn pieces(&self, piece: Piece, color: Color) -> BitBoard {
+        match (color, piece) {
+            (Color::White, Piece::Pawn) => self.white_pawns,
+            (Color::White, Piece::Knight) => self.white_knights,
+            (Color::White, Piece::Bishop) => self.white_bishops,
+            (Color::White, Piece::Rook) => self.white_rooks,
+            (Color::White, Piece::Queen) => self.white_queens,
+            (Color::White, Piece::King) => self.white_king,
+            (Color::Black, Piece::Pawn) => self.black_pawns,
+            (Color::Black, Piece::Knight) => self.black_knights,
+            (Color::Black, Piece::Bishop) => self.black_bishops,
+            (Color::Black, Piece::Rook) => self.black_rooks,
+            (Color::Black, Piece::Queen) => self.black_queens,
+            (Color::Black, Piece::King) => self.black_king,
+        }
+    }
+
+    /// Returns a mutable reference to the bitboard of the given piece type and color.
+    pub fn pieces_mut(&mut self, piece: Piece, color: Color) -> &mut BitBoard {
+        match (color, piece) {
+            (Color::White, Piece::Pawn) => &mut self.white_pawns,
+            (Color::White, Piece::Knight) => &mut self.white_knights,
+            (Color::White, Piece::Bishop) => &mut self.white_bishops,
+            (Color::White, Piece::Rook) => &mut self.white_rooks,
+            (Color::White, Piece::Queen) => &mut self.white_queens,
+            (Color::White, Piece::King) => &mut self.white_king,
+            (Color::Black, Piece::Pawn) => &mut self.black_pawns,
+            (Color::Black, Piece::Knight) => &mut self.black_knights,
+            (Color::Black, Piece::Bishop) => &mut self.black_bishops,
+            (Color::Black, Piece::Rook) => &mut self.black_rooks,
+            (Color::Black, Piece::Queen) => &mut self.black_queens,
+            (Color::Black, Piece::King) => &mut self.black_king,
+        }
+    }
+
+    /// Returns all pieces of the given color.
+    pub fn color_pieces(&self, color: Color) -> BitBoard {
+        match color {
+            Color::White => self.white_pieces,
+            Color::Black => self.black_pieces,
+        }
+    }
+
+    /// Returns the piece and its color on the given square, if any.
+    pub fn piece_at(&self, square: Square) -> Option<(Piece, Color)> {
+        match self.tiles[square.index()] {
+            Tile::Empty => None,
+            Tile::Occupied { piece, color } => Some((piece, color)),
+        }
+    }
+
+    /// Returns the square of the king of the given color.
+    pub fn king_square(&self, color: Color) -> Square {
+        self.pieces(Piece::King, color).lsb()
+    }
+
+    /// Computes the Zobrist hash of the position from scratch.
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = 0;
+        for square in self.all_pieces.iter_squares() {
+            if let Some((piece, color)) = self.piece_at(square) {
+                hash ^= ZOBRIST.pieces[color.index()][piece.index()][square.index()];
+            }
+        }
+        hash ^= ZOBRIST.castling[self.castling_rights.0 as usize];
+        if let Some(square) = self.en_passant {
+            hash ^= ZOBRIST.en_passant[square.file() as usize];
+        }
+        if self.side_to_move == Color::Black {
+            hash ^= ZOBRIST.side;
+        }
+        hash
+    }
+
+    /// Computes the Zobrist hash of the pawns from scratch.
+    pub fn compute_pawn_hash(&self) -> u64 {
+        let mut hash = 0;
+        for color in [Color::White, Color::Black] {
+            for square in self.pieces(Piece::Pawn, color).iter_squares() {
+                hash ^= ZOBRIST.pieces[color.index()][Piece::Pawn.index()][square.index()];
+            }
+        }
+        hash
+    }
+
+    /// Places a piece on an empty square, updating the bitboards, tiles, hash and evaluation terms.
+    pub fn put_piece(&mut self, square: Square, piece: Piece, color: Color) {
+        let bit = BitBoard::from(square);
+        *self.pieces_mut(piece, color) |= bit;
+        match color {
+            Color::White => self.white_pieces |= bit,
+            Color::Black => self.black_pieces |= bit,
+        }
+        self.all_pieces |= bit;
+        self.tiles[square.index()] = Tile::Occupied { piece, color };
+        self.hash ^= ZOBRIST.pieces[color.index()][piece.index()][square.index()];
+        if piece == Piece::Pawn {
+            self.pawn_hash ^= ZOBRIST.pieces[color.index()][piece.index()][square.index()];
+        }
+        self.psqt += eval::psqt(piece, color, square);
+        self.phase += PHASE_WEIGHTS[piece.index()];
+        if let Some(nnue) = &mut self.nnue {
+            nnue.add_piece(piece, color, square);
+        }
+    }
+
+    /// Removes a piece from a square, updating the bitboards, tiles, hash and evaluation terms.
+    pub fn remove_piece(&mut self, square: Square, piece: Piece, color: Color) {
+        let bit = !BitBoard::from(square);
+        *self.pieces_mut(piece, color) &= bit;
+        match color {
+            Color::White => self.white_pieces &= bit,
+            Color::Black => self.black_pieces &= bit,
+        }
+        self.all_pieces &= bit;
+        self.tiles[square.index()] = Tile::Empty;
+        self.hash ^= ZOBRIST.pieces[color.index()][piece.index()][square.index()];
+        if piece == Piece::Pawn {
+            self.pawn_hash ^= ZOBRIST.pieces[color.index()][piece.index()][square.index()];
+        }
+        self.psqt -= eval::psqt(piece, color, square);
+        self.phase -= PHASE_WEIGHTS[piece.index()];
+        if let Some(nnue) = &mut self.nnue {
+            nnue.remove_piece(piece, color, square);
+        }
+    }
+
+    /// Returns true if the move captures a piece, including en passant.
+    pub fn is_capture(&self, mv: Move) -> bool {
+        self.captured_piece(mv).is_some()
+    }
+
+    /// Returns the piece type the move captures, if any.
+    pub fn captured_piece(&self, mv: Move) -> Option<Piece> {
+        if self.is_en_passant(mv) {
+            return Some(Piece::Pawn);
+        }
+        // In Chess960 castling moves land on the own rook, which is not a capture.
+        match self.piece_at(mv.to) {
+            Some((piece, color)) if color != self.side_to_move => Some(piece),
+            _ => None,
+        }
+    }
+
+    /// Returns the piece type standing on the from square of the move.
+    pub fn moved_piece(&self, mv: Move) -> Piece {
+        self.piece_at(mv.from).expect("no piece on the from square of the move").0
+    }
+
+    /// Returns true if the move is an en passant capture.
+    pub fn is_en_passant(&self, mv: Move) -> bool {
+        self.en_passant == Some(mv.to) && matches!(self.piece_at(mv.from), Some((Piece::Pawn, _)))
+    }
+
+    /// Returns true if castling needs Chess960 rules: a side that may still castle has its king
+    /// off the e-file home square or the rook off its standard corner.
+    pub fn requires_chess960(&self) -> bool {
+        (0..4).any(|i| {
+            let (color, king_home) = if i < 2 { (Color::White, Square::E1) } else { (Color::Black, Square::E8) };
+            self.castling_rights.contains(CastlingRights(1 << i))
+                && (self.castling_rooks[i] != STANDARD_CASTLING_ROOKS[i] || !self.pieces(Piece::King, color).contains(king_home))
+        })
+    }
+
+    /// Returns true if the move is a castling move, i.e. the king moves two files, or in Chess960
+    /// the king captures its own rook.
+    pub fn is_castling(&self, mv: Move) -> bool {
+        match self.piece_at(mv.from) {
+            Some((Piece::King, color)) if self.chess960 => self.piece_at(mv.to) == Some((Piece::Rook, color)),
+            Some((Piece::King, _)) => mv.from.file().abs_diff(mv.to.file()) == 2,
+            _ => false,
+        }
+    }
+
+    /// For a castling move, the destination square of the king and the start and destination
+    /// squares of the rook. Works before and after the move is made.
+    pub fn castling_squares(&self, mv: Move) -> (Square, Square, Square) {
+        let kingside = mv.to.file() > mv.from.file();
+        let rank = mv.from.rank();
+        let king_to = Square::from_rank_file(rank, if kingside { 6 } else { 2 });
+        let rook_to = Square::from_rank_file(rank, if kingside { 5 } else { 3 });
+        let color = if rank == 0 { Color::White } else { Color::Black };
+        let rook_from = if self.chess960 { mv.to } else { self.castling_rooks[CastlingRights::index(color, kingside)] };
+        (king_to, rook_from, rook_to)
+    }
+
+    /// Returns true if the move, played by `us` with `rights` before it, was a castling move.
+    /// Unlike `is_castling` this works after the move is made.
+    fn was_castling(&self, mv: Move, us: Color, rights: CastlingRights) -> bool {
+        if self.chess960 {
+            // Only castling moves a piece onto a castling rook that still has its right.
+            [true, false].into_iter().any(|kingside| {
+                rights.contains(CastlingRights::of(us, kingside)) && self.castling_rooks[CastlingRights::index(us, kingside)] == mv.to
+            })
+        } else {
+            self.pieces(Piece::King, us).contains(mv.to) && mv.from.file().abs_diff(mv.to.file()) == 2
+        }
+    }
+
+    /// Returns true if the move is neither a capture nor a promotion.
+    pub fn is_quiet(&self, mv: Move) -> bool {
+        mv.promotion.is_none() && !self.is_capture(mv)
+    }
+
+    /// Returns true if a piece of the given color attacks the square.
+    pub fn is_square_attacked(&self, square: Square, by: Color) -> bool {
+        self.is_square_attacked_through(square, by, self.all_pieces)
+    }
+
+    /// Returns true if a piece of the given color attacks the square when sliding pieces are
+    /// blocked by `occupancy` instead of the pieces on the board.
+    pub fn is_square_attacked_through(&self, square: Square, by: Color, occupancy: BitBoard) -> bool {
+        let queens = self.pieces(Piece::Queen, by);
+        !(knight_attacks(square) & self.pieces(Piece::Knight, by)).is_empty()
+            || !(king_attacks(square) & self.pieces(Piece::King, by)).is_empty()
+            || !(pawn_attacks(square, by.opposite()) & self.pieces(Piece::Pawn, by)).is_empty()
+            || !(bishop_attacks(square, occupancy) & (self.pieces(Piece::Bishop, by) | queens)).is_empty()
+            || !(rook_attacks(square, occupancy) & (self.pieces(Piece::Rook, by) | queens)).is_empty()
+    }
+
+    /// Returns true if the side to move is in check.
+    pub fn in_check(&self) -> bool {
+        self.is_square_attacked(self.king_square(self.side_to_move), self.side_to_move.opposite())
+    }
+
+    /// Returns true if the move checks the opponent's king, directly or by uncovering a sliding
+    /// piece. Works out the pieces after the move instead of making it, so it is cheap enough to
+    /// call before deciding whether to search a move at all.
+    pub fn gives_check(&self, mv: Move) -> bool {
+        let us = self.side_to_move;
+        let king = self.king_square(us.opposite());
+        let mut pieces = Piece::ALL.map(|piece| self.pieces(piece, us));
+        let (from, to) = (BitBoard::from(mv.from), BitBoard::from(mv.to));
+        let mut occupancy = self.all_pieces;
+        if self.is_castling(mv) {
+            let (king_to, rook_from, rook_to) = self.castling_squares(mv);
+            occupancy = occupancy & !from & !BitBoard::from(rook_from) | BitBoard::from(king_to) | BitBoard::from(rook_to);
+            let rooks = &mut pieces[Piece::Rook.index()];
+            *rooks = *rooks & !BitBoard::from(rook_from) | BitBoard::from(rook_to);
+        } else {
+            let moved = self.moved_piece(mv);
+            if self.is_en_passant(mv) {
+                occupancy &= !BitBoard::from(Square::from_rank_file(mv.from.rank(), mv.to.file()));
+            }
+            occupancy = occupancy & !from | to;
+            pieces[moved.index()] &= !from;
+            pieces[mv.promotion.unwrap_or(moved).index()] |= to;
+        }
+        let [pawns, rooks, knights, bishops, queens, _] = pieces;
+        !(knight_attacks(king) & knights).is_empty()
+            || !(pawn_attacks(king, us.opposite()) & pawns).is_empty()
+            || !(bishop_attacks(king, occupancy) & (bishops | queens)).is_empty()
+            || !(rook_attacks(king, occupancy) & (rooks | queens)).is_empty()
+    }
+
+    /// Plays a move on the board. The move must be legal in the current position.
+    pub fn make_move(&mut self, mv: Move) {
+        let us = self.side_to_move;
+        let them = us.opposite();
+        let piece = self.moved_piece(mv);
+        let is_en_passant = self.is_en_passant(mv);
+        let is_castling = self.is_castling(mv);
+        let captured = self.captured_piece(mv);
+
+        self.history.push(StateInfo {
+            mv: Some(mv),
+            captured,
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            hash: self.hash,
+        });
+
+        if let Some(square) = self.en_passant.take() {
+            self.hash ^= ZOBRIST.en_passant[square.file() as usize];
+        }
+
+        if let Some(captured) = captured {
+            let capture_square = if is_en_passant {
+                Square::from_rank_file(mv.from.rank(), mv.to.file())
+            } else {
+                mv.to
+            };
+            self.remove_piece(capture_square, captured, them);
+        }
+
+        if is_castling {
+            // The king and rook may swap squares in Chess960, so both leave before either lands.
+            let (king_to, rook_from, rook_to) = self.castling_squares(mv);
+            self.remove_piece(mv.from, Piece::King, us);
+            self.remove_piece(rook_from, Piece::Rook, us);
+            self.put_piece(king_to, Piece::King, us);
+            self.put_piece(rook_to, Piece::Rook, us);
+        } else {
+            self.remove_piece(mv.from, piece, us);
+            self.put_piece(mv.to, mv.promotion.unwrap_or(piece), us);
+        }
+
+        // Rights are lost when the king moves or a castling rook moves or is captured.
+        self.hash ^= ZOBRIST.castling[self.castling_rights.0 as usize];
+        if piece == Piece::King {
+            self.castling_rights.remove(CastlingRights::both(us));
+        }
+        for (i, &rook) in self.castling_rooks.iter().enumerate() {
+            if rook == mv.from || rook == mv.to {
+                self.castling_rights.remove(CastlingRights(1 << i));
+            }
+        }
+        self.hash ^= ZOBRIST.castling[self.castling_rights.0 as usize];
+
+        // Only record the en passant square if it can actually be captured on, so that
+        // transpositions hash equally regardless of the last move.
+        if piece == Piece::Pawn && mv.from.rank().abs_diff(mv.to.rank()) == 2 {
+            let skipped = Square::from_rank_file((mv.from.rank() + mv.to.rank()) / 2, mv.from.file());
+            if !(pawn_attacks(skipped, us) & self.pieces(Piece::Pawn, them)).is_empty() {
+                self.en_passant = Some(skipped);
+                self.hash ^= ZOBRIST.en_passant[skipped.file() as usize];
+            }
+        }
+
+        if piece == Piece::Pawn || captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if us == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = them;
+        self.hash ^= ZOBRIST.side;
     }
-    
-        
 
+    /// Takes back the last move played with `make_move`.
+    pub fn unmake_move(&mut self) {
+        let state = self.history.pop().expect("no move to unmake");
+        let mv = state.mv.expect("the last move was a null move");
+        let them = self.side_to_move;
+        let us = them.opposite();
+        self.side_to_move = us;
+        if us == Color::Black {
+            self.fullmove_number -= 1;
+        }
+
+        if self.was_castling(mv, us, state.castling_rights) {
+            let (king_to, rook_from, rook_to) = self.castling_squares(mv);
+            self.remove_piece(king_to, Piece::King, us);
+            self.remove_piece(rook_to, Piece::Rook, us);
+            self.put_piece(mv.from, Piece::King, us);
+            self.put_piece(rook_from, Piece::Rook, us);
+        } else {
+            let (piece, _) = self.piece_at(mv.to).expect("no piece on the to square of the move");
+            self.remove_piece(mv.to, piece, us);
+            let moved = if mv.promotion.is_some() { Piece::Pawn } else { piece };
+            self.put_piece(mv.from, moved, us);
+
+            if let Some(captured) = state.captured {
+                let is_en_passant = moved == Piece::Pawn && state.en_passant == Some(mv.to);
+                let capture_square = if is_en_passant {
+                    Square::from_rank_file(mv.from.rank(), mv.to.file())
+                } else {
+                    mv.to
+                };
+                self.put_piece(capture_square, captured, them);
+            }
+        }
+
+        self.castling_rights = state.castling_rights;
+        self.en_passant = state.en_passant;
+        self.halfmove_clock = state.halfmove_clock;
+        self.hash = state.hash;
+    }
+
+    /// Passes the turn to the other side without moving a piece. Used by null move pruning.
+    pub fn make_null_move(&mut self) {
+        self.history.push(StateInfo {
+            mv: None,
+            captured: None,
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            hash: self.hash,
+        });
+        if let Some(square) = self.en_passant.take() {
+            self.hash ^= ZOBRIST.en_passant[square.file() as usize];
+        }
+        // A null move is not a real move, so repetitions across it must not count.
+        self.halfmove_clock = 0;
+        self.side_to_move = self.side_to_move.opposite();
+        self.hash ^= ZOBRIST.side;
+    }
+
+    /// Takes back a null move played with `make_null_move`.
+    pub fn unmake_null_move(&mut self) {
+        let state = self.history.pop().expect("no null move to unmake");
+        debug_assert!(state.mv.is_none(), "the last move was not a null move");
+        self.side_to_move = self.side_to_move.opposite();
+        self.en_passant = state.en_passant;
+        self.halfmove_clock = state.halfmove_clock;
+        self.hash = state.hash;
+    }
+
+    /// Returns true if the last move played was a null move.
+    pub fn last_move_was_null(&self) -> bool {
+        self.history.last().is_some_and(|state| state.mv.is_none())
+    }
+
+    /// Returns the position flipped vertically with the colors swapped, e.g. a white knight on g1
+    /// becomes a black knight on g8 and the other side is to move. The history is not kept.
+    pub fn mirrored(&self) -> Board {
+        let mut board = Board::empty();
+        for square in self.all_pieces.iter_squares() {
+            let (piece, color) = self.piece_at(square).expect("occupied squares hold a piece");
+            *board.pieces_mut(piece, color.opposite()) |= BitBoard::from(square.flip());
+        }
+        let rights = self.castling_rights.0;
+        board.castling_rights = CastlingRights((rights & 0b0011) << 2 | rights >> 2);
+        let rooks = self.castling_rooks.map(Square::flip);
+        board.castling_rooks = [rooks[2], rooks[3], rooks[0], rooks[1]];
+        board.chess960 = self.chess960;
+        board.side_to_move = self.side_to_move.opposite();
+        board.en_passant = self.en_passant.map(Square::flip);
+        board.halfmove_clock = self.halfmove_clock;
+        board.fullmove_number = self.fullmove_number;
+        board.nnue = self.nnue.clone();
+        board.update();
+        board
+    }
+
+    /// Returns true if the side has any pieces other than pawns and the king. Positions where this
+    /// is false are prone to zugzwang.
+    pub fn has_non_pawn_material(&self, color: Color) -> bool {
+        let pieces = self.pieces(Piece::Knight, color) | self.pieces(Piece::Bishop, color) | self.pieces(Piece::Rook, color) | self.pieces(Piece::Queen, color);
+        !pieces.is_empty()
+    }
+
+    /// Returns true if the current position occurred before since the last irreversible move.
+    pub fn is_repetition(&self) -> bool {
+        self.history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .skip(1)
+            .step_by(2)
+            .any(|state| state.hash == self.hash)
+    }
+
+    /// Returns true if neither side has enough material left to deliver mate.
+    pub fn is_insufficient_material(&self) -> bool {
+        let heavy = self.white_pawns | self.black_pawns | self.white_rooks | self.black_rooks | self.white_queens | self.black_queens;
+        let minors = self.white_knights | self.black_knights | self.white_bishops | self.black_bishops;
+        heavy.is_empty() && minors.count() <= 1
+    }
+
+    /// Returns true if the position is drawn by the fifty move rule, repetition or material.
+    pub fn is_draw(&self) -> bool {
+        self.halfmove_clock >= 100 || self.is_repetition() || self.is_insufficient_material()
+    }
+
+    /// Returns true if the current position occurred at least twice before, which ends the game
+    /// under the threefold repetition rule.
+    pub fn is_threefold_repetition(&self) -> bool {
+        let repetitions = self
+            .history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .skip(1)
+            .step_by(2)
+            .filter(|state| state.hash == self.hash)
+            .count();
+        repetitions >= 2
+    }
+
+    /// Returns how the game ended in this position, or None if it goes on. Unlike `is_draw`, which
+    /// the search uses, a single repetition doesn't end the game.
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        if generate_legal_moves(self).is_empty() {
+            return Some(if self.in_check() {
+                GameOutcome::Checkmate(self.side_to_move.opposite())
+            } else {
+                GameOutcome::Stalemate
+            });
+        }
+        if self.is_insufficient_material() {
+            Some(GameOutcome::InsufficientMaterial)
+        } else if self.halfmove_clock >= 100 {
+            Some(GameOutcome::FiftyMoveRule)
+        } else if self.is_threefold_repetition() {
+            Some(GameOutcome::Repetition)
+        } else {
+            None
+        }
+    }
+}
+
+/// How a game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameOutcome {
+    /// The given side delivered mate.
+    Checkmate(Color),
+    Stalemate,
+    InsufficientMaterial,
+    FiftyMoveRule,
+    Repetition,
+}
+
+impl GameOutcome {
+    /// The result as written in PGN: "1-0", "0-1" or "1/2-1/2".
+    pub fn result(self) -> &'static str {
+        match self {
+            GameOutcome::Checkmate(Color::White) => "1-0",
+            GameOutcome::Checkmate(Color::Black) => "0-1",
+            _ => "1/2-1/2",
+        }
+    }
+
+    /// A short description of why the game ended.
+    pub fn reason(self) -> &'static str {
+        match self {
+            GameOutcome::Checkmate(Color::White) => "White mates",
+            GameOutcome::Checkmate(Color::Black) => "Black mates",
+            GameOutcome::Stalemate => "Stalemate",
+            GameOutcome::InsufficientMaterial => "Insufficient material",
+            GameOutcome::FiftyMoveRule => "Fifty move rule",
+            GameOutcome::Repetition => "Threefold repetition",
+        }
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
 }
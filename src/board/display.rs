@@ -0,0 +1,72 @@
+use std::fmt;
+
+use super::{
+    bitboard::BitBoard,
+    board::Board,
+    piece::Color,
+    square::Square,
+};
+
+/// A printable diagram of a position, made with `Board::diagram`.
+///
+/// Each square is three characters wide: a piece as its FEN letter, or its Unicode symbol with
+/// `unicode`, and an empty square as a dot. Highlighted squares are put in brackets, so e.g. the
+/// attacks of a piece can be shown on top of the position. Below the board follow the side to
+/// move, the castling rights, the en passant square and the FEN.
+#[derive(Clone, Copy, Debug)]
+pub struct Diagram<'a> {
+    pub board: &'a Board,
+    /// The squares to highlight.
+    pub highlight: BitBoard,
+    /// Whether pieces are drawn as Unicode chess symbols instead of letters.
+    pub unicode: bool,
+}
+
+impl Board {
+    /// Returns a diagram of the position with nothing highlighted. `Board` implements `Display`
+    /// with this diagram; the alternate flag, as in `{board:#}`, selects Unicode symbols.
+    pub fn diagram(&self) -> Diagram<'_> {
+        Diagram { board: self, highlight: BitBoard::EMPTY, unicode: false }
+    }
+}
+
+impl fmt::Display for Diagram<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let board = self.board;
+        writeln!(f, "  +------------------------+")?;
+        for rank in (0..8).rev() {
+            write!(f, "{} |", rank + 1)?;
+            for file in 0..8 {
+                let square = Square::from_rank_file(rank, file);
+                let c = match board.piece_at(square) {
+                    Some((piece, color)) if self.unicode => piece.to_glyph(color),
+                    Some((piece, color)) => piece.to_char(color),
+                    None => '.',
+                };
+                if self.highlight.contains(square) {
+                    write!(f, "[{c}]")?;
+                } else {
+                    write!(f, " {c} ")?;
+                }
+            }
+            writeln!(f, "|")?;
+        }
+        writeln!(f, "  +------------------------+")?;
+        writeln!(f, "    a  b  c  d  e  f  g  h")?;
+
+        let side = if board.side_to_move == Color::White { "white" } else { "black" };
+        // The castling and en passant fields are taken from the FEN, so they're written the same way.
+        let fen = board.to_fen();
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        writeln!(f, "Side to move: {side}")?;
+        writeln!(f, "Castling: {}", fields[2])?;
+        writeln!(f, "En passant: {}", fields[3])?;
+        write!(f, "FEN: {fen}")
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Diagram { unicode: f.alternate(), ..self.diagram() }.fmt(f)
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt;
+
+use super::{
+    board::{Board, CastlingRights},
+    piece::{Color, Piece},
+    square::Square,
+};
+
+/// The FEN of the standard starting position.
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    MissingField(&'static str),
+    InvalidPiecePlacement(String),
+    InvalidSideToMove(String),
+    InvalidCastlingRights(String),
+    InvalidEnPassant(String),
+    InvalidMoveCounter(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "FEN is missing the {field} field"),
+            FenError::InvalidPiecePlacement(s) => write!(f, "invalid piece placement: {s}"),
+            FenError::InvalidSideToMove(s) => write!(f, "invalid side to move: {s}"),
+            FenError::InvalidCastlingRights(s) => write!(f, "invalid castling rights: {s}"),
+            FenError::InvalidEnPassant(s) => write!(f, "invalid en passant square: {s}"),
+            FenError::InvalidMoveCounter(s) => write!(f, "invalid move counter: {s}"),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl Board {
+    /// Parses a position from Forsyth-Edwards Notation. The move counters may be omitted, as is
+    /// common in EPD files.
+    ///
+    /// Besides "KQkq", the castling field may name the files of the castling rooks as in
+    /// Shredder-FEN ("HAha") or X-FEN, which mixes both. In X-FEN "K" and "Q" stand for the rook
+    /// in the corner, or else the outermost rook on that side of the king. Positions whose kings
+    /// or castling rooks aren't on their standard squares are Chess960 positions.
+    ///
+    /// Only the syntax is checked, so e.g. a position without kings parses fine. Callers loading
+    /// positions from outside the engine should follow up with `Board::validate`.
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        let mut fields = fen.split_whitespace();
+        let placement = fields.next().ok_or(FenError::MissingField("piece placement"))?;
+        let side = fields.next().ok_or(FenError::MissingField("side to move"))?;
+        let castling = fields.next().ok_or(FenError::MissingField("castling rights"))?;
+        let en_passant = fields.next().ok_or(FenError::MissingField("en passant"))?;
+        let halfmove = fields.next().unwrap_or("0");
+        let fullmove = fields.next().unwrap_or("1");
+
+        let mut board = Board::empty();
+
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::InvalidPiecePlacement(placement.to_string()));
+        }
+        for (i, rank_str) in ranks.iter().enumerate() {
+            let rank = 7 - i as u8;
+            let mut file = 0u8;
+            for c in rank_str.chars() {
+                if let Some(skip) = c.to_digit(10) {
+                    // Checking the run before adding keeps long runs of digits from overflowing.
+                    if !(1..=8).contains(&skip) || file + skip as u8 > 8 {
+                        return Err(FenError::InvalidPiecePlacement(placement.to_string()));
+                    }
+                    file += skip as u8;
+                } else if let Some((piece, color)) = Piece::from_char(c) {
+                    if file >= 8 {
+                        return Err(FenError::InvalidPiecePlacement(placement.to_string()));
+                    }
+                    *board.pieces_mut(piece, color) |= Square::from_rank_file(rank, file).into();
+                    file += 1;
+                } else {
+                    return Err(FenError::InvalidPiecePlacement(placement.to_string()));
+                }
+            }
+            if file != 8 {
+                return Err(FenError::InvalidPiecePlacement(placement.to_string()));
+            }
+        }
+
+        board.side_to_move = match side {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(FenError::InvalidSideToMove(side.to_string())),
+        };
+
+        if castling != "-" {
+            for c in castling.chars() {
+                let invalid = || FenError::InvalidCastlingRights(castling.to_string());
+                let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+                let rank = if color == Color::White { 0 } else { 7 };
+                let king_file = board.pieces(Piece::King, color).iter_squares().next().filter(|king| king.rank() == rank).map(Square::file);
+                let is_rook = |file: u8| board.pieces(Piece::Rook, color).contains(Square::from_rank_file(rank, file));
+                let (kingside, rook_file) = match c.to_ascii_lowercase() {
+                    'k' => {
+                        let outermost = king_file.and_then(|king| (king + 1..8).rev().find(|&file| is_rook(file)));
+                        (true, if is_rook(7) { 7 } else { outermost.unwrap_or(7) })
+                    }
+                    'q' => {
+                        let outermost = king_file.and_then(|king| (0..king).find(|&file| is_rook(file)));
+                        (false, if is_rook(0) { 0 } else { outermost.unwrap_or(0) })
+                    }
+                    file @ 'a'..='h' => {
+                        let file = file as u8 - b'a';
+                        (file > king_file.ok_or_else(invalid)?, file)
+                    }
+                    _ => return Err(invalid()),
+                };
+                board.castling_rights.insert(CastlingRights::of(color, kingside));
+                board.castling_rooks[CastlingRights::index(color, kingside)] = Square::from_rank_file(rank, rook_file);
+            }
+            board.chess960 = board.requires_chess960();
+        }
+
+        board.en_passant = match en_passant {
+            "-" => None,
+            square => Some(square.parse().map_err(|_| FenError::InvalidEnPassant(square.to_string()))?),
+        };
+
+        board.halfmove_clock = halfmove.parse().map_err(|_| FenError::InvalidMoveCounter(halfmove.to_string()))?;
+        board.fullmove_number = fullmove.parse().map_err(|_| FenError::InvalidMoveCounter(fullmove.to_string()))?;
+
+        board.update();
+        Ok(board)
+    }
+
+    /// Formats the position as Forsyth-Edwards Notation. Chess960 positions are written as
+    /// Shredder-FEN, with the files of the castling rooks.
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                match self.piece_at(Square::from_rank_file(rank, file)) {
+                    Some((piece, color)) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece.to_char(color));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(if self.side_to_move == Color::White { " w " } else { " b " });
+
+        let mut castling = String::new();
+        for (i, c) in ['K', 'Q', 'k', 'q'].into_iter().enumerate() {
+            if !self.castling_rights.contains(CastlingRights(1 << i)) {
+                continue;
+            }
+            if self.chess960 {
+                let file = (b'a' + self.castling_rooks[i].file()) as char;
+                castling.push(if i < 2 { file.to_ascii_uppercase() } else { file });
+            } else {
+                castling.push(c);
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        fen.push_str(&castling);
+
+        match self.en_passant {
+            Some(square) => fen.push_str(&format!(" {square}")),
+            None => fen.push_str(" -"),
+        }
+        fen.push_str(&format!(" {} {}", self.halfmove_clock, self.fullmove_number));
+        fen
+    }
+}
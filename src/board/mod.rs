@@ -1,8 +1,19 @@
 pub mod piece;
+#[doc(hidden)]
 pub mod tile;
+#[doc(hidden)]
 pub mod utils;
 pub mod bitboard;
+#[allow(clippy::module_inception)]
 pub mod board;
 pub mod square;
+#[doc(hidden)]
 pub mod tables;
-pub mod movegen;
\ No newline at end of file
+pub mod movegen;
+pub mod moves;
+pub mod fen;
+pub mod san;
+#[doc(hidden)]
+pub mod zobrist;
+pub mod validate;
+pub mod display;
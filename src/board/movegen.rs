@@ -1,12 +1,18 @@
-use core::time;
-use std::time::Instant;
-
-use super::{bitboard::{self, BitBoard}, board::{self, Board}, piece::Piece, square::{self, Square}, tables::{PrecomputedMasks, SlidingAttackTable}, utils::{pdep, pext, print_bitboard}};
+use super::{bitboard::BitBoard, board::{Board, CastlingRights}, moves::Move, piece::{Color, Piece}, square::Square, tables::{attack_tables, PrecomputedMasks, SlidingAttackTable}, utils::{pdep, pext}};
 
 
 
+/// Returns the squares a piece on the given square attacks, given the occupancy of the board.
+/// Pawn attacks depend on the color of the pawn, so use `pawn_attacks` for those instead.
 pub fn fetch_psuedo_legal_moves(piece: Piece, square: Square, occupation: BitBoard) -> BitBoard {
-    BitBoard(1)
+    match piece {
+        Piece::Pawn => BitBoard::EMPTY,
+        Piece::Knight => knight_attacks(square),
+        Piece::Bishop => bishop_attacks(square, occupation),
+        Piece::Rook => rook_attacks(square, occupation),
+        Piece::Queen => queen_attacks(square, occupation),
+        Piece::King => king_attacks(square),
+    }
 }
 
 /// This function generates the attack mask for a bishop on a given square.
@@ -35,13 +41,17 @@ pub fn fetch_psuedo_legal_moves(piece: Piece, square: Square, occupation: BitBoa
 /// # Example
 ///
 /// ```
+/// use knightbot::board::{board::Board, movegen::get_attack_mask_for_bishop, square::Square};
+/// use knightbot::board::tables::{generate_rank_attack_table, precompute_masks};
+/// use knightbot::board::utils::print_bitboard;
+///
 /// let board = Board::new();
 /// let rank_attacks = generate_rank_attack_table();
 /// let precomputed_masks = precompute_masks();
 /// let square = Square::B6;
 /// let full_board = board.all_pieces;
-/// let attack_mask = get_attack_mask_for_bishop(square, full_board, rank_attacks, precomputed_masks);
-/// print_bitboard(attack_mask);
+/// let attack_mask = get_attack_mask_for_bishop(square, full_board, &rank_attacks, &precomputed_masks);
+/// print_bitboard(*attack_mask);
 /// ```
 pub fn get_attack_mask_for_bishop(square: Square, occupancy: BitBoard, rank_attacks: &SlidingAttackTable, precomputed_masks: &PrecomputedMasks) -> BitBoard {
     let mut total_mask = 0u64;
@@ -60,20 +70,13 @@ pub fn get_attack_mask_for_bishop(square: Square, occupancy: BitBoard, rank_atta
 pub fn get_attack_mask_for_rook(square: Square, occupancy: BitBoard, rank_attacks: &SlidingAttackTable, precomputed_masks: &PrecomputedMasks) -> BitBoard {
     let mut total_mask = 0u64;
     let rook_masks = precomputed_masks.rook_masks.get(&square).unwrap();
-    let mut i = 0 as u8;
-    let mut index = 0;
-    for mask in rook_masks {
-        if i == 0 {
-            index = square.rank();
-        }
-        else {
-            index = square.file();
-        }
+    for (i, mask) in rook_masks.iter().enumerate() {
+        // The first mask is the file, on which the rook sits at index rank. The second is the rank.
+        let index = if i == 0 { square.rank() } else { square.file() };
         let extracted = pext(*occupancy, *mask);
         let attack_mask = *rank_attacks.get(index, extracted).unwrap();
         let deposited = pdep(attack_mask, *mask);
         total_mask |= deposited;
-        i += 1;
     }
     BitBoard(total_mask)
 }
@@ -83,4 +86,252 @@ pub fn get_attack_mask_for_queen(square: Square, occupancy: BitBoard, rank_attac
     let rook_mask = get_attack_mask_for_rook(square, occupancy, rank_attacks, precomputed_masks);
 
     BitBoard(diagonal_mask.0 | rook_mask.0)
-}
\ No newline at end of file
+}
+
+/// Bishop attacks using the global attack tables.
+pub fn bishop_attacks(square: Square, occupancy: BitBoard) -> BitBoard {
+    let tables = attack_tables();
+    get_attack_mask_for_bishop(square, occupancy, &tables.rook_attacks, &tables.precomputed_masks)
+}
+
+/// Rook attacks using the global attack tables.
+pub fn rook_attacks(square: Square, occupancy: BitBoard) -> BitBoard {
+    let tables = attack_tables();
+    get_attack_mask_for_rook(square, occupancy, &tables.rook_attacks, &tables.precomputed_masks)
+}
+
+/// Queen attacks using the global attack tables.
+pub fn queen_attacks(square: Square, occupancy: BitBoard) -> BitBoard {
+    bishop_attacks(square, occupancy) | rook_attacks(square, occupancy)
+}
+
+pub fn knight_attacks(square: Square) -> BitBoard {
+    BitBoard(attack_tables().knight_attacks.get(square))
+}
+
+pub fn king_attacks(square: Square) -> BitBoard {
+    BitBoard(attack_tables().king_attacks.get(square))
+}
+
+/// The squares a pawn of the given color on the given square attacks.
+pub fn pawn_attacks(square: Square, color: Color) -> BitBoard {
+    BitBoard(attack_tables().pawn_attacks.get(square, color))
+}
+
+/// Returns every piece of either color attacking the square, given the occupancy of the board.
+/// The occupancy is passed separately so callers can remove pieces to reveal x-ray attackers.
+pub fn attackers_to(board: &Board, square: Square, occupancy: BitBoard) -> BitBoard {
+    let bishops = board.white_bishops | board.black_bishops | board.white_queens | board.black_queens;
+    let rooks = board.white_rooks | board.black_rooks | board.white_queens | board.black_queens;
+    (knight_attacks(square) & (board.white_knights | board.black_knights))
+        | (king_attacks(square) & (board.white_king | board.black_king))
+        | (pawn_attacks(square, Color::Black) & board.white_pawns)
+        | (pawn_attacks(square, Color::White) & board.black_pawns)
+        | (bishop_attacks(square, occupancy) & bishops)
+        | (rook_attacks(square, occupancy) & rooks)
+}
+
+/// Pushes a move for every square in `targets`, moving from the square `offset` behind it.
+fn push_pawn_moves(moves: &mut Vec<Move>, targets: BitBoard, offset: i8) {
+    let promotion_ranks = BitBoard::RANK_1 | BitBoard::RANK_8;
+    for to in targets.iter_squares() {
+        let from = Square::from_index((to as i8 - offset) as u8);
+        if promotion_ranks.contains(to) {
+            for piece in [Piece::Queen, Piece::Knight, Piece::Rook, Piece::Bishop] {
+                moves.push(Move::with_promotion(from, to, piece));
+            }
+        } else {
+            moves.push(Move::new(from, to));
+        }
+    }
+}
+
+/// Generates all pseudo-legal moves for the side to move, i.e. moves that may leave the own king in check.
+/// Castling moves are only generated when they are fully legal.
+pub fn generate_pseudo_legal_moves(board: &Board) -> Vec<Move> {
+    let mut moves = Vec::with_capacity(64);
+    let us = board.side_to_move;
+    let them = us.opposite();
+    let own = board.color_pieces(us);
+    let enemies = board.color_pieces(them);
+    let empty = !board.all_pieces;
+
+    // Pawns are generated set-wise by shifting the whole pawn bitboard.
+    let pawns = board.pieces(Piece::Pawn, us);
+    let ep_target = board.en_passant.map(BitBoard::from).unwrap_or(BitBoard::EMPTY);
+    let capture_targets = enemies | ep_target;
+    let (single, double, left, right, forward) = match us {
+        Color::White => {
+            let single = (pawns << 8) & empty;
+            let double = ((single & BitBoard::RANK_3) << 8) & empty;
+            let left = ((pawns & !BitBoard::FILE_A) << 7) & capture_targets;
+            let right = ((pawns & !BitBoard::FILE_H) << 9) & capture_targets;
+            (single, double, left, right, 8i8)
+        }
+        Color::Black => {
+            let single = (pawns >> 8) & empty;
+            let double = ((single & BitBoard::RANK_6) >> 8) & empty;
+            let left = ((pawns & !BitBoard::FILE_A) >> 9) & capture_targets;
+            let right = ((pawns & !BitBoard::FILE_H) >> 7) & capture_targets;
+            (single, double, left, right, -8i8)
+        }
+    };
+    push_pawn_moves(&mut moves, left, forward - 1);
+    push_pawn_moves(&mut moves, right, forward + 1);
+    push_pawn_moves(&mut moves, single, forward);
+    push_pawn_moves(&mut moves, double, 2 * forward);
+
+    for piece in [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen, Piece::King] {
+        for from in board.pieces(piece, us).iter_squares() {
+            let targets = fetch_psuedo_legal_moves(piece, from, board.all_pieces) & !own;
+            for to in targets.iter_squares() {
+                moves.push(Move::new(from, to));
+            }
+        }
+    }
+
+    generate_castling_moves(board, &mut moves);
+    moves
+}
+
+/// The squares of a rank from `a` to `b`, both included.
+fn rank_span(a: Square, b: Square) -> BitBoard {
+    let (low, high) = ((a as u8).min(b as u8), (a as u8).max(b as u8));
+    BitBoard((u64::MAX >> (63 - high)) & (u64::MAX << low))
+}
+
+/// Generates the castling moves of the side to move. The king ends up on the g or c file and the
+/// rook next to it on the f or d file, wherever they started, which in standard chess means the
+/// king on e1 or e8 and the rooks in the corners.
+fn generate_castling_moves(board: &Board, moves: &mut Vec<Move>) {
+    let us = board.side_to_move;
+    let them = us.opposite();
+    if !board.castling_rights.contains(CastlingRights::of(us, true)) && !board.castling_rights.contains(CastlingRights::of(us, false)) {
+        return;
+    }
+    let king_from = board.king_square(us);
+    let home = if us == Color::White { Square::E1 } else { Square::E8 };
+    if (!board.chess960 && king_from != home) || board.is_square_attacked(king_from, them) {
+        return;
+    }
+    for kingside in [true, false] {
+        if !board.castling_rights.contains(CastlingRights::of(us, kingside)) {
+            continue;
+        }
+        let rook_from = board.castling_rooks[CastlingRights::index(us, kingside)];
+        if !board.pieces(Piece::Rook, us).contains(rook_from) || rook_from.rank() != king_from.rank() {
+            continue;
+        }
+        let rank = king_from.rank();
+        let king_to = Square::from_rank_file(rank, if kingside { 6 } else { 2 });
+        let rook_to = Square::from_rank_file(rank, if kingside { 5 } else { 3 });
+        // Every square the king and rook pass or land on must be empty apart from the two of them.
+        let others = board.all_pieces & !BitBoard::from(king_from) & !BitBoard::from(rook_from);
+        if !((rank_span(king_from, king_to) | rank_span(rook_from, rook_to)) & others).is_empty() {
+            continue;
+        }
+        // The king may not pass through or land on an attacked square. The rook is left out of the
+        // occupancy, since in Chess960 it may be shielding the king's destination.
+        if rank_span(king_from, king_to).iter_squares().any(|square| board.is_square_attacked_through(square, them, others)) {
+            continue;
+        }
+        moves.push(Move::new(king_from, if board.chess960 { rook_from } else { king_to }));
+    }
+}
+
+/// Returns true if the pseudo-legal move does not leave the own king in check.
+/// This works on the board without making the move, by recomputing the attacks on the king square
+/// with the occupancy the move would produce.
+pub fn is_legal(board: &Board, mv: Move) -> bool {
+    // Castling moves are only generated when they are legal.
+    if board.is_castling(mv) {
+        return true;
+    }
+    let us = board.side_to_move;
+    let them = us.opposite();
+    let piece = board.moved_piece(mv);
+    let from = BitBoard::from(mv.from);
+    let to = BitBoard::from(mv.to);
+
+    let mut captured = to;
+    if board.is_en_passant(mv) {
+        captured = BitBoard::from(Square::from_rank_file(mv.from.rank(), mv.to.file()));
+    }
+    let occupancy = (board.all_pieces & !from & !captured) | to;
+    let king = if piece == Piece::King { mv.to } else { board.king_square(us) };
+
+    let enemy = |piece: Piece| board.pieces(piece, them) & !captured;
+    let queens = enemy(Piece::Queen);
+    (knight_attacks(king) & enemy(Piece::Knight)).is_empty()
+        && (king_attacks(king) & enemy(Piece::King)).is_empty()
+        && (pawn_attacks(king, us) & enemy(Piece::Pawn)).is_empty()
+        && (bishop_attacks(king, occupancy) & (enemy(Piece::Bishop) | queens)).is_empty()
+        && (rook_attacks(king, occupancy) & (enemy(Piece::Rook) | queens)).is_empty()
+}
+
+/// Returns true if the move could be generated by `generate_pseudo_legal_moves` in this position.
+/// Used to validate moves from the transposition table and killer slots, which may come from other positions.
+pub fn is_pseudo_legal(board: &Board, mv: Move) -> bool {
+    let us = board.side_to_move;
+    let piece = match board.piece_at(mv.from) {
+        Some((piece, color)) if color == us => piece,
+        _ => return false,
+    };
+    if piece == Piece::King && board.is_castling(mv) {
+        let mut castling_moves = Vec::new();
+        generate_castling_moves(board, &mut castling_moves);
+        return castling_moves.contains(&mv);
+    }
+    if board.color_pieces(us).contains(mv.to) {
+        return false;
+    }
+    let promotion_rank = BitBoard::RANK_1 | BitBoard::RANK_8;
+    if piece != Piece::Pawn || !promotion_rank.contains(mv.to) {
+        if mv.promotion.is_some() {
+            return false;
+        }
+    } else if !matches!(mv.promotion, Some(Piece::Knight | Piece::Bishop | Piece::Rook | Piece::Queen)) {
+        return false;
+    }
+
+    match piece {
+        Piece::Pawn => {
+            let forward: i8 = if us == Color::White { 8 } else { -8 };
+            let start_rank = if us == Color::White { 1 } else { 6 };
+            let delta = mv.to as i8 - mv.from as i8;
+            if pawn_attacks(mv.from, us).contains(mv.to) {
+                board.color_pieces(us.opposite()).contains(mv.to) || board.en_passant == Some(mv.to)
+            } else if delta == forward {
+                !board.all_pieces.contains(mv.to)
+            } else if delta == 2 * forward && mv.from.rank() == start_rank {
+                let skipped = Square::from_index((mv.from as i8 + forward) as u8);
+                !board.all_pieces.contains(mv.to) && !board.all_pieces.contains(skipped)
+            } else {
+                false
+            }
+        }
+        _ => fetch_psuedo_legal_moves(piece, mv.from, board.all_pieces).contains(mv.to),
+    }
+}
+
+/// Generates all legal moves for the side to move.
+pub fn generate_legal_moves(board: &Board) -> Vec<Move> {
+    let mut moves = generate_pseudo_legal_moves(board);
+    moves.retain(|mv| is_legal(board, *mv));
+    moves
+}
+
+/// Counts the leaf nodes of the legal move tree to the given depth. Used to verify move generation.
+pub fn perft(board: &mut Board, depth: u32) -> u64 {
+    let moves = generate_legal_moves(board);
+    if depth <= 1 {
+        return if depth == 0 { 1 } else { moves.len() as u64 };
+    }
+    let mut nodes = 0;
+    for mv in moves {
+        board.make_move(mv);
+        nodes += perft(board, depth - 1);
+        board.unmake_move();
+    }
+    nodes
+}
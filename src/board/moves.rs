@@ -0,0 +1,86 @@
+use std::{fmt, str::FromStr};
+
+use super::{piece::Piece, square::Square};
+
+/// A move from one square to another. Castling is encoded as the king moving two squares, and
+/// whether a move is a capture or en passant is looked up on the board it is played on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<Piece>,
+}
+
+impl Move {
+    pub fn new(from: Square, to: Square) -> Self {
+        Move { from, to, promotion: None }
+    }
+
+    pub fn with_promotion(from: Square, to: Square, promotion: Piece) -> Self {
+        Move { from, to, promotion: Some(promotion) }
+    }
+
+    /// Packs the move into 15 bits: 6 for the from square, 6 for the to square and 3 for the
+    /// promotion piece. Zero is never a valid packed move, since from and to would be equal.
+    pub fn pack(self) -> u16 {
+        let promotion = match self.promotion {
+            None => 0,
+            Some(Piece::Knight) => 1,
+            Some(Piece::Bishop) => 2,
+            Some(Piece::Rook) => 3,
+            Some(_) => 4,
+        };
+        self.from as u16 | (self.to as u16) << 6 | promotion << 12
+    }
+
+    /// Reverses `Move::pack`. Returns None for the empty value 0.
+    pub fn unpack(packed: u16) -> Option<Move> {
+        if packed == 0 {
+            return None;
+        }
+        let from = Square::from_index((packed & 0x3F) as u8);
+        let to = Square::from_index(((packed >> 6) & 0x3F) as u8);
+        let promotion = match packed >> 12 {
+            1 => Some(Piece::Knight),
+            2 => Some(Piece::Bishop),
+            3 => Some(Piece::Rook),
+            4 => Some(Piece::Queen),
+            _ => None,
+        };
+        Some(Move { from, to, promotion })
+    }
+}
+
+impl fmt::Display for Move {
+    /// Formats the move in the long algebraic notation used by UCI, e.g. "e2e4" or "e7e8q".
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(promotion) = self.promotion {
+            write!(f, "{}", promotion.to_char(super::piece::Color::Black))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    /// Parses a move in the long algebraic notation used by UCI. This only checks the syntax; whether
+    /// the move is legal depends on the position.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.is_ascii() || !(4..=5).contains(&s.len()) {
+            return Err("Move must be two squares optionally followed by a promotion piece");
+        }
+        let from = s[0..2].parse()?;
+        let to = s[2..4].parse()?;
+        let promotion = match s.as_bytes().get(4) {
+            None => None,
+            Some(b'n') => Some(Piece::Knight),
+            Some(b'b') => Some(Piece::Bishop),
+            Some(b'r') => Some(Piece::Rook),
+            Some(b'q') => Some(Piece::Queen),
+            Some(_) => return Err("Invalid promotion piece"),
+        };
+        Ok(Move { from, to, promotion })
+    }
+}
@@ -1,5 +1,5 @@
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Piece {
     Pawn,
     Rook,
@@ -9,9 +9,79 @@ pub enum Piece {
     King,
 }
 
+impl Piece {
+    /// All piece types, in the order of their discriminants.
+    pub const ALL: [Piece; 6] = [Piece::Pawn, Piece::Rook, Piece::Knight, Piece::Bishop, Piece::Queen, Piece::King];
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// Returns the index of the piece, for use with arrays of size 6.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the piece for an index produced by `Piece::index`.
+    pub fn from_index(index: usize) -> Piece {
+        Piece::ALL[index]
+    }
+
+    /// Returns the FEN letter of the piece, uppercase for white and lowercase for black.
+    pub fn to_char(self, color: Color) -> char {
+        let c = match self {
+            Piece::Pawn => 'p',
+            Piece::Rook => 'r',
+            Piece::Knight => 'n',
+            Piece::Bishop => 'b',
+            Piece::Queen => 'q',
+            Piece::King => 'k',
+        };
+        match color {
+            Color::White => c.to_ascii_uppercase(),
+            Color::Black => c,
+        }
+    }
+
+    /// Returns the Unicode chess symbol of the piece, e.g. '♘' for a white knight.
+    pub fn to_glyph(self, color: Color) -> char {
+        let glyphs = match color {
+            Color::White => ['♙', '♖', '♘', '♗', '♕', '♔'],
+            Color::Black => ['♟', '♜', '♞', '♝', '♛', '♚'],
+        };
+        glyphs[self.index()]
+    }
+
+    /// Parses a FEN letter into a piece and its color.
+    pub fn from_char(c: char) -> Option<(Piece, Color)> {
+        let piece = match c.to_ascii_lowercase() {
+            'p' => Piece::Pawn,
+            'r' => Piece::Rook,
+            'n' => Piece::Knight,
+            'b' => Piece::Bishop,
+            'q' => Piece::Queen,
+            'k' => Piece::King,
+            _ => return None,
+        };
+        let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+        Some((piece, color))
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Color {
     White,
     Black,
-}
\ No newline at end of file
+}
+
+impl Color {
+    /// Returns the other color.
+    pub fn opposite(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+
+    /// Returns the index of the color, for use with arrays of size 2.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
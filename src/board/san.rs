@@ -0,0 +1,170 @@
+use std::fmt;
+
+use super::{
+    board::Board,
+    movegen::generate_legal_moves,
+    moves::Move,
+    piece::{Color, Piece},
+    square::Square,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SanError {
+    /// The string is not a move in Standard Algebraic Notation.
+    InvalidSyntax(String),
+    /// No legal move in the position matches the string.
+    IllegalMove(String),
+    /// More than one legal move matches the string.
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanError::InvalidSyntax(s) => write!(f, "invalid SAN move: {s}"),
+            SanError::IllegalMove(s) => write!(f, "illegal move: {s}"),
+            SanError::AmbiguousMove(s) => write!(f, "ambiguous move: {s}"),
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+impl Move {
+    /// Formats the move in Standard Algebraic Notation, e.g. "Nbd7", "exd6", "e8=Q+" or "O-O-O".
+    /// The move must be legal on `board`.
+    pub fn to_san(self, board: &Board) -> String {
+        let mut san = if board.is_castling(self) {
+            if self.to.file() > self.from.file() { "O-O" } else { "O-O-O" }.to_string()
+        } else {
+            let piece = board.moved_piece(self);
+            let mut san = String::new();
+            if piece == Piece::Pawn {
+                if board.is_capture(self) {
+                    san.push(file_char(self.from));
+                }
+            } else {
+                san.push(piece.to_char(Color::White));
+                // Disambiguate by file if that is enough, otherwise by rank, otherwise by both.
+                let others: Vec<Square> = generate_legal_moves(board)
+                    .into_iter()
+                    .filter(|other| other.to == self.to && other.from != self.from && board.moved_piece(*other) == piece)
+                    .map(|other| other.from)
+                    .collect();
+                if !others.is_empty() {
+                    if others.iter().all(|other| other.file() != self.from.file()) {
+                        san.push(file_char(self.from));
+                    } else if others.iter().all(|other| other.rank() != self.from.rank()) {
+                        san.push(rank_char(self.from));
+                    } else {
+                        san.push_str(&self.from.to_string());
+                    }
+                }
+            }
+            if board.is_capture(self) {
+                san.push('x');
+            }
+            san.push_str(&self.to.to_string());
+            if let Some(promotion) = self.promotion {
+                san.push('=');
+                san.push(promotion.to_char(Color::White));
+            }
+            san
+        };
+
+        let mut after = board.clone();
+        after.make_move(self);
+        if after.in_check() {
+            san.push(if generate_legal_moves(&after).is_empty() { '#' } else { '+' });
+        }
+        san
+    }
+}
+
+impl Board {
+    /// Finds the legal move written in Standard Algebraic Notation. Common variants are accepted:
+    /// castling with zeros, missing or superfluous check and capture marks, annotations like "!?"
+    /// and promotions without "=". Piece letters must be uppercase, since a lowercase "b" is a file.
+    pub fn parse_san(&self, s: &str) -> Result<Move, SanError> {
+        let invalid = || SanError::InvalidSyntax(s.to_string());
+        let trimmed = s.trim();
+        let trimmed = trimmed.strip_suffix("e.p.").unwrap_or(trimmed).trim_end();
+        let san = trimmed.trim_end_matches(['+', '#', '!', '?']);
+
+        let legal_moves = generate_legal_moves(self);
+        if let Some(queenside) = match san {
+            "O-O" | "0-0" => Some(false),
+            "O-O-O" | "0-0-0" => Some(true),
+            _ => None,
+        } {
+            return legal_moves
+                .into_iter()
+                .find(|&mv| self.is_castling(mv) && (mv.to.file() < mv.from.file()) == queenside)
+                .ok_or_else(|| SanError::IllegalMove(s.to_string()));
+        }
+
+        let mut chars: Vec<char> = san.chars().collect();
+        let piece = match chars.first() {
+            Some('N') => Piece::Knight,
+            Some('B') => Piece::Bishop,
+            Some('R') => Piece::Rook,
+            Some('Q') => Piece::Queen,
+            Some('K') => Piece::King,
+            Some('a'..='h') => Piece::Pawn,
+            _ => return Err(invalid()),
+        };
+        if piece != Piece::Pawn {
+            chars.remove(0);
+        }
+
+        let promotion = match chars.last() {
+            Some(&c @ ('N' | 'B' | 'R' | 'Q')) => {
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+                Some(Piece::from_char(c).ok_or_else(invalid)?.0)
+            }
+            _ => None,
+        };
+
+        // What is left is the optional origin file and rank, an optional capture mark and the
+        // destination square.
+        if chars.len() < 2 {
+            return Err(invalid());
+        }
+        let to: Square = chars.split_off(chars.len() - 2).iter().collect::<String>().parse().map_err(|_| invalid())?;
+        if chars.last() == Some(&'x') {
+            chars.pop();
+        }
+        let (from_file, from_rank) = match chars[..] {
+            [] => (None, None),
+            [file @ 'a'..='h'] => (Some(file), None),
+            [rank @ '1'..='8'] => (None, Some(rank)),
+            [file @ 'a'..='h', rank @ '1'..='8'] => (Some(file), Some(rank)),
+            _ => return Err(invalid()),
+        };
+
+        let mut candidates = legal_moves.into_iter().filter(|&mv| {
+            mv.to == to
+                && mv.promotion == promotion
+                && !self.is_castling(mv)
+                && self.moved_piece(mv) == piece
+                && from_file.is_none_or(|file| file_char(mv.from) == file)
+                && from_rank.is_none_or(|rank| rank_char(mv.from) == rank)
+        });
+        match (candidates.next(), candidates.next()) {
+            (Some(mv), None) => Ok(mv),
+            (Some(_), Some(_)) => Err(SanError::AmbiguousMove(s.to_string())),
+            (None, _) => Err(SanError::IllegalMove(s.to_string())),
+        }
+    }
+}
+
+fn file_char(square: Square) -> char {
+    (b'a' + square.file()) as char
+}
+
+fn rank_char(square: Square) -> char {
+    (b'1' + square.rank()) as char
+}
@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, str::FromStr};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Square {
@@ -30,6 +30,26 @@ impl Square {
     pub fn rank(self) -> u8 {
         (self as u8).wrapping_shr(3)
     }
+
+    /// Returns the square on the given rank and file, both counted from 0.
+    pub fn from_rank_file(rank: u8, file: u8) -> Square {
+        Square::from_index(rank * 8 + file)
+    }
+
+    /// Returns the index of the square, for use with arrays of size 64.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Mirrors the square vertically, so that A1 becomes A8 and E2 becomes E7.
+    pub fn flip(self) -> Square {
+        Square::from_index(self as u8 ^ 56)
+    }
+
+    /// Returns an iterator over all 64 squares, from A1 to H8.
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).map(Square::from_index)
+    }
 }
 
 impl fmt::Display for Square {
@@ -47,9 +67,28 @@ impl TryFrom<u8> for Square {
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         if value < 64 {
-            Ok(unsafe { std::mem::transmute(value) })
+            Ok(unsafe { std::mem::transmute::<u8, Square>(value) })
         } else {
             Err("Value out of bounds for Square")
         }
     }
-}
\ No newline at end of file
+}
+
+impl FromStr for Square {
+    type Err = &'static str;
+
+    /// Parses a square from algebraic coordinates, e.g. "e4" or "E4".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s.as_bytes();
+        if bytes.len() != 2 {
+            return Err("Square must be a file letter followed by a rank digit");
+        }
+        let file = bytes[0].to_ascii_lowercase().wrapping_sub(b'a');
+        let rank = bytes[1].wrapping_sub(b'1');
+        if file < 8 && rank < 8 {
+            Ok(Square::from_rank_file(rank, file))
+        } else {
+            Err("Square coordinates out of bounds")
+        }
+    }
+}
@@ -1,9 +1,40 @@
-use std::collections::HashMap;
-use super::{bitboard::BitBoard, square::Square, utils::{pdep, pext}};
+use std::{collections::HashMap, sync::OnceLock};
+use super::{bitboard::BitBoard, piece::Color, square::Square};
 
 
+/// All attack tables the engine needs, built once and shared through `attack_tables`.
 pub struct AttackTables {
+    /// The rank attack table, which is shared by all sliding pieces.
     pub rook_attacks: SlidingAttackTable,
+    pub precomputed_masks: PrecomputedMasks,
+    pub knight_attacks: KnightAttackTable,
+    pub king_attacks: KingAttackTable,
+    pub pawn_attacks: PawnAttackTable,
+}
+
+impl AttackTables {
+    pub fn new() -> Self {
+        AttackTables {
+            rook_attacks: generate_rank_attack_table(),
+            precomputed_masks: precompute_masks(),
+            knight_attacks: KnightAttackTable::new(),
+            king_attacks: KingAttackTable::new(),
+            pawn_attacks: PawnAttackTable::new(),
+        }
+    }
+}
+
+impl Default for AttackTables {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static ATTACK_TABLES: OnceLock<AttackTables> = OnceLock::new();
+
+/// Returns the global attack tables, generating them on first use.
+pub fn attack_tables() -> &'static AttackTables {
+    ATTACK_TABLES.get_or_init(AttackTables::new)
 }
 
 
@@ -23,20 +54,112 @@ impl BishopAttackTable {
         }
     }
 
-    pub fn insert(&mut self, square: Square, mask: u64, value: u64) {
+    pub fn insert(&mut self, square: Square, _mask: u64, value: u64) {
         self.table.insert(square, value);
     }
 
-    pub fn get(&self, square: Square, mask: u64) -> Option<&u64> {
+    pub fn get(&self, square: Square, _mask: u64) -> Option<&u64> {
         self.table.get(&square)
     }
 }
 
+impl Default for BishopAttackTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// Generates the attack mask of a piece that moves a fixed set of (file, rank) steps from a square.
+fn leaper_attacks(square: Square, steps: &[(i8, i8)]) -> u64 {
+    let mut attacks = 0u64;
+    for (file_step, rank_step) in steps {
+        let file = square.file() as i8 + file_step;
+        let rank = square.rank() as i8 + rank_step;
+        if (0..8).contains(&file) && (0..8).contains(&rank) {
+            attacks |= 1 << (rank * 8 + file);
+        }
+    }
+    attacks
+}
+
 
 pub struct KnightAttackTable {
     pub table: HashMap<Square, u64>,
 }
 
+impl KnightAttackTable {
+    pub fn new() -> Self {
+        const STEPS: [(i8, i8); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+        KnightAttackTable {
+            table: Square::all().map(|square| (square, leaper_attacks(square, &STEPS))).collect(),
+        }
+    }
+
+    pub fn get(&self, square: Square) -> u64 {
+        self.table[&square]
+    }
+}
+
+impl Default for KnightAttackTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+pub struct KingAttackTable {
+    pub table: HashMap<Square, u64>,
+}
+
+impl KingAttackTable {
+    pub fn new() -> Self {
+        const STEPS: [(i8, i8); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+        KingAttackTable {
+            table: Square::all().map(|square| (square, leaper_attacks(square, &STEPS))).collect(),
+        }
+    }
+
+    pub fn get(&self, square: Square) -> u64 {
+        self.table[&square]
+    }
+}
+
+impl Default for KingAttackTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// The squares attacked by a pawn of each color, indexed by `Color::index`.
+pub struct PawnAttackTable {
+    pub table: Vec<HashMap<Square, u64>>,
+}
+
+impl PawnAttackTable {
+    pub fn new() -> Self {
+        const WHITE_STEPS: [(i8, i8); 2] = [(-1, 1), (1, 1)];
+        const BLACK_STEPS: [(i8, i8); 2] = [(-1, -1), (1, -1)];
+        PawnAttackTable {
+            table: vec![
+                Square::all().map(|square| (square, leaper_attacks(square, &WHITE_STEPS))).collect(),
+                Square::all().map(|square| (square, leaper_attacks(square, &BLACK_STEPS))).collect(),
+            ],
+        }
+    }
+
+    pub fn get(&self, square: Square, color: Color) -> u64 {
+        self.table[color.index()][&square]
+    }
+}
+
+impl Default for PawnAttackTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 
 impl SlidingAttackTable {
     pub fn new() -> Self {
@@ -54,6 +177,12 @@ impl SlidingAttackTable {
     }
 }
 
+impl Default for SlidingAttackTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub fn test_attack_mask() {
     let file = 4;
     let mask = 0b11110100;
@@ -86,7 +215,7 @@ pub fn generate_rank_attack_table() -> SlidingAttackTable {
     for square in full.iter_squares() {
         let file = square.file() ;
         // Generate all possible permutations of pieces on the same rank.
-        for mask in 0..256 as u64 {
+        for mask in 0..256u64 {
             let mut attacks = 0;
 
             // Calculate the attack mask for the current permutation.
@@ -106,7 +235,7 @@ pub fn generate_rank_attack_table() -> SlidingAttackTable {
                 }
             }
 
-            rank_attacks.insert(square.file(), mask as u64, attacks);
+            rank_attacks.insert(square.file(), mask, attacks);
         }
 
     }
@@ -3,7 +3,7 @@ use core::mem::size_of;
 use std::collections::HashMap;
 
 
-use super::tables::SlidingAttackTable;
+use super::{board::Board, square::Square, tables::SlidingAttackTable};
 
 /// A function to extract bits from a source using a mask.
 pub fn pext(source: u64, mask: u64) -> u64 {
@@ -29,6 +29,26 @@ pub fn print_bitboard(bitboard: u64) {
     println!("  A B C D E F G H");
 }
 
+/// Renders the pieces of a board in the layout of `print_bitboard`, with FEN letters for pieces
+/// and dots for empty squares. Returns one line per rank and a line of file labels.
+pub fn board_diagram(board: &Board) -> Vec<String> {
+    let mut lines = Vec::new();
+    for rank in (0..8).rev() {
+        let mut line = format!("{} ", rank + 1);
+        for file in 0..8 {
+            let c = match board.piece_at(Square::from_rank_file(rank, file)) {
+                Some((piece, color)) => piece.to_char(color),
+                None => '.',
+            };
+            line.push(c);
+            line.push(' ');
+        }
+        lines.push(line);
+    }
+    lines.push("  A B C D E F G H ".to_string());
+    lines
+}
+
 /// A function to check the memory usage of the SlidingAttackTable struct.
 pub fn check_memory_of_table(attacks: SlidingAttackTable) {
         // Size of the RankAttack struct itself
@@ -39,9 +59,7 @@ pub fn check_memory_of_table(attacks: SlidingAttackTable) {
         let mut total_size = size_of_rank_attack;
         for hashmap in &attacks.table {
             total_size += size_of::<HashMap<u64, u64>>();
-            for (key, value) in hashmap {
-                total_size += size_of::<u64>() * 2; // Size of key and value
-            }
+            total_size += hashmap.len() * size_of::<u64>() * 2; // Size of keys and values
         }
         println!("Approximate total memory usage: {} bytes", total_size);
 }
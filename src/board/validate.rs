@@ -0,0 +1,163 @@
+use std::fmt;
+
+use super::{
+    bitboard::BitBoard,
+    board::{Board, CastlingRights, STANDARD_CASTLING_ROOKS},
+    piece::{Color, Piece},
+    square::Square,
+    tile::Tile,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PositionError {
+    /// More than one piece stands on the square.
+    OverlappingPieces(Square),
+    /// The side has the given number of kings instead of one.
+    KingCount(Color, u32),
+    PawnOnBackRank(Square),
+    /// The side that just moved left its king in check.
+    OpponentInCheck,
+    /// The castling right, a single flag, can't be used in any game reaching this position: the
+    /// king or the rook isn't where it started.
+    InvalidCastlingRights(CastlingRights),
+    /// No pawn can have just skipped over the en passant square.
+    InvalidEnPassant(Square),
+    /// The named occupancy field doesn't match the bitboards of the pieces.
+    InconsistentOccupancy(&'static str),
+}
+
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        Color::Black => "black",
+    }
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionError::OverlappingPieces(square) => write!(f, "more than one piece on {square}"),
+            PositionError::KingCount(color, count) => write!(f, "{} has {count} kings", color_name(*color)),
+            PositionError::PawnOnBackRank(square) => write!(f, "pawn on the back rank at {square}"),
+            PositionError::OpponentInCheck => write!(f, "the side not to move is in check"),
+            PositionError::InvalidCastlingRights(rights) => {
+                let right = ['K', 'Q', 'k', 'q'][rights.0.trailing_zeros() as usize % 4];
+                write!(f, "invalid castling right {right}")
+            }
+            PositionError::InvalidEnPassant(square) => write!(f, "invalid en passant square {square}"),
+            PositionError::InconsistentOccupancy(field) => write!(f, "{field} doesn't match the pieces"),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+impl Board {
+    /// Checks that the position is one the rest of the engine can handle: the pieces don't
+    /// overlap, the occupancy bitboards and tiles agree with the bitboards of the pieces, each
+    /// side has exactly one king, no pawns stand on the first or last rank, the side not to move
+    /// isn't in check, and the castling rights and en passant square are possible.
+    ///
+    /// The checks run in that order and the first one that fails is returned. Positions built with
+    /// `Board::from_fen` or by hand should be validated before searching them, since the search
+    /// and move generation assume all of this holds.
+    pub fn validate(&self) -> Result<(), PositionError> {
+        let mut occupied = BitBoard::EMPTY;
+        let mut tiles = [Tile::Empty; 64];
+        for color in [Color::White, Color::Black] {
+            for piece in Piece::ALL {
+                let pieces = self.pieces(piece, color);
+                let overlap = occupied & pieces;
+                if !overlap.is_empty() {
+                    return Err(PositionError::OverlappingPieces(overlap.lsb()));
+                }
+                occupied |= pieces;
+                for square in pieces.iter_squares() {
+                    tiles[square.index()] = Tile::Occupied { piece, color };
+                }
+            }
+        }
+
+        let color_pieces = |color| Piece::ALL.iter().fold(BitBoard::EMPTY, |all, &piece| all | self.pieces(piece, color));
+        if self.white_pieces != color_pieces(Color::White) {
+            return Err(PositionError::InconsistentOccupancy("white_pieces"));
+        }
+        if self.black_pieces != color_pieces(Color::Black) {
+            return Err(PositionError::InconsistentOccupancy("black_pieces"));
+        }
+        if self.all_pieces != occupied {
+            return Err(PositionError::InconsistentOccupancy("all_pieces"));
+        }
+        if self.tiles != tiles {
+            return Err(PositionError::InconsistentOccupancy("tiles"));
+        }
+
+        for color in [Color::White, Color::Black] {
+            let kings = self.pieces(Piece::King, color).count();
+            if kings != 1 {
+                return Err(PositionError::KingCount(color, kings));
+            }
+        }
+
+        let pawns = self.white_pawns | self.black_pawns;
+        let back_rank_pawns = pawns & (BitBoard::RANK_1 | BitBoard::RANK_8);
+        if !back_rank_pawns.is_empty() {
+            return Err(PositionError::PawnOnBackRank(back_rank_pawns.lsb()));
+        }
+
+        let them = self.side_to_move.opposite();
+        if self.is_square_attacked(self.king_square(them), self.side_to_move) {
+            return Err(PositionError::OpponentInCheck);
+        }
+
+        for (i, &rook) in self.castling_rooks.iter().enumerate() {
+            let right = CastlingRights(1 << i);
+            if self.castling_rights.contains(right) && !self.is_castling_right_possible(i, rook) {
+                return Err(PositionError::InvalidCastlingRights(right));
+            }
+        }
+
+        if let Some(square) = self.en_passant {
+            if !self.is_en_passant_possible(square) {
+                return Err(PositionError::InvalidEnPassant(square));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the king and the rook of castling right `i` are on their back rank, with
+    /// the rook on the side of the king it castles to. Outside Chess960 both must also be on
+    /// their standard squares.
+    fn is_castling_right_possible(&self, i: usize, rook: Square) -> bool {
+        let color = if i < 2 { Color::White } else { Color::Black };
+        let kingside = i.is_multiple_of(2);
+        let rank = if color == Color::White { 0 } else { 7 };
+        let king = self.king_square(color);
+        if king.rank() != rank || rook.rank() != rank || !self.pieces(Piece::Rook, color).contains(rook) {
+            return false;
+        }
+        if !self.chess960 {
+            return king.file() == 4 && rook == STANDARD_CASTLING_ROOKS[i];
+        }
+        if kingside {
+            rook.file() > king.file()
+        } else {
+            rook.file() < king.file()
+        }
+    }
+
+    /// Returns true if the side that just moved can have pushed a pawn over `square` by two
+    /// squares: the square is on the right rank and it and the pawn's start square are empty,
+    /// with the pawn standing beyond them.
+    fn is_en_passant_possible(&self, square: Square) -> bool {
+        let pusher = self.side_to_move.opposite();
+        let (rank, pawn_rank, start_rank) = if pusher == Color::White { (2, 3, 1) } else { (5, 4, 6) };
+        if square.rank() != rank {
+            return false;
+        }
+        let pawn = Square::from_rank_file(pawn_rank, square.file());
+        let start = Square::from_rank_file(start_rank, square.file());
+        self.pieces(Piece::Pawn, pusher).contains(pawn) && !self.all_pieces.contains(square) && !self.all_pieces.contains(start)
+    }
+}
@@ -0,0 +1,74 @@
+/// Random keys used to incrementally hash positions. The keys are generated at compile time from a
+/// fixed seed, so hashes are stable between runs (which the transposition table does not need, but
+/// makes debugging a lot easier).
+pub struct ZobristKeys {
+    /// One key per color, piece and square, indexed as `pieces[color][piece][square]`.
+    pub pieces: [[[u64; 64]; 6]; 2],
+    /// One key per combination of the four castling right bits.
+    pub castling: [u64; 16],
+    /// One key per file of the en passant square.
+    pub en_passant: [u64; 8],
+    /// XOR'ed into the hash when black is to move.
+    pub side: u64,
+}
+
+/// SplitMix64, which is tiny and good enough for hash keys.
+const fn next_key(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    (state, z ^ (z >> 31))
+}
+
+impl ZobristKeys {
+    const fn generate() -> Self {
+        let mut keys = ZobristKeys {
+            pieces: [[[0; 64]; 6]; 2],
+            castling: [0; 16],
+            en_passant: [0; 8],
+            side: 0,
+        };
+        let mut state = 0x4B6E_6967_6874_626F; // "Knightbo"
+
+        let mut color = 0;
+        while color < 2 {
+            let mut piece = 0;
+            while piece < 6 {
+                let mut square = 0;
+                while square < 64 {
+                    let (next, key) = next_key(state);
+                    state = next;
+                    keys.pieces[color][piece][square] = key;
+                    square += 1;
+                }
+                piece += 1;
+            }
+            color += 1;
+        }
+
+        // The empty castling right set hashes to zero, so a position without castling rights
+        // needs no special handling.
+        let mut i = 1;
+        while i < 16 {
+            let (next, key) = next_key(state);
+            state = next;
+            keys.castling[i] = key;
+            i += 1;
+        }
+
+        let mut file = 0;
+        while file < 8 {
+            let (next, key) = next_key(state);
+            state = next;
+            keys.en_passant[file] = key;
+            file += 1;
+        }
+
+        let (_, key) = next_key(state);
+        keys.side = key;
+        keys
+    }
+}
+
+pub static ZOBRIST: ZobristKeys = ZobristKeys::generate();
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use crate::{board::piece::Color, pgn::Game};
+
+use super::{encode_move, polyglot_key, Book, BookEntry};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookBuilderConfig {
+    /// Moves played in fewer games than this are left out.
+    pub min_games: u32,
+    /// Only the first this many plies of each game are added.
+    pub max_ply: usize,
+}
+
+impl Default for BookBuilderConfig {
+    fn default() -> Self {
+        BookBuilderConfig { min_games: 3, max_ply: 20 }
+    }
+}
+
+/// How often a move was played in a position, and how it went for the side that played it.
+#[derive(Clone, Copy, Debug, Default)]
+struct MoveStats {
+    games: u32,
+    /// Two points per win and one per draw, which becomes the weight of the move as in Polyglot.
+    points: u64,
+}
+
+/// Collects the opening moves of games and turns them into a Polyglot book.
+#[derive(Clone, Debug, Default)]
+pub struct BookBuilder {
+    config: BookBuilderConfig,
+    moves: HashMap<(u64, u16), MoveStats>,
+}
+
+impl BookBuilder {
+    pub fn new(config: BookBuilderConfig) -> Self {
+        BookBuilder { config, moves: HashMap::new() }
+    }
+
+    /// Adds the opening of a game. Unfinished games and games with an invalid FEN tag are
+    /// skipped, since there is no result to weigh their moves by.
+    pub fn add_game(&mut self, game: &Game) {
+        let white_points = match game.result.as_str() {
+            "1-0" => 2,
+            "1/2-1/2" => 1,
+            "0-1" => 0,
+            _ => return,
+        };
+        let Ok(mut board) = game.start_position() else { return };
+        for pgn_move in game.moves.iter().take(self.config.max_ply) {
+            let points = if board.side_to_move == Color::White { white_points } else { 2 - white_points };
+            let stats = self.moves.entry((polyglot_key(&board), encode_move(&board, pgn_move.mv))).or_default();
+            stats.games += 1;
+            stats.points += points;
+            board.make_move(pgn_move.mv);
+        }
+    }
+
+    /// The book of all moves played in at least `min_games` games that scored at least a draw
+    /// once. Weights are scaled down if needed to fit Polyglot's 16 bits.
+    pub fn build(&self) -> Book {
+        let kept: Vec<(&(u64, u16), &MoveStats)> =
+            self.moves.iter().filter(|(_, stats)| stats.games >= self.config.min_games && stats.points > 0).collect();
+        let max_points = kept.iter().map(|(_, stats)| stats.points).max().unwrap_or(0);
+        let scale = max_points.div_ceil(u16::MAX as u64).max(1);
+        let entries = kept
+            .into_iter()
+            .map(|(&(key, mv), stats)| BookEntry { key, mv, weight: (stats.points / scale).max(1) as u16, learn: 0 })
+            .collect();
+        Book::from_entries(entries)
+    }
+}
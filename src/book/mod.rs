@@ -0,0 +1,224 @@
+pub mod builder;
+pub mod random;
+
+use std::{fmt, fs, io, path::Path};
+
+use crate::board::{
+    board::{Board, CastlingRights},
+    movegen::{generate_legal_moves, pawn_attacks},
+    moves::Move,
+    piece::{Color, Piece},
+};
+
+use random::POLYGLOT_RANDOM;
+
+/// The size of an entry in a Polyglot book file.
+pub const ENTRY_SIZE: usize = 16;
+
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+
+#[derive(Debug)]
+pub enum BookError {
+    Io(io::Error),
+    /// The file size is not a multiple of `ENTRY_SIZE`.
+    InvalidSize(usize),
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BookError::Io(error) => write!(f, "{error}"),
+            BookError::InvalidSize(size) => write!(f, "book file is {size} bytes, not a multiple of {ENTRY_SIZE}"),
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+/// How to choose between the book moves of a position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BookSelection {
+    /// Always the move with the highest weight.
+    Best,
+    /// A random move, with probabilities proportional to the weights.
+    Weighted,
+}
+
+/// A move of a Polyglot book: 16 bytes, all big-endian. The move packs the to file in bits 0-2,
+/// the to rank in bits 3-5, the from file in bits 6-8, the from rank in bits 9-11 and the
+/// promotion piece in bits 12-14 (1 knight, 2 bishop, 3 rook, 4 queen). Castling is written as the
+/// king capturing its own rook, e.g. e1h1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookEntry {
+    pub key: u64,
+    pub mv: u16,
+    pub weight: u16,
+    /// Used by some programs to learn from played games, ignored here.
+    pub learn: u32,
+}
+
+impl BookEntry {
+    pub fn from_bytes(bytes: &[u8; ENTRY_SIZE]) -> BookEntry {
+        BookEntry {
+            key: u64::from_be_bytes(bytes[0..8].try_into().expect("8 bytes of key")),
+            mv: u16::from_be_bytes([bytes[8], bytes[9]]),
+            weight: u16::from_be_bytes([bytes[10], bytes[11]]),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().expect("4 bytes of learning data")),
+        }
+    }
+
+    pub fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.mv.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+/// The Polyglot piece kind, see `POLYGLOT_RANDOM`.
+fn polyglot_kind(piece: Piece, color: Color) -> usize {
+    let piece_type = match piece {
+        Piece::Pawn => 0,
+        Piece::Knight => 1,
+        Piece::Bishop => 2,
+        Piece::Rook => 3,
+        Piece::Queen => 4,
+        Piece::King => 5,
+    };
+    piece_type * 2 + if color == Color::White { 1 } else { 0 }
+}
+
+/// The Polyglot key of a position, which books are indexed by. This is a different hash from
+/// `Board::hash`.
+pub fn polyglot_key(board: &Board) -> u64 {
+    let mut key = 0;
+    for square in board.all_pieces.iter_squares() {
+        let (piece, color) = board.piece_at(square).expect("occupied squares hold a piece");
+        key ^= POLYGLOT_RANDOM[64 * polyglot_kind(piece, color) + square.index()];
+    }
+    let rights = [
+        CastlingRights::WHITE_KINGSIDE,
+        CastlingRights::WHITE_QUEENSIDE,
+        CastlingRights::BLACK_KINGSIDE,
+        CastlingRights::BLACK_QUEENSIDE,
+    ];
+    for (i, right) in rights.into_iter().enumerate() {
+        if board.castling_rights.contains(right) {
+            key ^= POLYGLOT_RANDOM[CASTLING_OFFSET + i];
+        }
+    }
+    // Polyglot only counts the en passant square if a pawn could capture onto it.
+    if let Some(square) = board.en_passant {
+        let us = board.side_to_move;
+        if !(pawn_attacks(square, us.opposite()) & board.pieces(Piece::Pawn, us)).is_empty() {
+            key ^= POLYGLOT_RANDOM[EN_PASSANT_OFFSET + square.file() as usize];
+        }
+    }
+    if board.side_to_move == Color::White {
+        key ^= POLYGLOT_RANDOM[TURN_OFFSET];
+    }
+    key
+}
+
+/// Encodes a legal move of the position as a Polyglot book move.
+pub fn encode_move(board: &Board, mv: Move) -> u16 {
+    let to = if board.is_castling(mv) { board.castling_squares(mv).1 } else { mv.to };
+    let promotion = match mv.promotion {
+        Some(Piece::Knight) => 1,
+        Some(Piece::Bishop) => 2,
+        Some(Piece::Rook) => 3,
+        Some(Piece::Queen) => 4,
+        _ => 0,
+    };
+    (to.file() as u16) | (to.rank() as u16) << 3 | (mv.from.file() as u16) << 6 | (mv.from.rank() as u16) << 9 | promotion << 12
+}
+
+/// Decodes a Polyglot book move, or returns None if it isn't legal in the position.
+pub fn decode_move(board: &Board, raw: u16) -> Option<Move> {
+    generate_legal_moves(board).into_iter().find(|&mv| encode_move(board, mv) == raw)
+}
+
+/// A Polyglot opening book, sorted by key.
+#[derive(Clone, Debug, Default)]
+pub struct Book {
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    /// Creates a book from entries in any order.
+    pub fn from_entries(mut entries: Vec<BookEntry>) -> Book {
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)));
+        Book { entries }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Book, BookError> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(BookError::InvalidSize(bytes.len()));
+        }
+        let entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| BookEntry::from_bytes(chunk.try_into().expect("chunks are ENTRY_SIZE long")))
+            .collect();
+        // Books are sorted by key already, but sorting again is cheap and keeps lookups correct
+        // for sloppy files.
+        Ok(Book::from_entries(entries))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Book, BookError> {
+        Book::from_bytes(&fs::read(path).map_err(BookError::Io)?)
+    }
+
+    /// The book in the Polyglot file format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries.iter().flat_map(|entry| entry.to_bytes()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entries of the position with the given key, highest weight first.
+    pub fn entries(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = self.entries.partition_point(|entry| entry.key <= key);
+        &self.entries[start..end]
+    }
+
+    /// The legal book moves of the position and their weights, highest weight first.
+    pub fn moves(&self, board: &Board) -> Vec<(Move, u16)> {
+        self.entries(polyglot_key(board))
+            .iter()
+            .filter_map(|entry| decode_move(board, entry.mv).map(|mv| (mv, entry.weight)))
+            .collect()
+    }
+
+    /// Picks a book move for the position. `random` is any random number, used by
+    /// `BookSelection::Weighted`.
+    pub fn probe(&self, board: &Board, selection: BookSelection, random: u64) -> Option<Move> {
+        let moves = self.moves(board);
+        let total: u64 = moves.iter().map(|&(_, weight)| weight as u64).sum();
+        match selection {
+            _ if total == 0 => moves.first().map(|&(mv, _)| mv),
+            BookSelection::Best => moves.first().map(|&(mv, _)| mv),
+            BookSelection::Weighted => {
+                let mut pick = random % total;
+                moves.into_iter().find_map(|(mv, weight)| {
+                    if pick < weight as u64 {
+                        Some(mv)
+                    } else {
+                        pick -= weight as u64;
+                        None
+                    }
+                })
+            }
+        }
+    }
+}
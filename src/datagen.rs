@@ -0,0 +1,270 @@
+use std::{
+    io::{self, Read, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use crate::{
+    board::{
+        bitboard::BitBoard,
+        board::{Board, CastlingRights, GameOutcome},
+        movegen::generate_legal_moves,
+        piece::{Color, Piece},
+        square::Square,
+    },
+    search::{Searcher, SearchLimits, MATE_IN_MAX},
+};
+
+/// The size of a position in the binary format, see `PackedPosition`.
+pub const PACKED_SIZE: usize = 32;
+
+/// Openings whose evaluation after the random moves is further from equal than this are thrown
+/// away, since the games would teach little.
+const MAX_OPENING_SCORE: i32 = 1000;
+/// A game is adjudicated as won once the evaluation is this good for one side.
+const WIN_ADJUDICATION_SCORE: i32 = 2500;
+
+/// How training positions are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataFormat {
+    /// `PACKED_SIZE` bytes per position, see `PackedPosition`.
+    Binary,
+    /// One line per position: "<fen> | <score> | <result>", the score in centipawns and the
+    /// result as 1.0, 0.5 or 0.0, both from white's point of view.
+    Text,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DatagenConfig {
+    pub games: usize,
+    pub threads: usize,
+    /// The node limit of each search.
+    pub nodes: u64,
+    /// The number of random moves played from the starting position before the engine takes over.
+    pub random_plies: usize,
+    /// Games still going after this many plies, counting the random ones, are scored as draws.
+    pub max_plies: usize,
+    pub hash_mb: usize,
+    pub seed: u64,
+    pub format: DataFormat,
+}
+
+impl Default for DatagenConfig {
+    fn default() -> Self {
+        DatagenConfig {
+            games: 100,
+            threads: 1,
+            nodes: 5000,
+            random_plies: 8,
+            max_plies: 400,
+            hash_mb: 16,
+            seed: 1,
+            format: DataFormat::Binary,
+        }
+    }
+}
+
+/// The number of games played and positions written by `generate`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DatagenSummary {
+    pub games: usize,
+    pub positions: usize,
+}
+
+/// A position with its evaluation and game result in 32 bytes, all little-endian:
+///
+/// | Bytes | Contents                                                                          |
+/// |-------|-----------------------------------------------------------------------------------|
+/// | 8     | u64 occupancy, bit n set for square n (a1 = 0)                                    |
+/// | 16    | one nibble per occupied square in occupancy order, low nibble first: the piece   |
+/// |       | index in bits 0-2 and the color in bit 3 (set for black)                          |
+/// | 1     | side to move in bit 7 (set for black), en passant square in bits 0-6 (64 if none) |
+/// | 1     | halfmove clock                                                                    |
+/// | 2     | u16 fullmove number                                                               |
+/// | 2     | i16 evaluation in centipawns from white's point of view                           |
+/// | 1     | game result: 0 if black won, 1 for a draw, 2 if white won                         |
+/// | 1     | castling rights as in `CastlingRights`                                            |
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PackedPosition(pub [u8; PACKED_SIZE]);
+
+impl PackedPosition {
+    /// Packs a position with its evaluation from white's point of view and the game result as
+    /// 1.0, 0.5 or 0.0.
+    pub fn new(board: &Board, score: i16, result: f64) -> PackedPosition {
+        let mut bytes = [0; PACKED_SIZE];
+        bytes[..8].copy_from_slice(&board.all_pieces.0.to_le_bytes());
+        for (i, square) in board.all_pieces.iter_squares().enumerate() {
+            let (piece, color) = board.piece_at(square).expect("occupied squares hold a piece");
+            let nibble = (piece.index() | color.index() << 3) as u8;
+            bytes[8 + i / 2] |= nibble << (4 * (i % 2));
+        }
+        let black_to_move = if board.side_to_move == Color::Black { 0x80 } else { 0 };
+        bytes[24] = black_to_move | board.en_passant.map_or(64, |square| square.index() as u8);
+        bytes[25] = board.halfmove_clock.min(255) as u8;
+        bytes[26..28].copy_from_slice(&board.fullmove_number.to_le_bytes());
+        bytes[28..30].copy_from_slice(&score.to_le_bytes());
+        bytes[31] = board.castling_rights.0;
+        let mut packed = PackedPosition(bytes);
+        packed.set_result(result);
+        packed
+    }
+
+    /// Unpacks the position. The history is empty, so earlier repetitions are unknown.
+    pub fn board(&self) -> Board {
+        let bytes = &self.0;
+        let mut board = Board::empty();
+        let occupancy = BitBoard(u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes of occupancy")));
+        for (i, square) in occupancy.iter_squares().enumerate() {
+            let nibble = bytes[8 + i / 2] >> (4 * (i % 2)) & 0xF;
+            let color = if nibble & 8 == 0 { Color::White } else { Color::Black };
+            *board.pieces_mut(Piece::from_index((nibble & 7) as usize), color) |= BitBoard::from(square);
+        }
+        board.side_to_move = if bytes[24] & 0x80 == 0 { Color::White } else { Color::Black };
+        board.en_passant = match bytes[24] & 0x7F {
+            64 => None,
+            index => Some(Square::from_index(index)),
+        };
+        board.halfmove_clock = bytes[25] as u16;
+        board.fullmove_number = u16::from_le_bytes([bytes[26], bytes[27]]);
+        board.castling_rights = CastlingRights(bytes[31]);
+        board.update();
+        board
+    }
+
+    /// The evaluation in centipawns from white's point of view.
+    pub fn score(&self) -> i16 {
+        i16::from_le_bytes([self.0[28], self.0[29]])
+    }
+
+    /// The game result from white's point of view: 1.0 for a win, 0.5 for a draw and 0.0 for a loss.
+    pub fn result(&self) -> f64 {
+        self.0[30] as f64 / 2.0
+    }
+
+    pub fn set_result(&mut self, result: f64) {
+        self.0[30] = (result * 2.0).round() as u8;
+    }
+}
+
+/// Reads positions in the binary format until the end of the input.
+pub fn read_packed_positions<R: Read>(mut reader: R) -> io::Result<Vec<PackedPosition>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if !bytes.len().is_multiple_of(PACKED_SIZE) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "the input is not a whole number of positions"));
+    }
+    let positions = bytes.chunks_exact(PACKED_SIZE).map(|chunk| PackedPosition(chunk.try_into().expect("chunks are PACKED_SIZE long")));
+    Ok(positions.collect())
+}
+
+/// A small xorshift generator, enough to pick random opening moves.
+fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+/// Plays random moves from the starting position until the position is playable and roughly
+/// balanced.
+fn random_opening(searcher: &mut Searcher, random_plies: usize, limits: &SearchLimits, state: &mut u64) -> Board {
+    'retry: loop {
+        let mut board = Board::new();
+        for _ in 0..random_plies {
+            let moves = generate_legal_moves(&board);
+            if moves.is_empty() {
+                continue 'retry;
+            }
+            board.make_move(moves[next_random(state) as usize % moves.len()]);
+        }
+        if board.outcome().is_none() && searcher.search(&mut board, limits.clone()).score.abs() <= MAX_OPENING_SCORE {
+            return board;
+        }
+    }
+}
+
+/// Plays one game against itself and returns the quiet positions with their evaluation and the
+/// final result.
+fn play_game(searcher: &mut Searcher, config: &DatagenConfig, state: &mut u64) -> Vec<(PackedPosition, String)> {
+    let limits = SearchLimits { nodes: Some(config.nodes), ..SearchLimits::default() };
+    searcher.clear();
+    let mut board = random_opening(searcher, config.random_plies, &limits, state);
+    let mut positions = Vec::new();
+    let result = loop {
+        match board.outcome() {
+            Some(GameOutcome::Checkmate(Color::White)) => break 1.0,
+            Some(GameOutcome::Checkmate(Color::Black)) => break 0.0,
+            Some(_) => break 0.5,
+            None if board.history.len() >= config.max_plies => break 0.5,
+            None => {}
+        }
+
+        let search = searcher.search(&mut board, limits.clone());
+        let Some(best_move) = search.best_move else { break 0.5 };
+        let white_score = if board.side_to_move == Color::White { search.score } else { -search.score };
+        if white_score >= WIN_ADJUDICATION_SCORE {
+            break 1.0;
+        }
+        if white_score <= -WIN_ADJUDICATION_SCORE {
+            break 0.0;
+        }
+        // Positions in check or where the best move wins material are not quiet, and their
+        // evaluation would teach the static evaluation about tactics it can't see.
+        let tactical = board.in_check() || board.is_capture(best_move) || best_move.promotion.is_some();
+        if !tactical && search.score.abs() < MATE_IN_MAX {
+            positions.push((PackedPosition::new(&board, white_score as i16, 0.5), board.to_fen()));
+        }
+        board.make_move(best_move);
+    };
+
+    for (packed, _) in &mut positions {
+        packed.set_result(result);
+    }
+    positions
+}
+
+/// Plays self-play games on several threads and writes their quiet positions to `output` as
+/// they finish. `progress` is called after each game with the totals so far.
+pub fn generate<W: Write>(
+    config: &DatagenConfig,
+    output: &mut W,
+    mut progress: impl FnMut(DatagenSummary),
+) -> io::Result<DatagenSummary> {
+    let started = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let mut summary = DatagenSummary::default();
+    thread::scope(|scope| {
+        for thread_id in 0..config.threads.max(1) {
+            let sender = sender.clone();
+            let started = &started;
+            scope.spawn(move || {
+                let mut searcher = Searcher::new(config.hash_mb);
+                let mut state = config.seed.wrapping_add(thread_id as u64).wrapping_mul(0x9E3779B97F4A7C15) | 1;
+                while started.fetch_add(1, Ordering::Relaxed) < config.games {
+                    if sender.send(play_game(&mut searcher, config, &mut state)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        // Only the threads hold senders now, so the loop ends when they are done.
+        drop(sender);
+
+        for positions in receiver {
+            for (packed, fen) in &positions {
+                match config.format {
+                    DataFormat::Binary => output.write_all(&packed.0)?,
+                    DataFormat::Text => writeln!(output, "{fen} | {} | {:.1}", packed.score(), packed.result())?,
+                }
+            }
+            summary.games += 1;
+            summary.positions += positions.len();
+            progress(summary);
+        }
+        output.flush()
+    })?;
+    Ok(summary)
+}
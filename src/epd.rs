@@ -0,0 +1,279 @@
+use std::{
+    fmt,
+    io::{self, BufRead},
+    time::Duration,
+};
+
+use crate::{
+    board::{board::Board, fen::FenError, moves::Move, san::SanError, validate::PositionError},
+    search::{smp::ThreadPool, SearchLimits, SearchResult},
+    uci::parse_uci_move,
+};
+
+#[derive(Debug)]
+pub enum EpdErrorKind {
+    Io(io::Error),
+    InvalidFen(FenError),
+    /// The FEN fields describe a position that can't occur in a game.
+    InvalidPosition(PositionError),
+    /// An operation is malformed, e.g. a quoted operand isn't closed.
+    InvalidOperation(String),
+    /// A move operand of "bm" or "am" isn't a legal move.
+    InvalidMove(SanError),
+}
+
+/// An error in an EPD file, at the given line (starting at 1).
+#[derive(Debug)]
+pub struct EpdError {
+    pub line: usize,
+    pub kind: EpdErrorKind,
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            EpdErrorKind::Io(error) => write!(f, "{error}"),
+            EpdErrorKind::InvalidFen(error) => write!(f, "{error}"),
+            EpdErrorKind::InvalidPosition(error) => write!(f, "invalid position: {error}"),
+            EpdErrorKind::InvalidOperation(message) => write!(f, "{message}"),
+            EpdErrorKind::InvalidMove(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for EpdError {}
+
+/// A position of an Extended Position Description file, with its operations, e.g.
+/// `bm Qg6; id "WAC.001";`.
+#[derive(Clone, Debug)]
+pub struct EpdEntry {
+    pub board: Board,
+    /// The opcodes and their operands, in file order. Quotes around string operands are removed.
+    pub operations: Vec<(String, Vec<String>)>,
+    /// The moves of the "bm" (best move) and "am" (avoid move) operations.
+    pub best_moves: Vec<Move>,
+    pub avoid_moves: Vec<Move>,
+}
+
+impl EpdEntry {
+    /// Parses one line of an EPD file: the first four FEN fields followed by operations. Positions
+    /// that fail `Board::validate` are rejected.
+    pub fn parse(line: &str) -> Result<EpdEntry, EpdErrorKind> {
+        let mut rest = line.trim();
+        let mut fen_fields = Vec::new();
+        for _ in 0..4 {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            fen_fields.push(&rest[..end]);
+            rest = rest[end..].trim_start();
+        }
+        let mut board = Board::from_fen(&fen_fields.join(" ")).map_err(EpdErrorKind::InvalidFen)?;
+        board.validate().map_err(EpdErrorKind::InvalidPosition)?;
+        let operations = parse_operations(rest)?;
+
+        // The move counters can be given as operations.
+        for (opcode, operands) in &operations {
+            let value = operands.first().and_then(|operand| operand.parse().ok());
+            match (opcode.as_str(), value) {
+                ("hmvc", Some(halfmoves)) => board.halfmove_clock = halfmoves,
+                ("fmvn", Some(fullmoves)) => board.fullmove_number = fullmoves,
+                _ => {}
+            }
+        }
+
+        let moves = |opcode: &str| -> Result<Vec<Move>, EpdErrorKind> {
+            operations
+                .iter()
+                .filter(|(name, _)| name == opcode)
+                .flat_map(|(_, operands)| operands)
+                .map(|operand| {
+                    // Some suites give moves in coordinate notation instead of SAN.
+                    board.parse_san(operand).or_else(|error| parse_uci_move(&board, operand).map_err(|_| EpdErrorKind::InvalidMove(error)))
+                })
+                .collect()
+        };
+        let best_moves = moves("bm")?;
+        let avoid_moves = moves("am")?;
+        Ok(EpdEntry { board, operations, best_moves, avoid_moves })
+    }
+
+    /// The operands of the first operation with the given opcode.
+    pub fn operation(&self, opcode: &str) -> Option<&[String]> {
+        self.operations.iter().find(|(name, _)| name == opcode).map(|(_, operands)| operands.as_slice())
+    }
+
+    /// The "id" of the position, e.g. "WAC.001".
+    pub fn id(&self) -> Option<&str> {
+        self.operation("id").and_then(|operands| operands.first()).map(String::as_str)
+    }
+
+    /// The "c0" comment of the position.
+    pub fn comment(&self) -> Option<&str> {
+        self.operation("c0").and_then(|operands| operands.first()).map(String::as_str)
+    }
+
+    /// Returns true if playing `mv` solves the position: it must be one of the best moves and
+    /// none of the moves to avoid. Positions without either have nothing to solve.
+    pub fn is_solved_by(&self, mv: Move) -> bool {
+        if self.best_moves.is_empty() && self.avoid_moves.is_empty() {
+            return false;
+        }
+        (self.best_moves.is_empty() || self.best_moves.contains(&mv)) && !self.avoid_moves.contains(&mv)
+    }
+}
+
+/// Splits the operations part of an EPD line into opcodes and operands. Each operation ends with
+/// a semicolon; string operands are quoted and may contain spaces and semicolons, with `\"` and
+/// `\\` standing for a quote and a backslash.
+fn parse_operations(text: &str) -> Result<Vec<(String, Vec<String>)>, EpdErrorKind> {
+    let mut operations = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => {
+                if !words.is_empty() {
+                    let opcode = words.remove(0);
+                    operations.push((opcode, std::mem::take(&mut words)));
+                }
+            }
+            '"' => {
+                let mut word = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if chars.peek().is_some() => word.extend(chars.next()),
+                        Some(c) => word.push(c),
+                        None => return Err(EpdErrorKind::InvalidOperation(format!("unterminated string in {text:?}"))),
+                    }
+                }
+                words.push(word);
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == ';' || c == '"' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                words.push(word);
+            }
+        }
+    }
+    if !words.is_empty() {
+        return Err(EpdErrorKind::InvalidOperation(format!("operation {:?} is missing its semicolon", words.join(" "))));
+    }
+    Ok(operations)
+}
+
+/// Reads all positions of an EPD file, skipping empty lines and lines starting with "#".
+pub fn read_epd<R: BufRead>(reader: R) -> Result<Vec<EpdEntry>, EpdError> {
+    let mut entries = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|error| EpdError { line: index + 1, kind: EpdErrorKind::Io(error) })?;
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        entries.push(EpdEntry::parse(&line).map_err(|kind| EpdError { line: index + 1, kind })?);
+    }
+    Ok(entries)
+}
+
+/// The outcome of searching one position of a suite.
+#[derive(Clone, Debug)]
+pub struct PositionResult {
+    /// The id of the position, or its FEN if it has none.
+    pub name: String,
+    pub expected: String,
+    pub solved: bool,
+    /// The move the engine chose, in SAN.
+    pub played: String,
+    pub search: SearchResult,
+}
+
+/// The outcome of running a whole suite.
+#[derive(Clone, Debug, Default)]
+pub struct SuiteReport {
+    pub results: Vec<PositionResult>,
+    /// Positions without "bm" or "am" operations, which can't be scored.
+    pub skipped: usize,
+    pub time: Duration,
+}
+
+impl SuiteReport {
+    pub fn solved(&self) -> usize {
+        self.results.iter().filter(|result| result.solved).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.solved()
+    }
+}
+
+impl fmt::Display for SuiteReport {
+    /// Formats one line per position followed by the totals.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name_width = self.results.iter().map(|result| result.name.len()).max().unwrap_or(0);
+        for result in &self.results {
+            writeln!(
+                f,
+                "{:name_width$}  {:6}  played {:7} expected {:12} depth {:2} score {:10} time {}ms",
+                result.name,
+                if result.solved { "solved" } else { "failed" },
+                result.played,
+                result.expected,
+                result.search.depth,
+                result.search.uci_score(),
+                result.search.time.as_millis()
+            )?;
+        }
+        write!(f, "Solved {}/{} ({} failed", self.solved(), self.results.len(), self.failed())?;
+        if self.skipped > 0 {
+            write!(f, ", {} skipped", self.skipped)?;
+        }
+        write!(f, ") in {:.1}s", self.time.as_secs_f64())
+    }
+}
+
+/// Searches every position of a suite with the given limits and checks the engine's move against
+/// the "bm" and "am" operations. The transposition table is cleared before each position so the
+/// results don't depend on the order of the suite. `on_result` is called after each position,
+/// e.g. to print progress.
+pub fn run_suite(
+    pool: &mut ThreadPool,
+    entries: &[EpdEntry],
+    limits: SearchLimits,
+    mut on_result: impl FnMut(&PositionResult),
+) -> SuiteReport {
+    let mut report = SuiteReport::default();
+    for entry in entries {
+        if entry.best_moves.is_empty() && entry.avoid_moves.is_empty() {
+            report.skipped += 1;
+            continue;
+        }
+        pool.clear();
+        let search = pool.search(&entry.board, limits.clone());
+        let san = |moves: &[Move]| moves.iter().map(|mv| mv.to_san(&entry.board)).collect::<Vec<_>>().join(" ");
+        let mut expected = Vec::new();
+        if !entry.best_moves.is_empty() {
+            expected.push(format!("bm {}", san(&entry.best_moves)));
+        }
+        if !entry.avoid_moves.is_empty() {
+            expected.push(format!("am {}", san(&entry.avoid_moves)));
+        }
+        let result = PositionResult {
+            name: entry.id().map_or_else(|| entry.board.to_fen(), str::to_string),
+            expected: expected.join(", "),
+            solved: search.best_move.is_some_and(|mv| entry.is_solved_by(mv)),
+            played: search.best_move.map_or_else(|| "none".to_string(), |mv| mv.to_san(&entry.board)),
+            search,
+        };
+        on_result(&result);
+        report.time += result.search.time;
+        report.results.push(result);
+    }
+    report
+}
@@ -0,0 +1,72 @@
+use crate::board::{
+    bitboard::BitBoard,
+    board::Board,
+    movegen::{fetch_psuedo_legal_moves, king_attacks},
+    piece::{Color, Piece},
+    square::Square,
+};
+
+use super::{params::PAWN_SHIELD, Param, Score, Terms, Tracer};
+
+/// The squares around the king plus the three squares in front of those, as seen by `color`.
+/// Enemy attacks on these squares make the king unsafe.
+pub fn king_zone(square: Square, color: Color) -> BitBoard {
+    let zone = king_attacks(square) | BitBoard::from(square);
+    match color {
+        Color::White => zone | zone << 8,
+        Color::Black => zone | zone >> 8,
+    }
+}
+
+/// Evaluates the safety of `color`'s king, from its point of view: enemy attacks on the king
+/// zone, the pawn shield in front of the king and open files next to it.
+pub fn evaluate_king_safety(board: &Board, color: Color) -> Score {
+    king_safety_terms(board, color, &mut ())
+}
+
+/// `evaluate_king_safety`, reporting the weights used to `tracer`.
+pub fn king_safety_terms<T: Tracer>(board: &Board, color: Color, tracer: &mut T) -> Score {
+    let them = color.opposite();
+    let king = board.king_square(color);
+    let zone = king_zone(king, color);
+    let mut terms = Terms::new(color, tracer);
+
+    // A single attacker is rarely dangerous, so the attacks only count once two pieces join in.
+    let mut attackers = 0;
+    let mut attacked_squares = [0; 6];
+    for piece in [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+        for square in board.pieces(piece, them).iter_squares() {
+            let attacks = fetch_psuedo_legal_moves(piece, square, board.all_pieces) & zone;
+            if !attacks.is_empty() {
+                attackers += 1;
+                attacked_squares[piece.index()] += attacks.count() as i32;
+            }
+        }
+    }
+    if attackers >= 2 {
+        for piece in Piece::ALL {
+            if attacked_squares[piece.index()] > 0 {
+                terms.add_times(Param::KingZoneAttack, piece.index(), attacked_squares[piece.index()]);
+            }
+        }
+    }
+
+    let our_pawns = board.pieces(Piece::Pawn, color);
+    let their_pawns = board.pieces(Piece::Pawn, them);
+    for file in king.file().saturating_sub(1)..=(king.file() + 1).min(7) {
+        let file_mask = BitBoard::FILE_A << file;
+        if (our_pawns & file_mask).is_empty() {
+            terms.add(if (their_pawns & file_mask).is_empty() { Param::KingOpenFile } else { Param::KingSemiOpenFile }, 0);
+        }
+        for distance in 0..PAWN_SHIELD.len() {
+            let rank = match color {
+                Color::White => king.rank() as i32 + distance as i32 + 1,
+                Color::Black => king.rank() as i32 - distance as i32 - 1,
+            };
+            if (0..8).contains(&rank) && our_pawns.contains(Square::from_rank_file(rank as u8, file)) {
+                terms.add(Param::PawnShield, distance);
+            }
+        }
+    }
+    terms.score
+}
@@ -0,0 +1,46 @@
+use crate::board::{
+    bitboard::BitBoard,
+    board::Board,
+    movegen::fetch_psuedo_legal_moves,
+    piece::{Color, Piece},
+};
+
+use super::{pawns::pawn_attack_map, Param, Score, Terms, Tracer};
+
+/// The squares that count for the mobility of `color`'s pieces: those not occupied by its own
+/// pieces and not attacked by enemy pawns, since a piece moving there would just be lost.
+pub fn mobility_area(board: &Board, color: Color) -> BitBoard {
+    let enemy_pawn_attacks = pawn_attack_map(board.pieces(Piece::Pawn, color.opposite()), color.opposite());
+    !(board.color_pieces(color) | enemy_pawn_attacks)
+}
+
+/// The group of mobility weights of a piece type, or None for pawns and kings.
+pub fn mobility_param(piece: Piece) -> Option<Param> {
+    match piece {
+        Piece::Knight => Some(Param::KnightMobility),
+        Piece::Bishop => Some(Param::BishopMobility),
+        Piece::Rook => Some(Param::RookMobility),
+        Piece::Queen => Some(Param::QueenMobility),
+        Piece::Pawn | Piece::King => None,
+    }
+}
+
+/// Evaluates the mobility of `color`'s knights, bishops, rooks and queens, from its point of
+/// view. Sliders see through nothing, so a rook behind its own queen is not rewarded.
+pub fn evaluate_mobility(board: &Board, color: Color) -> Score {
+    mobility_terms(board, color, &mut ())
+}
+
+/// `evaluate_mobility`, reporting the weights used to `tracer`.
+pub fn mobility_terms<T: Tracer>(board: &Board, color: Color, tracer: &mut T) -> Score {
+    let area = mobility_area(board, color);
+    let mut terms = Terms::new(color, tracer);
+    for piece in [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+        let param = mobility_param(piece).expect("minor and major pieces have mobility weights");
+        for square in board.pieces(piece, color).iter_squares() {
+            let moves = fetch_psuedo_legal_moves(piece, square, board.all_pieces) & area;
+            terms.add(param, moves.count() as usize);
+        }
+    }
+    terms.score
+}
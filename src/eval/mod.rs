@@ -0,0 +1,267 @@
+pub mod king_safety;
+pub mod mobility;
+pub mod nnue;
+pub mod params;
+pub mod pawns;
+pub mod trace;
+
+use std::{
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+    slice,
+};
+
+use crate::board::{
+    board::Board,
+    piece::{Color, Piece},
+    square::Square,
+};
+
+use king_safety::evaluate_king_safety;
+use mobility::evaluate_mobility;
+use params::{
+    BACKWARD_PAWN, BISHOP_MOBILITY, CANDIDATE_PAWN, CONNECTED_PAWN, DOUBLED_PAWN, ISOLATED_PAWN, KING_OPEN_FILE, KING_SEMI_OPEN_FILE,
+    KING_ZONE_ATTACK, KNIGHT_MOBILITY, MATERIAL, PASSED_PAWN, PAWN_SHIELD, PST, QUEEN_MOBILITY, ROOK_MOBILITY,
+};
+use pawns::PawnTable;
+
+/// The game phase of the starting position. Each knight and bishop counts 1, each rook 2 and each
+/// queen 4; positions with more material (after promotions) are clamped to this.
+pub const MAX_PHASE: i32 = 24;
+
+/// The phase weight of each piece type, indexed by `Piece::index`.
+pub const PHASE_WEIGHTS: [i32; 6] = [0, 2, 1, 1, 4, 0];
+
+/// A pair of middlegame and endgame values in centipawns. Evaluation terms are summed as pairs and
+/// blended by the game phase at the end, so the engine's judgement changes smoothly as pieces
+/// come off the board.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Score {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Score {
+    pub const ZERO: Score = Score::new(0, 0);
+
+    pub const fn new(mg: i32, eg: i32) -> Score {
+        Score { mg, eg }
+    }
+
+    /// Blends the middlegame and endgame values, from all middlegame at `MAX_PHASE` to all endgame
+    /// at 0.
+    pub fn taper(self, phase: i32) -> i32 {
+        let phase = phase.clamp(0, MAX_PHASE);
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Add for Score {
+    type Output = Score;
+
+    fn add(self, other: Score) -> Score {
+        Score::new(self.mg + other.mg, self.eg + other.eg)
+    }
+}
+
+impl Sub for Score {
+    type Output = Score;
+
+    fn sub(self, other: Score) -> Score {
+        Score::new(self.mg - other.mg, self.eg - other.eg)
+    }
+}
+
+impl Neg for Score {
+    type Output = Score;
+
+    fn neg(self) -> Score {
+        Score::new(-self.mg, -self.eg)
+    }
+}
+
+impl Mul<i32> for Score {
+    type Output = Score;
+
+    fn mul(self, factor: i32) -> Score {
+        Score::new(self.mg * factor, self.eg * factor)
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, other: Score) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Score {
+    fn sub_assign(&mut self, other: Score) {
+        *self = *self - other;
+    }
+}
+
+/// A group of weights in `params`, named after its constant. Evaluation terms add weights by group
+/// and index, so that a `Tracer` can see which weights a position uses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Param {
+    Material,
+    /// The piece-square tables, flattened to `piece * 64 + square`.
+    Pst,
+    PassedPawn,
+    CandidatePawn,
+    ConnectedPawn,
+    IsolatedPawn,
+    DoubledPawn,
+    BackwardPawn,
+    KnightMobility,
+    BishopMobility,
+    RookMobility,
+    QueenMobility,
+    KingZoneAttack,
+    PawnShield,
+    KingSemiOpenFile,
+    KingOpenFile,
+}
+
+impl Param {
+    /// All groups, in the order their constants appear in `params`.
+    pub const ALL: [Param; 16] = [
+        Param::Material,
+        Param::Pst,
+        Param::PassedPawn,
+        Param::CandidatePawn,
+        Param::ConnectedPawn,
+        Param::IsolatedPawn,
+        Param::DoubledPawn,
+        Param::BackwardPawn,
+        Param::KnightMobility,
+        Param::BishopMobility,
+        Param::RookMobility,
+        Param::QueenMobility,
+        Param::KingZoneAttack,
+        Param::PawnShield,
+        Param::KingSemiOpenFile,
+        Param::KingOpenFile,
+    ];
+
+    /// The name of the constant in `params` holding the weights.
+    pub fn name(self) -> &'static str {
+        match self {
+            Param::Material => "MATERIAL",
+            Param::Pst => "PST",
+            Param::PassedPawn => "PASSED_PAWN",
+            Param::CandidatePawn => "CANDIDATE_PAWN",
+            Param::ConnectedPawn => "CONNECTED_PAWN",
+            Param::IsolatedPawn => "ISOLATED_PAWN",
+            Param::DoubledPawn => "DOUBLED_PAWN",
+            Param::BackwardPawn => "BACKWARD_PAWN",
+            Param::KnightMobility => "KNIGHT_MOBILITY",
+            Param::BishopMobility => "BISHOP_MOBILITY",
+            Param::RookMobility => "ROOK_MOBILITY",
+            Param::QueenMobility => "QUEEN_MOBILITY",
+            Param::KingZoneAttack => "KING_ZONE_ATTACK",
+            Param::PawnShield => "PAWN_SHIELD",
+            Param::KingSemiOpenFile => "KING_SEMI_OPEN_FILE",
+            Param::KingOpenFile => "KING_OPEN_FILE",
+        }
+    }
+
+    /// The weights of the group. Single weights are a slice of one.
+    pub fn weights(self) -> &'static [Score] {
+        match self {
+            Param::Material => &MATERIAL,
+            Param::Pst => PST.as_flattened(),
+            Param::PassedPawn => &PASSED_PAWN,
+            Param::CandidatePawn => &CANDIDATE_PAWN,
+            Param::ConnectedPawn => &CONNECTED_PAWN,
+            Param::IsolatedPawn => slice::from_ref(&ISOLATED_PAWN),
+            Param::DoubledPawn => slice::from_ref(&DOUBLED_PAWN),
+            Param::BackwardPawn => slice::from_ref(&BACKWARD_PAWN),
+            Param::KnightMobility => &KNIGHT_MOBILITY,
+            Param::BishopMobility => &BISHOP_MOBILITY,
+            Param::RookMobility => &ROOK_MOBILITY,
+            Param::QueenMobility => &QUEEN_MOBILITY,
+            Param::KingZoneAttack => &KING_ZONE_ATTACK,
+            Param::PawnShield => &PAWN_SHIELD,
+            Param::KingSemiOpenFile => slice::from_ref(&KING_SEMI_OPEN_FILE),
+            Param::KingOpenFile => slice::from_ref(&KING_OPEN_FILE),
+        }
+    }
+}
+
+/// Receives every weight the evaluation terms add, e.g. to express the evaluation as a linear
+/// function of the weights for tuning. The unit type ignores them, which is what the search uses.
+pub trait Tracer {
+    /// Called when the weight at `index` of `param` is added `count` times to `color`'s score.
+    fn record(&mut self, param: Param, index: usize, color: Color, count: i32);
+}
+
+impl Tracer for () {
+    #[inline(always)]
+    fn record(&mut self, _param: Param, _index: usize, _color: Color, _count: i32) {}
+}
+
+/// The score of one color, summed from weights that are each reported to a tracer.
+pub struct Terms<'a, T: Tracer> {
+    pub score: Score,
+    color: Color,
+    tracer: &'a mut T,
+}
+
+impl<'a, T: Tracer> Terms<'a, T> {
+    pub fn new(color: Color, tracer: &'a mut T) -> Self {
+        Terms { score: Score::ZERO, color, tracer }
+    }
+
+    /// Adds the weight at `index` of `param`.
+    pub fn add(&mut self, param: Param, index: usize) {
+        self.add_times(param, index, 1);
+    }
+
+    /// Adds the weight at `index` of `param` `count` times.
+    pub fn add_times(&mut self, param: Param, index: usize, count: i32) {
+        self.score += param.weights()[index] * count;
+        self.tracer.record(param, index, self.color, count);
+    }
+}
+
+/// Material plus piece-square value of every piece on every square, indexed as
+/// `PSQT[color][piece][square]`. Black values are negated, so the sum over all pieces is the score
+/// from white's point of view. `Board` keeps this sum up to date as pieces move.
+static PSQT: [[[Score; 64]; 6]; 2] = {
+    let mut table = [[[Score::ZERO; 64]; 6]; 2];
+    let mut piece = 0;
+    while piece < 6 {
+        let mut square = 0;
+        while square < 64 {
+            let white = PST[piece][square];
+            let black = PST[piece][square ^ 56];
+            let material = MATERIAL[piece];
+            table[0][piece][square] = Score::new(material.mg + white.mg, material.eg + white.eg);
+            table[1][piece][square] = Score::new(-material.mg - black.mg, -material.eg - black.eg);
+            square += 1;
+        }
+        piece += 1;
+    }
+    table
+};
+
+/// The material and piece-square score of a piece, from white's point of view.
+pub fn psqt(piece: Piece, color: Color, square: Square) -> Score {
+    PSQT[color.index()][piece.index()][square.index()]
+}
+
+/// Evaluates the position in centipawns from the side to move's perspective, with the board's
+/// network if it has one. The pawn structure is looked up in, or added to, `pawn_table`.
+pub fn evaluate(board: &Board, pawn_table: &mut PawnTable) -> i32 {
+    if let Some(nnue) = &board.nnue {
+        return nnue.evaluate(board.side_to_move);
+    }
+    let mut score = board.psqt + pawn_table.probe(board).score;
+    score += evaluate_mobility(board, Color::White) - evaluate_mobility(board, Color::Black);
+    score += evaluate_king_safety(board, Color::White) - evaluate_king_safety(board, Color::Black);
+    let score = score.taper(board.phase);
+    match board.side_to_move {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
@@ -0,0 +1,340 @@
+use std::{fmt, fs, io, path::Path, sync::Arc};
+
+use crate::board::{
+    board::Board,
+    piece::{Color, Piece},
+    square::Square,
+};
+
+/// The number of input features: one per piece type, color and square.
+pub const INPUTS: usize = 768;
+
+/// The first bytes of a network file.
+pub const MAGIC: &[u8; 4] = b"KBNN";
+/// The version of the network file format written by `Network::to_bytes`.
+pub const VERSION: u32 = 1;
+
+/// The quantization of the accumulator: its values are clipped to 0..=QA.
+const QA: i32 = 255;
+/// The quantization of the output weights.
+const QB: i32 = 64;
+/// Converts the output of the network to centipawns.
+const SCALE: i32 = 400;
+
+const HEADER_SIZE: usize = 12;
+
+#[derive(Debug)]
+pub enum NetworkError {
+    Io(io::Error),
+    /// The file doesn't start with `MAGIC`.
+    InvalidMagic,
+    UnsupportedVersion(u32),
+    /// The hidden layer is empty or not a multiple of 16 neurons.
+    InvalidHiddenSize(u32),
+    /// The file is shorter or longer than the header says.
+    InvalidSize { expected: usize, actual: usize },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkError::Io(error) => write!(f, "{error}"),
+            NetworkError::InvalidMagic => write!(f, "not a knightbot network file"),
+            NetworkError::UnsupportedVersion(version) => write!(f, "unsupported network version {version}"),
+            NetworkError::InvalidHiddenSize(size) => {
+                write!(f, "invalid hidden layer size {size}, must be a positive multiple of 16")
+            }
+            NetworkError::InvalidSize { expected, actual } => write!(f, "network file is {actual} bytes, expected {expected}"),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+/// A 768→N→1 perspective network. Each side has its own accumulator of N hidden neurons, fed by
+/// the pieces as seen from that side: the board is flipped vertically for black, and "our" pieces
+/// come first. The output layer reads the side to move's accumulator followed by the other one,
+/// clipped to 0..=QA.
+///
+/// The file format, all little-endian:
+///
+/// | Bytes   | Contents                                                                 |
+/// |---------|--------------------------------------------------------------------------|
+/// | 4       | `MAGIC`, "KBNN"                                                          |
+/// | 4       | u32 format version, `VERSION`                                            |
+/// | 4       | u32 hidden layer size N, a multiple of 16                                |
+/// | 768×N×2 | i16 feature weights, N per feature in `feature_index` order, scaled by QA |
+/// | N×2     | i16 hidden biases, scaled by QA                                          |
+/// | 2N×2    | i16 output weights, side to move first, scaled by QB                     |
+/// | 2       | i16 output bias, scaled by QA×QB                                         |
+///
+/// The evaluation is `(output + bias) * 400 / (QA * QB)` centipawns, with QA = 255 and QB = 64.
+#[derive(Clone)]
+pub struct Network {
+    hidden: usize,
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i16,
+}
+
+impl fmt::Debug for Network {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Network").field("hidden", &self.hidden).finish_non_exhaustive()
+    }
+}
+
+fn read_i16s(bytes: &[u8]) -> Vec<i16> {
+    bytes.chunks_exact(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]])).collect()
+}
+
+impl Network {
+    /// Reads a network in the format described on `Network`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Network, NetworkError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(NetworkError::InvalidSize { expected: HEADER_SIZE, actual: bytes.len() });
+        }
+        if &bytes[..4] != MAGIC {
+            return Err(NetworkError::InvalidMagic);
+        }
+        let version = u32::from_le_bytes(bytes[4..8].try_into().expect("the header is long enough"));
+        if version != VERSION {
+            return Err(NetworkError::UnsupportedVersion(version));
+        }
+        let hidden_size = u32::from_le_bytes(bytes[8..12].try_into().expect("the header is long enough"));
+        if hidden_size == 0 || !hidden_size.is_multiple_of(16) {
+            return Err(NetworkError::InvalidHiddenSize(hidden_size));
+        }
+
+        let hidden = hidden_size as usize;
+        let expected = HEADER_SIZE + 2 * (INPUTS * hidden + hidden + 2 * hidden + 1);
+        if bytes.len() != expected {
+            return Err(NetworkError::InvalidSize { expected, actual: bytes.len() });
+        }
+        let values = read_i16s(&bytes[HEADER_SIZE..]);
+        let (feature_weights, rest) = values.split_at(INPUTS * hidden);
+        let (feature_biases, rest) = rest.split_at(hidden);
+        let (output_weights, rest) = rest.split_at(2 * hidden);
+        Ok(Network {
+            hidden,
+            feature_weights: feature_weights.to_vec(),
+            feature_biases: feature_biases.to_vec(),
+            output_weights: output_weights.to_vec(),
+            output_bias: rest[0],
+        })
+    }
+
+    /// Loads a network file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Network, NetworkError> {
+        Network::from_bytes(&fs::read(path).map_err(NetworkError::Io)?)
+    }
+
+    /// Writes the network in the format described on `Network`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + 2 * (INPUTS * self.hidden + 3 * self.hidden + 1));
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.hidden as u32).to_le_bytes());
+        for value in self.feature_weights.iter().chain(&self.feature_biases).chain(&self.output_weights) {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.output_bias.to_le_bytes());
+        bytes
+    }
+
+    /// The number of neurons in the hidden layer.
+    pub fn hidden_size(&self) -> usize {
+        self.hidden
+    }
+
+    fn feature_weights(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden]
+    }
+}
+
+/// The input feature of a piece as seen from `perspective`'s side of the board.
+pub fn feature_index(perspective: Color, piece: Piece, color: Color, square: Square) -> usize {
+    let (side, square) = match perspective {
+        Color::White => (color.index(), square.index()),
+        Color::Black => (color.opposite().index(), square.index() ^ 56),
+    };
+    side * 384 + piece.index() * 64 + square
+}
+
+/// The hidden layer of both perspectives, indexed by `Color::index`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Accumulator {
+    pub values: [Vec<i16>; 2],
+}
+
+/// A network and the accumulator of a position, kept up to date by `Board::put_piece` and
+/// `Board::remove_piece` so that making or unmaking a move only touches the pieces that moved.
+#[derive(Clone, Debug)]
+pub struct Nnue {
+    pub network: Arc<Network>,
+    pub accumulator: Accumulator,
+}
+
+impl Nnue {
+    /// Creates the accumulator of a position from scratch.
+    pub fn new(network: Arc<Network>, board: &Board) -> Nnue {
+        let mut nnue = Nnue {
+            accumulator: Accumulator { values: [network.feature_biases.clone(), network.feature_biases.clone()] },
+            network,
+        };
+        nnue.refresh(board);
+        nnue
+    }
+
+    /// Recomputes the accumulator from the pieces on the board.
+    pub fn refresh(&mut self, board: &Board) {
+        for values in &mut self.accumulator.values {
+            values.copy_from_slice(&self.network.feature_biases);
+        }
+        for color in [Color::White, Color::Black] {
+            for piece in Piece::ALL {
+                for square in board.pieces(piece, color).iter_squares() {
+                    self.add_piece(piece, color, square);
+                }
+            }
+        }
+    }
+
+    pub fn add_piece(&mut self, piece: Piece, color: Color, square: Square) {
+        for perspective in [Color::White, Color::Black] {
+            let weights = self.network.feature_weights(feature_index(perspective, piece, color, square));
+            add_assign(&mut self.accumulator.values[perspective.index()], weights);
+        }
+    }
+
+    pub fn remove_piece(&mut self, piece: Piece, color: Color, square: Square) {
+        for perspective in [Color::White, Color::Black] {
+            let weights = self.network.feature_weights(feature_index(perspective, piece, color, square));
+            sub_assign(&mut self.accumulator.values[perspective.index()], weights);
+        }
+    }
+
+    /// The evaluation in centipawns from the side to move's point of view.
+    pub fn evaluate(&self, side_to_move: Color) -> i32 {
+        let hidden = self.network.hidden;
+        let ours = &self.accumulator.values[side_to_move.index()];
+        let theirs = &self.accumulator.values[side_to_move.opposite().index()];
+        let output = dot_clipped(ours, &self.network.output_weights[..hidden])
+            + dot_clipped(theirs, &self.network.output_weights[hidden..]);
+        (output + self.network.output_bias as i32) * SCALE / (QA * QB)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn has_avx2() -> bool {
+    is_x86_feature_detected!("avx2")
+}
+
+fn add_assign(values: &mut [i16], weights: &[i16]) {
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // SAFETY: AVX2 is available, and the hidden size is a multiple of 16.
+        return unsafe { avx2::add_assign(values, weights) };
+    }
+    scalar::add_assign(values, weights)
+}
+
+fn sub_assign(values: &mut [i16], weights: &[i16]) {
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // SAFETY: AVX2 is available, and the hidden size is a multiple of 16.
+        return unsafe { avx2::sub_assign(values, weights) };
+    }
+    scalar::sub_assign(values, weights)
+}
+
+fn dot_clipped(values: &[i16], weights: &[i16]) -> i32 {
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // SAFETY: AVX2 is available, and the hidden size is a multiple of 16.
+        return unsafe { avx2::dot_clipped(values, weights) };
+    }
+    scalar::dot_clipped(values, weights)
+}
+
+/// The network arithmetic in plain Rust, used when no SIMD instructions are available.
+pub mod scalar {
+    use super::QA;
+
+    pub fn add_assign(values: &mut [i16], weights: &[i16]) {
+        for (value, weight) in values.iter_mut().zip(weights) {
+            *value = value.wrapping_add(*weight);
+        }
+    }
+
+    pub fn sub_assign(values: &mut [i16], weights: &[i16]) {
+        for (value, weight) in values.iter_mut().zip(weights) {
+            *value = value.wrapping_sub(*weight);
+        }
+    }
+
+    /// The dot product of the values, clipped to 0..=QA, and the weights.
+    pub fn dot_clipped(values: &[i16], weights: &[i16]) -> i32 {
+        values.iter().zip(weights).map(|(&value, &weight)| (value as i32).clamp(0, QA) * weight as i32).sum()
+    }
+}
+
+/// The network arithmetic with AVX2, 16 neurons at a time. Gives the same results as `scalar`.
+#[cfg(target_arch = "x86_64")]
+pub mod avx2 {
+    use std::arch::x86_64::*;
+
+    use super::QA;
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2, and both slices must have the same length, a multiple of 16.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn add_assign(values: &mut [i16], weights: &[i16]) {
+        debug_assert!(values.len() == weights.len() && values.len().is_multiple_of(16));
+        for (value, weight) in values.chunks_exact_mut(16).zip(weights.chunks_exact(16)) {
+            let sum = _mm256_add_epi16(
+                _mm256_loadu_si256(value.as_ptr() as *const __m256i),
+                _mm256_loadu_si256(weight.as_ptr() as *const __m256i),
+            );
+            _mm256_storeu_si256(value.as_mut_ptr() as *mut __m256i, sum);
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2, and both slices must have the same length, a multiple of 16.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn sub_assign(values: &mut [i16], weights: &[i16]) {
+        debug_assert!(values.len() == weights.len() && values.len().is_multiple_of(16));
+        for (value, weight) in values.chunks_exact_mut(16).zip(weights.chunks_exact(16)) {
+            let difference = _mm256_sub_epi16(
+                _mm256_loadu_si256(value.as_ptr() as *const __m256i),
+                _mm256_loadu_si256(weight.as_ptr() as *const __m256i),
+            );
+            _mm256_storeu_si256(value.as_mut_ptr() as *mut __m256i, difference);
+        }
+    }
+
+    /// The dot product of the values, clipped to 0..=QA, and the weights.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2, and both slices must have the same length, a multiple of 16.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn dot_clipped(values: &[i16], weights: &[i16]) -> i32 {
+        debug_assert!(values.len() == weights.len() && values.len().is_multiple_of(16));
+        let zero = _mm256_setzero_si256();
+        let max = _mm256_set1_epi16(QA as i16);
+        let mut sum = _mm256_setzero_si256();
+        for (value, weight) in values.chunks_exact(16).zip(weights.chunks_exact(16)) {
+            let clipped = _mm256_min_epi16(_mm256_max_epi16(_mm256_loadu_si256(value.as_ptr() as *const __m256i), zero), max);
+            let products = _mm256_madd_epi16(clipped, _mm256_loadu_si256(weight.as_ptr() as *const __m256i));
+            sum = _mm256_add_epi32(sum, products);
+        }
+        let halves = _mm_add_epi32(_mm256_castsi256_si128(sum), _mm256_extracti128_si256(sum, 1));
+        let pairs = _mm_add_epi32(halves, _mm_shuffle_epi32(halves, 0b01_00_11_10));
+        let total = _mm_add_epi32(pairs, _mm_shuffle_epi32(pairs, 0b10_11_00_01));
+        _mm_cvtsi128_si32(total)
+    }
+}
@@ -0,0 +1,204 @@
+use crate::board::{
+    bitboard::BitBoard,
+    board::Board,
+    movegen::pawn_attacks,
+    piece::{Color, Piece},
+    square::Square,
+};
+
+use super::{Param, Score, Terms, Tracer};
+
+/// The number of entries of a pawn hash table. Pawn structures change rarely during a search, so
+/// a small table already hits almost every time.
+const PAWN_TABLE_SIZE: usize = 1 << 14;
+
+/// Bitboard masks for evaluating pawns, all indexed by `[color][square]` and looking in the
+/// direction the pawns of that color move.
+struct PawnMasks {
+    /// The squares in front of the square on the same file.
+    front_span: [[BitBoard; 64]; 2],
+    /// The squares in front of the square on the adjacent files, which pawns on the square can
+    /// attack as they advance.
+    attack_span: [[BitBoard; 64]; 2],
+    /// The squares on the adjacent files not in front of the square. Friendly pawns there can
+    /// defend a pawn on the square by advancing.
+    support_span: [[BitBoard; 64]; 2],
+}
+
+const fn file_mask(file: i32) -> u64 {
+    if file < 0 || file > 7 {
+        0
+    } else {
+        0x0101010101010101 << file
+    }
+}
+
+/// The ranks in front of `rank` as seen by `color`.
+const fn forward_ranks(color: usize, rank: i32) -> u64 {
+    if color == 0 {
+        if rank == 7 { 0 } else { u64::MAX << ((rank + 1) * 8) }
+    } else if rank == 0 {
+        0
+    } else {
+        u64::MAX >> ((8 - rank) * 8)
+    }
+}
+
+static PAWN_MASKS: PawnMasks = {
+    let mut masks = PawnMasks {
+        front_span: [[BitBoard(0); 64]; 2],
+        attack_span: [[BitBoard(0); 64]; 2],
+        support_span: [[BitBoard(0); 64]; 2],
+    };
+    let mut color = 0;
+    while color < 2 {
+        let mut square = 0;
+        while square < 64 {
+            let (file, rank) = ((square % 8) as i32, (square / 8) as i32);
+            let forward = forward_ranks(color, rank);
+            let adjacent = file_mask(file - 1) | file_mask(file + 1);
+            masks.front_span[color][square] = BitBoard(forward & file_mask(file));
+            masks.attack_span[color][square] = BitBoard(forward & adjacent);
+            masks.support_span[color][square] = BitBoard(!forward & adjacent);
+            square += 1;
+        }
+        color += 1;
+    }
+    masks
+};
+
+/// The squares a pawn must pass to promote, on its own and the adjacent files. A pawn is passed
+/// if there are no enemy pawns in this mask.
+pub fn passed_pawn_mask(square: Square, color: Color) -> BitBoard {
+    PAWN_MASKS.front_span[color.index()][square.index()] | PAWN_MASKS.attack_span[color.index()][square.index()]
+}
+
+/// The rank of the square as seen by `color`, counting from 0 on its back rank.
+pub fn relative_rank(square: Square, color: Color) -> usize {
+    match color {
+        Color::White => square.rank() as usize,
+        Color::Black => 7 - square.rank() as usize,
+    }
+}
+
+/// All squares attacked by the given pawns of `color`.
+pub fn pawn_attack_map(pawns: BitBoard, color: Color) -> BitBoard {
+    match color {
+        Color::White => ((pawns & !BitBoard::FILE_A) << 7) | ((pawns & !BitBoard::FILE_H) << 9),
+        Color::Black => ((pawns & !BitBoard::FILE_H) >> 7) | ((pawns & !BitBoard::FILE_A) >> 9),
+    }
+}
+
+/// The rank of the square as a bitboard.
+fn rank_mask(square: Square) -> BitBoard {
+    BitBoard(0xFF << (square.rank() * 8))
+}
+
+/// The evaluation of a pawn structure, as stored in the pawn hash table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PawnEntry {
+    /// The pawn hash the entry belongs to, see `Board::pawn_hash`.
+    pub key: u64,
+    /// The score of the pawn structure from white's point of view.
+    pub score: Score,
+    /// The passed pawns of each color, indexed by `Color::index`.
+    pub passed: [BitBoard; 2],
+}
+
+/// Evaluates the pawns of one color: passed, candidate, connected, isolated, doubled and backward
+/// pawns. Returns the score from that color's point of view and its passed pawns.
+pub fn evaluate_pawn_structure(board: &Board, us: Color) -> (Score, BitBoard) {
+    pawn_structure_terms(board, us, &mut ())
+}
+
+/// `evaluate_pawn_structure`, reporting the weights used to `tracer`.
+pub fn pawn_structure_terms<T: Tracer>(board: &Board, us: Color, tracer: &mut T) -> (Score, BitBoard) {
+    let them = us.opposite();
+    let our_pawns = board.pieces(Piece::Pawn, us);
+    let their_pawns = board.pieces(Piece::Pawn, them);
+    let mut terms = Terms::new(us, tracer);
+    let mut passed = BitBoard::EMPTY;
+
+    for square in our_pawns.iter_squares() {
+        let rank = relative_rank(square, us);
+        let front_span = PAWN_MASKS.front_span[us.index()][square.index()];
+        let attack_span = PAWN_MASKS.attack_span[us.index()][square.index()];
+        let support_span = PAWN_MASKS.support_span[us.index()][square.index()];
+        let adjacent_files = attack_span | support_span;
+
+        // Only the frontmost of doubled pawns can be passed.
+        let doubled = !(front_span & our_pawns).is_empty();
+        let blocked = !(front_span & their_pawns).is_empty();
+        let sentries = attack_span & their_pawns;
+        let supporters = support_span & our_pawns;
+
+        if doubled {
+            terms.add(Param::DoubledPawn, 0);
+        }
+        if !doubled && !blocked && sentries.is_empty() {
+            terms.add(Param::PassedPawn, rank);
+            passed |= BitBoard::from(square);
+        } else if !blocked && supporters.count() >= sentries.count() {
+            terms.add(Param::CandidatePawn, rank);
+        }
+
+        let defenders = pawn_attacks(square, them) & our_pawns;
+        let phalanx = adjacent_files & our_pawns & rank_mask(square);
+        if !defenders.is_empty() || !phalanx.is_empty() {
+            terms.add(Param::ConnectedPawn, rank);
+        }
+
+        if (adjacent_files & our_pawns).is_empty() {
+            terms.add(Param::IsolatedPawn, 0);
+        } else if supporters.is_empty() && rank < 6 {
+            let stop = Square::from_index(match us {
+                Color::White => square as u8 + 8,
+                Color::Black => square as u8 - 8,
+            });
+            if !(pawn_attacks(stop, us) & their_pawns).is_empty() {
+                terms.add(Param::BackwardPawn, 0);
+            }
+        }
+    }
+    (terms.score, passed)
+}
+
+/// Evaluates the pawn structure of both sides from scratch.
+pub fn evaluate_pawns(board: &Board) -> PawnEntry {
+    let (white_score, white_passed) = evaluate_pawn_structure(board, Color::White);
+    let (black_score, black_passed) = evaluate_pawn_structure(board, Color::Black);
+    PawnEntry { key: board.pawn_hash, score: white_score - black_score, passed: [white_passed, black_passed] }
+}
+
+/// A cache of pawn structure evaluations, indexed by the pawn hash. Each search thread has its own.
+#[derive(Clone, Debug)]
+pub struct PawnTable {
+    entries: Vec<PawnEntry>,
+}
+
+impl PawnTable {
+    pub fn new() -> Self {
+        PawnTable { entries: vec![PawnEntry::default(); PAWN_TABLE_SIZE] }
+    }
+
+    /// Returns the evaluation of the board's pawn structure, computing and storing it on a miss.
+    pub fn probe(&mut self, board: &Board) -> PawnEntry {
+        let index = board.pawn_hash as usize & (self.entries.len() - 1);
+        let entry = &mut self.entries[index];
+        // A position without pawns has a pawn hash of 0 and scores 0, just like an empty entry.
+        if entry.key != board.pawn_hash {
+            *entry = evaluate_pawns(board);
+        }
+        *entry
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(PawnEntry::default());
+    }
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
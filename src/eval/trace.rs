@@ -0,0 +1,128 @@
+use std::fmt;
+
+use crate::board::{
+    board::Board,
+    piece::{Color, Piece},
+    utils::board_diagram,
+};
+
+use super::{
+    king_safety::king_safety_terms, mobility::mobility_terms, pawns::pawn_structure_terms, Param, Score, Terms, Tracer, MAX_PHASE,
+};
+
+/// The evaluation of a position broken down into its terms. Each term holds the score of both
+/// colors from their own point of view, indexed by `Color::index`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EvalTrace {
+    pub material: [Score; 2],
+    pub psqt: [Score; 2],
+    pub pawns: [Score; 2],
+    pub mobility: [Score; 2],
+    pub king_safety: [Score; 2],
+    /// The game phase used to blend the middlegame and endgame scores.
+    pub phase: i32,
+}
+
+impl EvalTrace {
+    /// The names and scores of all terms, in the order they are printed.
+    pub fn terms(&self) -> [(&'static str, [Score; 2]); 5] {
+        [
+            ("Material", self.material),
+            ("Piece-square", self.psqt),
+            ("Pawns", self.pawns),
+            ("Mobility", self.mobility),
+            ("King safety", self.king_safety),
+        ]
+    }
+
+    /// The sum of all terms of one color, from its point of view.
+    pub fn color_total(&self, color: Color) -> Score {
+        self.terms().iter().fold(Score::ZERO, |total, (_, scores)| total + scores[color.index()])
+    }
+
+    /// The sum of all terms from white's point of view, before tapering.
+    pub fn total(&self) -> Score {
+        self.color_total(Color::White) - self.color_total(Color::Black)
+    }
+
+    /// The tapered evaluation in centipawns from white's point of view.
+    pub fn score(&self) -> i32 {
+        self.total().taper(self.phase)
+    }
+}
+
+/// Evaluates the position term by term, without using the incrementally updated scores or the
+/// pawn hash table. The sum of the terms equals `evaluate`.
+pub fn eval_trace(board: &Board) -> EvalTrace {
+    eval_trace_with(board, &mut ())
+}
+
+/// `eval_trace`, reporting every weight used to `tracer`.
+pub fn eval_trace_with<T: Tracer>(board: &Board, tracer: &mut T) -> EvalTrace {
+    let mut trace = EvalTrace {
+        material: [Score::ZERO; 2],
+        psqt: [Score::ZERO; 2],
+        pawns: [Score::ZERO; 2],
+        mobility: [Score::ZERO; 2],
+        king_safety: [Score::ZERO; 2],
+        phase: board.phase,
+    };
+    for color in [Color::White, Color::Black] {
+        let index = color.index();
+        let mut material = Terms::new(color, tracer);
+        for piece in Piece::ALL {
+            material.add_times(Param::Material, piece.index(), board.pieces(piece, color).count() as i32);
+        }
+        trace.material[index] = material.score;
+
+        let mut psqt = Terms::new(color, tracer);
+        for piece in Piece::ALL {
+            for square in board.pieces(piece, color).iter_squares() {
+                let square = if color == Color::White { square } else { square.flip() };
+                psqt.add(Param::Pst, piece.index() * 64 + square.index());
+            }
+        }
+        trace.psqt[index] = psqt.score;
+
+        trace.pawns[index] = pawn_structure_terms(board, color, tracer).0;
+        trace.mobility[index] = mobility_terms(board, color, tracer);
+        trace.king_safety[index] = king_safety_terms(board, color, tracer);
+    }
+    trace
+}
+
+impl fmt::Display for EvalTrace {
+    /// Formats the terms as a table of middlegame and endgame scores for each color and their
+    /// difference, followed by the tapered evaluation.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<14} {:>13}   {:>13}   {:>13}", "Term", "White", "Black", "Total")?;
+        writeln!(f, "{:<14} {:>6} {:>6}   {:>6} {:>6}   {:>6} {:>6}", "", "MG", "EG", "MG", "EG", "MG", "EG")?;
+        let totals = [("Total", [self.color_total(Color::White), self.color_total(Color::Black)])];
+        for (name, [white, black]) in self.terms().iter().chain(&totals) {
+            let difference = *white - *black;
+            writeln!(
+                f,
+                "{name:<14} {:>6} {:>6}   {:>6} {:>6}   {:>6} {:>6}",
+                white.mg, white.eg, black.mg, black.eg, difference.mg, difference.eg
+            )?;
+        }
+        write!(f, "Phase {}/{MAX_PHASE}, evaluation {:+} (white's point of view)", self.phase.min(MAX_PHASE), self.score())
+    }
+}
+
+/// The board diagram next to the evaluation breakdown, as printed by the "eval" command.
+pub fn eval_report(board: &Board) -> String {
+    let diagram = board_diagram(board);
+    let trace = eval_trace(board).to_string();
+    let table: Vec<&str> = trace.lines().collect();
+    let width = diagram.iter().map(String::len).max().unwrap_or(0);
+    let mut report = String::new();
+    for i in 0..diagram.len().max(table.len()) {
+        let left = diagram.get(i).map_or("", String::as_str);
+        let right = table.get(i).copied().unwrap_or("");
+        report.push_str(format!("{left:width$}   {right}").trim_end());
+        report.push('\n');
+    }
+    report.push_str(&format!("FEN: {}", board.to_fen()));
+    report
+}
@@ -1 +1,58 @@
-pub mod board;
\ No newline at end of file
+//! knightbot, a bitboard chess engine that can be embedded as a library.
+//!
+//! The types most programs need are re-exported here: `Board` with `Move`, `Square`, `BitBoard`,
+//! `Piece` and `Color` to set up and play through positions, the `movegen` functions to list
+//! moves, and `Searcher` or the multithreaded `ThreadPool` to search.
+//!
+//! # Stability
+//!
+//! The re-exports below, the `board` module's types and notation (FEN, SAN, `Board::diagram`),
+//! and the `pgn` and `book` readers are the supported interface. The fields of `Board` are public
+//! for the engine's own use; read positions through its methods instead. The remaining modules
+//! hold the engine internals, the UCI and xboard front ends the `knightbot` binary runs, and the
+//! tuning, data generation and match tools. They are public so the binary, tests and benchmarks
+//! can reach them, are hidden from the documentation, and may change in any release.
+//!
+//! # Example
+//!
+//! ```
+//! use knightbot::{generate_legal_moves, Board, SearchLimits, Searcher};
+//!
+//! let mut board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+//! assert_eq!(generate_legal_moves(&board).len(), 17);
+//!
+//! let mut searcher = Searcher::new(16);
+//! let result = searcher.search(&mut board, SearchLimits { depth: Some(3), ..SearchLimits::default() });
+//! assert_eq!(result.best_move.unwrap().to_string(), "a1a8");
+//! ```
+
+pub mod board;
+pub mod book;
+#[doc(hidden)]
+pub mod datagen;
+#[doc(hidden)]
+pub mod epd;
+#[doc(hidden)]
+pub mod eval;
+pub mod pgn;
+pub mod search;
+#[doc(hidden)]
+pub mod tournament;
+#[doc(hidden)]
+pub mod tune;
+#[doc(hidden)]
+pub mod uci;
+#[doc(hidden)]
+pub mod xboard;
+
+pub use board::{
+    bitboard::BitBoard,
+    board::{Board, CastlingRights, GameOutcome},
+    fen::{FenError, START_FEN},
+    movegen::{self, generate_legal_moves, is_legal, perft},
+    moves::Move,
+    piece::{Color, Piece},
+    square::Square,
+    validate::PositionError,
+};
+pub use search::{smp::ThreadPool, SearchConfig, SearchLimits, SearchResult, Searcher};
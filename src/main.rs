@@ -1,23 +1,288 @@
-mod board;
+use std::{
+    env, fs,
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    process,
+    time::Duration,
+};
 
-use std::time::Instant;
+use knightbot::book::builder::{BookBuilder, BookBuilderConfig};
+use knightbot::datagen::{generate, DataFormat, DatagenConfig};
+use knightbot::epd::{read_epd, run_suite};
+use knightbot::eval::trace::eval_report;
+use knightbot::pgn::PgnReader;
+use knightbot::tournament::{self, match_report, stats::Sprt, MatchConfig, TimeControl};
+use knightbot::tune::{loss, read_positions, write_params, Tuner};
+use knightbot::uci::UciEngine;
+use knightbot::xboard::XBoardEngine;
+use knightbot::{Board, SearchLimits, ThreadPool};
+
+const USAGE: &str = "usage: knightbot [epd <file> [depth <plies>] [movetime <ms>] [threads <n>] [hash <mb>] | eval [<fen>] \
+                     | tune <file> [epochs <n>] [rate <r>] [output <file>] \
+                     | datagen <file> [games <n>] [threads <n>] [nodes <n>] [plies <n>] [max-plies <n>] [seed <n>] \
+                     [hash <mb>] [format binary|text] | book <pgn> <output> [min-games <n>] [max-ply <n>] \
+                     | match <engine> <engine> [openings <epd>] [games <n>] [concurrency <n>] [tc <base+inc>] \
+                     [movetime <ms>] [nodes <n>] [hash <mb>] [max-plies <n>] [sprt <elo0>,<elo1>] [alpha <a>] [beta <b>]]";
 
-use board::bitboard::BitBoard;
-use board::board::Board;
-use board::movegen::{get_attack_mask_for_bishop, get_attack_mask_for_queen, get_attack_mask_for_rook};
-use board::square::Square;
-use board::tables::{generate_rank_attack_table, precompute_masks};
-use board::utils::print_bitboard;
 fn main() {
-    let board = Board::new();
-    let rank_attacks = generate_rank_attack_table();
-    let precomputed_masks = precompute_masks(); 
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => run_protocol(),
+        Some("epd") => run_epd(&args[1..]),
+        Some("eval") => run_eval(&args[1..]),
+        Some("tune") => run_tune(&args[1..]),
+        Some("datagen") => run_datagen(&args[1..]),
+        Some("book") => run_book(&args[1..]),
+        Some("match") => run_match(&args[1..]),
+        Some(_) => {
+            eprintln!("{USAGE}");
+            process::exit(2);
+        }
+    }
+}
+
+/// Speaks UCI or XBoard on stdin and stdout. The first command selects the protocol: XBoard
+/// sends "xboard", anything else is UCI.
+fn run_protocol() {
+    let mut first_line = String::new();
+    if io::stdin().read_line(&mut first_line).is_err() {
+        return;
+    }
+    if first_line.trim() == "xboard" {
+        let mut engine = XBoardEngine::new();
+        if engine.handle_command(&first_line) {
+            engine.run();
+        }
+    } else {
+        let mut engine = UciEngine::new();
+        if engine.handle_command(&first_line) {
+            engine.run();
+        }
+    }
+}
+
+/// Runs an EPD test suite and prints the result of every position and the totals. Without a
+/// limit, each position is searched for one second.
+fn run_epd(args: &[String]) {
+    let fail = |message: String| -> ! {
+        eprintln!("{message}\n{USAGE}");
+        process::exit(2);
+    };
+    let Some(path) = args.first() else { fail("missing EPD file".to_string()) };
+    let mut limits = SearchLimits::default();
+    let (mut threads, mut hash) = (1, 16);
+    for pair in args[1..].chunks(2) {
+        let [name, value] = pair else { fail(format!("missing value for {}", pair[0])) };
+        let value: u64 = value.parse().unwrap_or_else(|_| fail(format!("invalid value for {name}: {value}")));
+        match name.as_str() {
+            "depth" => limits.depth = Some(value as i32),
+            "movetime" => limits.movetime = Some(Duration::from_millis(value)),
+            "threads" => threads = value as usize,
+            "hash" => hash = value as usize,
+            _ => fail(format!("unknown option {name}")),
+        }
+    }
+    if limits.depth.is_none() && limits.movetime.is_none() {
+        limits.movetime = Some(Duration::from_secs(1));
+    }
+
+    let file = File::open(path).unwrap_or_else(|error| fail(format!("can't open {path}: {error}")));
+    let entries = read_epd(BufReader::new(file)).unwrap_or_else(|error| fail(format!("{path}: {error}")));
+    let mut pool = ThreadPool::new(threads, hash);
+    let report = run_suite(&mut pool, &entries, limits, |result| {
+        eprintln!("{}: {}", result.name, if result.solved { "solved" } else { "failed" });
+    });
+    println!("{report}");
+}
+
+/// Prints the evaluation breakdown of a position given as FEN, or of the starting position.
+fn run_eval(args: &[String]) {
+    let board = if args.is_empty() {
+        Board::new()
+    } else {
+        Board::from_fen(&args.join(" ")).unwrap_or_else(|error| {
+            eprintln!("invalid FEN: {error}\n{USAGE}");
+            process::exit(2);
+        })
+    };
+    if let Err(error) = board.validate() {
+        eprintln!("invalid position: {error}\n{USAGE}");
+        process::exit(2);
+    }
+    println!("{}", eval_report(&board));
+}
+
+/// Tunes the evaluation weights on a file of positions labelled with game results and writes the
+/// tuned `eval::params` source to the output file, or to stdout.
+fn run_tune(args: &[String]) {
+    let fail = |message: String| -> ! {
+        eprintln!("{message}\n{USAGE}");
+        process::exit(2);
+    };
+    let Some(path) = args.first() else { fail("missing file of labelled positions".to_string()) };
+    let (mut epochs, mut rate, mut output) = (1000, 1.0, None);
+    for pair in args[1..].chunks(2) {
+        let [name, value] = pair else { fail(format!("missing value for {}", pair[0])) };
+        match name.as_str() {
+            "epochs" => epochs = value.parse().unwrap_or_else(|_| fail(format!("invalid value for {name}: {value}"))),
+            "rate" => rate = value.parse().unwrap_or_else(|_| fail(format!("invalid value for {name}: {value}"))),
+            "output" => output = Some(value.clone()),
+            _ => fail(format!("unknown option {name}")),
+        }
+    }
+
+    let file = File::open(path).unwrap_or_else(|error| fail(format!("can't open {path}: {error}")));
+    let positions = read_positions(BufReader::new(file)).unwrap_or_else(|error| fail(format!("{path}: {error}")));
+    let mut tuner = Tuner::new(&positions, rate);
+    eprintln!("{} positions, K = {:.4}, loss {:.6}", positions.len(), tuner.k, loss(&positions, &tuner.weights, tuner.k));
+    for epoch in 1..=epochs {
+        let loss = tuner.epoch(&positions);
+        if epoch % 50 == 0 || epoch == epochs {
+            eprintln!("epoch {epoch}: loss {loss:.6}");
+        }
+    }
+
+    let source = write_params(&tuner.weights);
+    match output {
+        Some(output) => fs::write(&output, source).unwrap_or_else(|error| fail(format!("can't write {output}: {error}"))),
+        None => print!("{source}"),
+    }
+}
+
+/// Generates training positions from self-play games and writes them to a file.
+fn run_datagen(args: &[String]) {
+    let fail = |message: String| -> ! {
+        eprintln!("{message}\n{USAGE}");
+        process::exit(2);
+    };
+    let Some(path) = args.first() else { fail("missing output file".to_string()) };
+    let mut config = DatagenConfig::default();
+    for pair in args[1..].chunks(2) {
+        let [name, value] = pair else { fail(format!("missing value for {}", pair[0])) };
+        if name == "format" {
+            config.format = match value.as_str() {
+                "binary" => DataFormat::Binary,
+                "text" => DataFormat::Text,
+                _ => fail(format!("unknown format {value}")),
+            };
+            continue;
+        }
+        let value: u64 = value.parse().unwrap_or_else(|_| fail(format!("invalid value for {name}: {value}")));
+        match name.as_str() {
+            "games" => config.games = value as usize,
+            "threads" => config.threads = value as usize,
+            "nodes" => config.nodes = value,
+            "plies" => config.random_plies = value as usize,
+            "max-plies" => config.max_plies = value as usize,
+            "seed" => config.seed = value,
+            "hash" => config.hash_mb = value as usize,
+            _ => fail(format!("unknown option {name}")),
+        }
+    }
+
+    let file = File::create(path).unwrap_or_else(|error| fail(format!("can't create {path}: {error}")));
+    let mut output = BufWriter::new(file);
+    let summary = generate(&config, &mut output, |summary| {
+        eprintln!("game {}/{}: {} positions", summary.games, config.games, summary.positions);
+    })
+    .unwrap_or_else(|error| fail(format!("can't write {path}: {error}")));
+    println!("{} games, {} positions written to {path}", summary.games, summary.positions);
+}
+
+/// Builds a Polyglot opening book from the games of a PGN file.
+fn run_book(args: &[String]) {
+    let fail = |message: String| -> ! {
+        eprintln!("{message}\n{USAGE}");
+        process::exit(2);
+    };
+    let [pgn_path, output, options @ ..] = args else { fail("missing PGN or output file".to_string()) };
+    let mut config = BookBuilderConfig::default();
+    for pair in options.chunks(2) {
+        let [name, value] = pair else { fail(format!("missing value for {}", pair[0])) };
+        let value: u64 = value.parse().unwrap_or_else(|_| fail(format!("invalid value for {name}: {value}")));
+        match name.as_str() {
+            "min-games" => config.min_games = value as u32,
+            "max-ply" => config.max_ply = value as usize,
+            _ => fail(format!("unknown option {name}")),
+        }
+    }
+
+    let file = File::open(pgn_path).unwrap_or_else(|error| fail(format!("can't open {pgn_path}: {error}")));
+    let mut reader = PgnReader::new(BufReader::new(file));
+    let mut builder = BookBuilder::new(config);
+    let mut games = 0;
+    loop {
+        match reader.read_game() {
+            Ok(Some(game)) => {
+                builder.add_game(&game);
+                games += 1;
+            }
+            Ok(None) => break,
+            Err(error) => eprintln!("{pgn_path}: {error}"),
+        }
+    }
+    let book = builder.build();
+    fs::write(output, book.to_bytes()).unwrap_or_else(|error| fail(format!("can't write {output}: {error}")));
+    println!("{games} games, {} book entries written to {output}", book.len());
+}
 
-    let square = Square::A4; 
-    let full_board = board.all_pieces;
+/// Plays a match between two engines, each "knightbot[:<settings>]" or the path of a UCI engine,
+/// and prints the score, the Elo difference and the SPRT verdict.
+fn run_match(args: &[String]) {
+    let fail = |message: String| -> ! {
+        eprintln!("{message}\n{USAGE}");
+        process::exit(2);
+    };
+    let [first, second, options @ ..] = args else { fail("missing engines".to_string()) };
+    let mut config = MatchConfig::default();
+    for (engine, spec) in config.engines.iter_mut().zip([first, second]) {
+        *engine = spec.parse().unwrap_or_else(|error: String| fail(error));
+    }
+    let (mut sprt, mut alpha, mut beta) = (None, None, None);
+    for pair in options.chunks(2) {
+        let [name, value] = pair else { fail(format!("missing value for {}", pair[0])) };
+        let invalid = || -> ! { fail(format!("invalid value for {name}: {value}")) };
+        match name.as_str() {
+            "openings" => {
+                let file = File::open(value).unwrap_or_else(|error| fail(format!("can't open {value}: {error}")));
+                let entries = read_epd(BufReader::new(file)).unwrap_or_else(|error| fail(format!("{value}: {error}")));
+                config.openings = entries.into_iter().map(|entry| entry.board).collect();
+            }
+            "tc" => config.time_control = value.parse().unwrap_or_else(|error: String| fail(error)),
+            "movetime" => config.time_control = TimeControl::MoveTime(Duration::from_millis(value.parse().unwrap_or_else(|_| invalid()))),
+            "nodes" => config.time_control = TimeControl::Nodes(value.parse().unwrap_or_else(|_| invalid())),
+            "games" => config.games = value.parse().unwrap_or_else(|_| invalid()),
+            "concurrency" => config.concurrency = value.parse().unwrap_or_else(|_| invalid()),
+            "hash" => config.hash_mb = value.parse().unwrap_or_else(|_| invalid()),
+            "max-plies" => config.max_plies = value.parse().unwrap_or_else(|_| invalid()),
+            "sprt" => {
+                let (elo0, elo1) = value.split_once(',').unwrap_or_else(|| invalid());
+                sprt = Some((elo0.parse().unwrap_or_else(|_| invalid()), elo1.parse().unwrap_or_else(|_| invalid())));
+            }
+            "alpha" => alpha = Some(value.parse().unwrap_or_else(|_| invalid())),
+            "beta" => beta = Some(value.parse().unwrap_or_else(|_| invalid())),
+            _ => fail(format!("unknown option {name}")),
+        }
+    }
+    if let Some((elo0, elo1)) = sprt {
+        let default = Sprt::default();
+        config.sprt = Some(Sprt { elo0, elo1, alpha: alpha.unwrap_or(default.alpha), beta: beta.unwrap_or(default.beta) });
+    }
 
-    let start = Instant::now();
-    let queen_attack_mask = get_attack_mask_for_queen(square, full_board, &rank_attacks, &precomputed_masks);
-    let elapsed = start.elapsed();
-    println!("Time elapsed for queen attack mask: {:?}", elapsed);
+    let stats = tournament::run_match(&config, |record, stats| {
+        let white = &config.engines[record.white];
+        let black = &config.engines[1 - record.white];
+        eprintln!(
+            "game {}/{}: {white} vs {black} {} ({}), score {stats}",
+            record.game + 1,
+            config.games,
+            record.result_string(),
+            record.termination
+        );
+    })
+    .unwrap_or_else(|error| {
+        eprintln!("can't run the match: {error}");
+        process::exit(1);
+    });
+    println!("{}", match_report(&config, &stats));
 }
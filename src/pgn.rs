@@ -0,0 +1,679 @@
+use std::{
+    fmt,
+    io::{self, BufRead},
+    time::Duration,
+};
+
+use crate::{
+    board::{board::Board, fen::FenError, moves::Move, piece::Color, san::SanError, validate::PositionError},
+    search::{MATE, MATE_IN_MAX},
+};
+
+/// The tags every PGN game has, in the order they must be written.
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+/// Movetext lines are wrapped before reaching this length.
+const MAX_LINE_LENGTH: usize = 80;
+
+#[derive(Debug)]
+pub enum PgnErrorKind {
+    Io(io::Error),
+    /// The text doesn't follow the PGN syntax.
+    Syntax(String),
+    /// The FEN tag doesn't hold a valid position.
+    InvalidFen(FenError),
+    /// The FEN tag holds a position that can't occur in a game.
+    InvalidPosition(PositionError),
+    /// A move isn't legal in the position it is played in.
+    InvalidMove(SanError),
+}
+
+/// An error in a PGN file, at the given line and column (both starting at 1).
+#[derive(Debug)]
+pub struct PgnError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: PgnErrorKind,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            PgnErrorKind::Io(error) => write!(f, "{error}"),
+            PgnErrorKind::Syntax(message) => write!(f, "{message}"),
+            PgnErrorKind::InvalidFen(error) => write!(f, "{error}"),
+            PgnErrorKind::InvalidPosition(error) => write!(f, "invalid position: {error}"),
+            PgnErrorKind::InvalidMove(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+/// An engine evaluation stored in a "[%eval ...]" comment command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PgnEval {
+    /// From white's point of view.
+    Centipawns(i32),
+    /// Mate in the given number of moves, negative if black mates.
+    Mate(i32),
+}
+
+impl PgnEval {
+    /// Converts a search score from the point of view of `side` to white's point of view.
+    pub fn from_score(score: i32, side: Color) -> Self {
+        let sign = if side == Color::White { 1 } else { -1 };
+        if score >= MATE_IN_MAX {
+            PgnEval::Mate(sign * (MATE - score + 1) / 2)
+        } else if score <= -MATE_IN_MAX {
+            PgnEval::Mate(-sign * (MATE + score) / 2)
+        } else {
+            PgnEval::Centipawns(sign * score)
+        }
+    }
+}
+
+impl fmt::Display for PgnEval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PgnEval::Centipawns(cp) => {
+                let sign = if cp < 0 { "-" } else { "" };
+                write!(f, "{sign}{}.{:02}", cp.abs() / 100, cp.abs() % 100)
+            }
+            PgnEval::Mate(moves) => write!(f, "#{moves}"),
+        }
+    }
+}
+
+/// A move in the movetext, with everything annotating it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnMove {
+    pub mv: Move,
+    /// Numeric annotation glyphs, e.g. 1 for "!" and 4 for "??".
+    pub nags: Vec<u8>,
+    /// A comment before the move, only found at the start of a variation.
+    pub comment_before: Option<String>,
+    /// The comment after the move, without the eval and clock commands.
+    pub comment: Option<String>,
+    pub eval: Option<PgnEval>,
+    /// The time left on the clock of the side that moved.
+    pub clock: Option<Duration>,
+    /// Alternatives to this move, each starting from the position before it.
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+impl PgnMove {
+    pub fn new(mv: Move) -> Self {
+        PgnMove { mv, nags: Vec::new(), comment_before: None, comment: None, eval: None, clock: None, variations: Vec::new() }
+    }
+}
+
+/// A game read from or to be written to a PGN file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Game {
+    /// The tag pairs in file order. The result is kept in `result` rather than a tag.
+    pub tags: Vec<(String, String)>,
+    /// A comment before the first move.
+    pub comment: Option<String>,
+    pub moves: Vec<PgnMove>,
+    /// "1-0", "0-1", "1/2-1/2" or "*" for an unfinished game.
+    pub result: String,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Game { tags: Vec::new(), comment: None, moves: Vec::new(), result: "*".to_string() }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    /// Sets a tag, replacing an existing value.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// The position the game starts from: the FEN tag, or the standard starting position. FEN
+    /// tags that fail `Board::validate` are rejected.
+    pub fn start_position(&self) -> Result<Board, PgnErrorKind> {
+        let Some(fen) = self.tag("FEN") else {
+            return Ok(Board::new());
+        };
+        let board = Board::from_fen(fen).map_err(PgnErrorKind::InvalidFen)?;
+        board.validate().map_err(PgnErrorKind::InvalidPosition)?;
+        Ok(board)
+    }
+
+    /// Replays the main line and returns the final position.
+    pub fn final_position(&self) -> Result<Board, PgnErrorKind> {
+        let mut board = self.start_position()?;
+        for pgn_move in &self.moves {
+            board.make_move(pgn_move.mv);
+        }
+        Ok(board)
+    }
+
+    /// Formats the game as PGN: the seven tag roster followed by the other tags, then the
+    /// movetext wrapped at 80 columns.
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        for (name, default) in SEVEN_TAG_ROSTER {
+            let value = if name == "Result" { Some(self.result.as_str()) } else { self.tag(name) };
+            pgn.push_str(&format!("[{name} \"{}\"]\n", escape(value.unwrap_or(default))));
+        }
+        for (name, value) in &self.tags {
+            if !SEVEN_TAG_ROSTER.iter().any(|(roster_name, _)| roster_name == name) {
+                pgn.push_str(&format!("[{name} \"{}\"]\n", escape(value)));
+            }
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        if let Some(comment) = &self.comment {
+            tokens.push(format!("{{{}}}", clean_comment(comment)));
+        }
+        // An invalid FEN or position is written as given, but the moves can't be formatted.
+        if let Ok(mut board) = self.start_position() {
+            write_line(&mut board, &self.moves, &mut tokens);
+        }
+        tokens.push(self.result.clone());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() >= MAX_LINE_LENGTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            // Variations are written as "(1. e4 e5)", without spaces inside the parentheses.
+            if !line.is_empty() && !line.ends_with('(') && token != ")" {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push_str("\n\n");
+        pgn
+    }
+}
+
+/// Appends the movetext tokens of a line of moves played from `board`, restoring the board
+/// afterwards. A black move gets a "N..." number at the start of a line or after anything
+/// interrupting the moves.
+fn write_line(board: &mut Board, moves: &[PgnMove], tokens: &mut Vec<String>) {
+    let mut interrupted = true;
+    for pgn_move in moves {
+        if let Some(comment) = &pgn_move.comment_before {
+            tokens.push(format!("{{{}}}", clean_comment(comment)));
+        }
+        // The move number is kept on the same line as the move.
+        let san = pgn_move.mv.to_san(board);
+        tokens.push(match board.side_to_move {
+            Color::White => format!("{}. {san}", board.fullmove_number),
+            Color::Black if interrupted => format!("{}... {san}", board.fullmove_number),
+            Color::Black => san,
+        });
+        interrupted = false;
+        for nag in &pgn_move.nags {
+            tokens.push(format!("${nag}"));
+        }
+
+        let mut commands = Vec::new();
+        if let Some(eval) = pgn_move.eval {
+            commands.push(format!("[%eval {eval}]"));
+        }
+        if let Some(clock) = pgn_move.clock {
+            commands.push(format!("[%clk {}]", format_clock(clock)));
+        }
+        if let Some(comment) = &pgn_move.comment {
+            commands.push(clean_comment(comment));
+        }
+        if !commands.is_empty() {
+            tokens.push(format!("{{{}}}", commands.join(" ")));
+            interrupted = true;
+        }
+
+        for variation in &pgn_move.variations {
+            tokens.push("(".to_string());
+            write_line(board, variation, tokens);
+            tokens.push(")".to_string());
+            interrupted = true;
+        }
+        board.make_move(pgn_move.mv);
+    }
+    for _ in moves {
+        board.unmake_move();
+    }
+}
+
+/// Formats a clock time as h:mm:ss, with tenths of a second if there are any.
+fn format_clock(clock: Duration) -> String {
+    let tenths = clock.as_millis() / 100;
+    let (hours, minutes, seconds) = (tenths / 36000, tenths / 600 % 60, tenths / 10 % 60);
+    match tenths % 10 {
+        0 => format!("{hours}:{minutes:02}:{seconds:02}"),
+        fraction => format!("{hours}:{minutes:02}:{seconds:02}.{fraction}"),
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// A comment can't contain its closing brace.
+fn clean_comment(comment: &str) -> String {
+    comment.replace('}', ")")
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum TokenKind {
+    TagOpen,
+    TagClose,
+    VariationOpen,
+    VariationClose,
+    String(String),
+    /// A move, move number, result or tag name.
+    Symbol(String),
+    Nag(u8),
+    /// A traditional suffix annotation like "!?".
+    Annotation(String),
+    Comment(String),
+    Result(String),
+}
+
+#[derive(Clone, Debug)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+    column: usize,
+}
+
+/// Reads games one at a time from a PGN file, replaying the moves to check they are legal. Large
+/// files are streamed, so only the game being read is kept in memory.
+///
+/// After an error the reader skips to the next game, so iteration can simply continue.
+pub struct PgnReader<R> {
+    reader: R,
+    /// The current line, with the line break.
+    line: Vec<char>,
+    line_number: usize,
+    /// The index of the next character in `line`.
+    position: usize,
+    peeked: Option<Token>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        PgnReader { reader, line: Vec::new(), line_number: 0, position: 0, peeked: None }
+    }
+
+    fn error(&self, line: usize, column: usize, kind: PgnErrorKind) -> PgnError {
+        PgnError { line, column, kind }
+    }
+
+    fn syntax_error(&self, token: &Token, message: &str) -> PgnError {
+        self.error(token.line, token.column, PgnErrorKind::Syntax(message.to_string()))
+    }
+
+    /// Reads the next line into `line`. Returns false at the end of the input.
+    fn next_line(&mut self) -> Result<bool, PgnError> {
+        let mut line = String::new();
+        let read = self.reader.read_line(&mut line).map_err(|error| self.error(self.line_number + 1, 1, PgnErrorKind::Io(error)))?;
+        if read == 0 {
+            return Ok(false);
+        }
+        self.line_number += 1;
+        self.line = line.chars().collect();
+        if self.line.last() != Some(&'\n') {
+            self.line.push('\n');
+        }
+        self.position = 0;
+        Ok(true)
+    }
+
+    fn peek_char(&mut self) -> Result<Option<char>, PgnError> {
+        while self.position >= self.line.len() {
+            if !self.next_line()? {
+                return Ok(None);
+            }
+            // Lines starting with "%" are escaped and ignored.
+            if self.line.first() == Some(&'%') {
+                self.position = self.line.len();
+            }
+        }
+        Ok(Some(self.line[self.position]))
+    }
+
+    fn next_char(&mut self) -> Result<Option<char>, PgnError> {
+        let c = self.peek_char()?;
+        self.position += 1;
+        Ok(c)
+    }
+
+    fn peek_token(&mut self) -> Result<Option<&Token>, PgnError> {
+        if self.peeked.is_none() {
+            self.peeked = self.read_token()?;
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, PgnError> {
+        match self.peeked.take() {
+            Some(token) => Ok(Some(token)),
+            None => self.read_token(),
+        }
+    }
+
+    fn read_token(&mut self) -> Result<Option<Token>, PgnError> {
+        // Move number periods carry no information, so they are skipped like whitespace.
+        while let Some(c) = self.peek_char()? {
+            if !c.is_whitespace() && c != '.' {
+                break;
+            }
+            self.position += 1;
+        }
+        let (line, column) = (self.line_number, self.position + 1);
+        let Some(c) = self.next_char()? else { return Ok(None) };
+        let kind = match c {
+            '[' => TokenKind::TagOpen,
+            ']' => TokenKind::TagClose,
+            '(' => TokenKind::VariationOpen,
+            ')' => TokenKind::VariationClose,
+            '*' => TokenKind::Result("*".to_string()),
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match self.next_char()? {
+                        Some('\\') => value.extend(self.next_char()?),
+                        Some('"') => break,
+                        Some('\n') | None => {
+                            return Err(self.error(line, column, PgnErrorKind::Syntax("unterminated string".to_string())))
+                        }
+                        Some(c) => value.push(c),
+                    }
+                }
+                TokenKind::String(value)
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match self.next_char()? {
+                        Some('}') => break,
+                        Some(c) => comment.push(c),
+                        None => return Err(self.error(line, column, PgnErrorKind::Syntax("unterminated comment".to_string()))),
+                    }
+                }
+                TokenKind::Comment(comment)
+            }
+            ';' => {
+                let comment: String = self.line[self.position..].iter().collect();
+                self.position = self.line.len();
+                TokenKind::Comment(comment)
+            }
+            '$' => {
+                let mut digits = String::new();
+                while let Some(c) = self.peek_char()?.filter(char::is_ascii_digit) {
+                    digits.push(c);
+                    self.position += 1;
+                }
+                let nag = digits.parse().map_err(|_| self.error(line, column, PgnErrorKind::Syntax("invalid NAG".to_string())))?;
+                TokenKind::Nag(nag)
+            }
+            '!' | '?' => {
+                let mut annotation = c.to_string();
+                while let Some(c) = self.peek_char()?.filter(|&c| c == '!' || c == '?') {
+                    annotation.push(c);
+                    self.position += 1;
+                }
+                TokenKind::Annotation(annotation)
+            }
+            c if c.is_ascii_alphanumeric() => {
+                let mut symbol = c.to_string();
+                while let Some(c) = self.peek_char()?.filter(|&c| c.is_ascii_alphanumeric() || "_+#=:-/".contains(c)) {
+                    symbol.push(c);
+                    self.position += 1;
+                }
+                match symbol.as_str() {
+                    "1-0" | "0-1" | "1/2-1/2" => TokenKind::Result(symbol),
+                    _ => TokenKind::Symbol(symbol),
+                }
+            }
+            c => return Err(self.error(line, column, PgnErrorKind::Syntax(format!("unexpected character {c:?}")))),
+        };
+        Ok(Some(Token { kind, line, column }))
+    }
+
+    /// Skips the rest of a broken game: everything up to the next line starting a tag section.
+    fn skip_game(&mut self, in_movetext: bool) -> Result<(), PgnError> {
+        self.peeked = None;
+        let mut seen_movetext = in_movetext;
+        self.position = self.line.len();
+        while self.next_line()? {
+            let first = self.line.iter().find(|c| !c.is_whitespace());
+            match first {
+                Some('[') if seen_movetext => return Ok(()),
+                Some('[') | None => {}
+                Some(_) => seen_movetext = true,
+            }
+            self.position = self.line.len();
+        }
+        Ok(())
+    }
+
+    /// Reads the next game, or None at the end of the input.
+    pub fn read_game(&mut self) -> Result<Option<Game>, PgnError> {
+        let mut game = Game::new();
+        let mut in_movetext = false;
+        let result = self.read_game_into(&mut game, &mut in_movetext);
+        match result {
+            Ok(true) => Ok(Some(game)),
+            Ok(false) => Ok(None),
+            Err(error) => {
+                // Leave the reader at the next game. An error while skipping is less useful
+                // than the original one.
+                let _ = self.skip_game(in_movetext);
+                Err(error)
+            }
+        }
+    }
+
+    /// Reads the tags and movetext of a game. Returns false if the input is exhausted.
+    fn read_game_into(&mut self, game: &mut Game, in_movetext: &mut bool) -> Result<bool, PgnError> {
+        let mut fen_token = None;
+        while let Some(Token { kind: TokenKind::TagOpen, .. }) = self.peek_token()? {
+            let open = self.next_token()?.expect("the token was peeked");
+            let name = self.next_token()?;
+            let value = self.next_token()?;
+            let close = self.next_token()?;
+            match (name, value, close) {
+                (
+                    Some(Token { kind: TokenKind::Symbol(name), .. }),
+                    Some(value_token @ Token { kind: TokenKind::String(_), .. }),
+                    Some(Token { kind: TokenKind::TagClose, .. }),
+                ) => {
+                    let TokenKind::String(value) = &value_token.kind else { unreachable!() };
+                    if name == "Result" {
+                        game.result = value.clone();
+                    } else {
+                        game.set_tag(&name, value);
+                    }
+                    if name == "FEN" {
+                        fen_token = Some(value_token);
+                    }
+                }
+                _ => return Err(self.syntax_error(&open, "malformed tag pair")),
+            }
+        }
+
+        *in_movetext = true;
+        if game.tags.is_empty() && self.peek_token()?.is_none() {
+            return Ok(false);
+        }
+        let mut board = game.start_position().map_err(|kind| {
+            let (line, column) = fen_token.as_ref().map_or((self.line_number, 1), |token| (token.line, token.column));
+            self.error(line, column, kind)
+        })?;
+        let mut comment = None;
+        let result = self.read_line(&mut board, &mut game.moves, &mut comment, false)?;
+        game.comment = comment;
+        if let Some(result) = result {
+            game.result = result;
+        }
+        Ok(true)
+    }
+
+    /// Reads a line of moves played from `board` until the end of the variation or game. Returns
+    /// the game result if the line ends with one.
+    fn read_line(
+        &mut self,
+        board: &mut Board,
+        moves: &mut Vec<PgnMove>,
+        leading_comment: &mut Option<String>,
+        nested: bool,
+    ) -> Result<Option<String>, PgnError> {
+        let start_ply = board.history.len();
+        let result = loop {
+            let Some(token) = self.next_token()? else {
+                if nested {
+                    return Err(self.error(self.line_number, self.position + 1, PgnErrorKind::Syntax("unterminated variation".to_string())));
+                }
+                break None;
+            };
+            match &token.kind {
+                TokenKind::Symbol(symbol) if symbol.chars().all(|c| c.is_ascii_digit()) => {}
+                TokenKind::Symbol(san) => {
+                    let mv = board.parse_san(san).map_err(|error| self.error(token.line, token.column, PgnErrorKind::InvalidMove(error)))?;
+                    board.make_move(mv);
+                    moves.push(PgnMove::new(mv));
+                }
+                TokenKind::Annotation(annotation) => {
+                    let nag = match annotation.as_str() {
+                        "!" => 1,
+                        "?" => 2,
+                        "!!" => 3,
+                        "??" => 4,
+                        "!?" => 5,
+                        "?!" => 6,
+                        _ => return Err(self.syntax_error(&token, "invalid annotation")),
+                    };
+                    moves.last_mut().ok_or_else(|| self.syntax_error(&token, "annotation before any move"))?.nags.push(nag);
+                }
+                TokenKind::Nag(nag) => {
+                    moves.last_mut().ok_or_else(|| self.syntax_error(&token, "NAG before any move"))?.nags.push(*nag);
+                }
+                TokenKind::Comment(text) => match moves.last_mut() {
+                    Some(last) => parse_comment(last, text),
+                    None => append_comment(leading_comment, text),
+                },
+                TokenKind::VariationOpen => {
+                    let last = moves.last_mut().ok_or_else(|| self.syntax_error(&token, "variation before any move"))?;
+                    // The variation replaces the last move.
+                    let mut variation_board = board.clone();
+                    variation_board.unmake_move();
+                    let mut variation = Vec::new();
+                    let mut variation_comment = None;
+                    self.read_line(&mut variation_board, &mut variation, &mut variation_comment, true)?;
+                    if let Some(first) = variation.first_mut() {
+                        first.comment_before = variation_comment;
+                    }
+                    if !variation.is_empty() {
+                        last.variations.push(variation);
+                    }
+                }
+                TokenKind::VariationClose if nested => break None,
+                TokenKind::VariationClose => return Err(self.syntax_error(&token, "unmatched )")),
+                TokenKind::Result(_) if nested => return Err(self.syntax_error(&token, "result inside a variation")),
+                TokenKind::Result(result) => break Some(result.clone()),
+                // A game without a result, directly followed by the tags of the next game.
+                TokenKind::TagOpen if !nested && token.column == 1 => {
+                    self.peeked = Some(token);
+                    break None;
+                }
+                TokenKind::TagOpen | TokenKind::TagClose | TokenKind::String(_) => return Err(self.syntax_error(&token, "unexpected token in movetext")),
+            }
+        };
+        while board.history.len() > start_ply {
+            board.unmake_move();
+        }
+        Ok(result)
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<Game, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_game().transpose()
+    }
+}
+
+fn append_comment(comment: &mut Option<String>, text: &str) {
+    let text = text.trim();
+    if text.is_empty() {
+        return;
+    }
+    match comment {
+        Some(existing) => {
+            existing.push(' ');
+            existing.push_str(text);
+        }
+        None => *comment = Some(text.to_string()),
+    }
+}
+
+/// Adds a comment to a move, taking out the "[%eval ...]" and "[%clk ...]" commands.
+fn parse_comment(pgn_move: &mut PgnMove, text: &str) {
+    let mut rest = String::new();
+    let mut remaining = text;
+    while let Some(start) = remaining.find("[%") {
+        let Some(length) = remaining[start..].find(']') else { break };
+        rest.push_str(&remaining[..start]);
+        let command = &remaining[start + 2..start + length];
+        let mut parts = command.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (Some("eval"), Some(value)) if parse_eval(value).is_some() => pgn_move.eval = parse_eval(value),
+            (Some("clk"), Some(value)) if parse_clock(value).is_some() => pgn_move.clock = parse_clock(value),
+            _ => rest.push_str(&remaining[start..=start + length]),
+        }
+        remaining = &remaining[start + length + 1..];
+    }
+    rest.push_str(remaining);
+    let rest = rest.split_whitespace().collect::<Vec<_>>().join(" ");
+    append_comment(&mut pgn_move.comment, &rest);
+}
+
+fn parse_eval(value: &str) -> Option<PgnEval> {
+    if let Some(moves) = value.strip_prefix('#') {
+        return moves.parse().ok().map(PgnEval::Mate);
+    }
+    let centipawns = (value.parse::<f64>().ok()? * 100.0).round();
+    // Casting would silently saturate infinite or huge values and turn NaN into 0.
+    (centipawns.abs() <= i32::MAX as f64).then_some(PgnEval::Centipawns(centipawns as i32))
+}
+
+fn parse_clock(value: &str) -> Option<Duration> {
+    let mut seconds = 0.0;
+    for part in value.split(':') {
+        seconds = seconds * 60.0 + part.parse::<f64>().ok().filter(|part| *part >= 0.0)?;
+    }
+    Duration::try_from_secs_f64(seconds).ok()
+}
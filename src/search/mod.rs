@@ -0,0 +1,672 @@
+#[doc(hidden)]
+pub mod ordering;
+#[doc(hidden)]
+pub mod see;
+#[doc(hidden)]
+pub mod smp;
+#[doc(hidden)]
+pub mod time;
+#[doc(hidden)]
+pub mod tt;
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::{board::Board, movegen::generate_legal_moves, moves::Move},
+    eval::{evaluate, pawns::PawnTable},
+};
+
+use ordering::{Heuristics, MovePicker};
+use see::see_ge;
+use time::{TimeManager, DEFAULT_MOVE_OVERHEAD};
+use tt::{score_from_tt, score_to_tt, Bound, TranspositionTable};
+
+/// The maximum number of plies the search can reach from the root.
+pub const MAX_PLY: usize = 128;
+/// A score larger than any real evaluation, used as the initial search window.
+pub const INFINITY: i32 = 32000;
+/// The score of delivering mate on the board. Mate in n plies scores `MATE - n`.
+pub const MATE: i32 = 31000;
+/// Scores at or above this are mates found within the maximum search ply.
+pub const MATE_IN_MAX: i32 = MATE - MAX_PLY as i32;
+
+/// Reverse futility pruning is applied up to this depth, with a margin of `RFP_MARGIN` per ply.
+const RFP_MAX_DEPTH: i32 = 6;
+const RFP_MARGIN: i32 = 80;
+/// Null move pruning is only tried with at least this much depth left.
+const NMP_MIN_DEPTH: i32 = 3;
+/// Futility pruning is applied up to this depth, with a margin of `FP_MARGIN` per ply.
+const FP_MAX_DEPTH: i32 = 4;
+const FP_MARGIN: i32 = 120;
+/// Late move pruning is applied up to this depth.
+const LMP_MAX_DEPTH: i32 = 4;
+/// Late move reductions are only applied with at least this much depth left.
+const LMR_MIN_DEPTH: i32 = 3;
+/// Aspiration windows start at `ASPIRATION_WINDOW` centipawns around the previous score from this
+/// depth on, and fall back to a full window once they grow past `ASPIRATION_MAX_WINDOW`.
+const ASPIRATION_MIN_DEPTH: i32 = 4;
+const ASPIRATION_WINDOW: i32 = 25;
+const ASPIRATION_MAX_WINDOW: i32 = 1000;
+
+/// Switches for the selective search techniques, so their effect can be measured by playing
+/// matches between configurations. All are enabled by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchConfig {
+    pub null_move_pruning: bool,
+    pub late_move_reductions: bool,
+    pub reverse_futility_pruning: bool,
+    pub futility_pruning: bool,
+    pub late_move_pruning: bool,
+}
+
+impl SearchConfig {
+    /// A configuration with all selectivity disabled, searching the full alpha-beta tree.
+    pub const NO_PRUNING: SearchConfig = SearchConfig {
+        null_move_pruning: false,
+        late_move_reductions: false,
+        reverse_futility_pruning: false,
+        futility_pruning: false,
+        late_move_pruning: false,
+    };
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            null_move_pruning: true,
+            late_move_reductions: true,
+            reverse_futility_pruning: true,
+            futility_pruning: true,
+            late_move_pruning: true,
+        }
+    }
+}
+
+/// Builds the late move reduction table, indexed by depth and move number. Reductions grow with
+/// the logarithm of both, so late moves at high depth are reduced the most.
+fn build_reduction_table() -> Box<[[i32; 64]; 64]> {
+    let mut table = Box::new([[0; 64]; 64]);
+    for (depth, row) in table.iter_mut().enumerate().skip(1) {
+        for (move_number, reduction) in row.iter_mut().enumerate().skip(1) {
+            *reduction = (0.75 + (depth as f64).ln() * (move_number as f64).ln() / 2.25) as i32;
+        }
+    }
+    table
+}
+
+/// When the search should stop. Limits that are None are unbounded. The clock fields follow the
+/// UCI "go" command and are turned into time limits by a `TimeManager`.
+#[derive(Clone, Debug, Default)]
+pub struct SearchLimits {
+    pub depth: Option<i32>,
+    pub nodes: Option<u64>,
+    /// Search exactly this long.
+    pub movetime: Option<Duration>,
+    /// The time left on the clocks of white and black.
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    /// The increments per move of white and black.
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    /// The number of moves until the next time control. None means the rest of the game.
+    pub movestogo: Option<u32>,
+    /// Stop as soon as a mate in at most this many moves is found.
+    pub mate: Option<u32>,
+    /// Only consider these moves at the root. Empty means all legal moves.
+    pub search_moves: Vec<Move>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub depth: i32,
+    /// The highest ply reached, including the quiescence search.
+    pub seldepth: usize,
+    pub nodes: u64,
+    pub time: Duration,
+    /// How full the transposition table is, in permille.
+    pub hashfull: u32,
+    /// The principal variation, starting with the best move.
+    pub pv: Vec<Move>,
+}
+
+impl SearchResult {
+    /// Formats the score for UCI, as "cp <centipawns>" or "mate <moves>" where a negative number
+    /// of moves means the engine is getting mated.
+    pub fn uci_score(&self) -> String {
+        if self.score >= MATE_IN_MAX {
+            format!("mate {}", (MATE - self.score + 1) / 2)
+        } else if self.score <= -MATE_IN_MAX {
+            format!("mate {}", -(MATE + self.score) / 2)
+        } else {
+            format!("cp {}", self.score)
+        }
+    }
+
+    /// Formats the result as the body of a UCI "info" line, without the leading "info".
+    pub fn uci_info(&self) -> String {
+        let millis = self.time.as_millis();
+        let nps = (self.nodes as u128 * 1000).checked_div(millis).unwrap_or(0);
+        let pv: Vec<String> = self.pv.iter().map(Move::to_string).collect();
+        format!(
+            "depth {} seldepth {} score {} nodes {} nps {} hashfull {} time {} pv {}",
+            self.depth,
+            self.seldepth,
+            self.uci_score(),
+            self.nodes,
+            nps,
+            self.hashfull,
+            millis,
+            pv.join(" ")
+        )
+    }
+}
+
+/// Counters collected during a search, used to measure move ordering quality.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SearchStats {
+    pub nodes: u64,
+    pub qnodes: u64,
+    /// The number of nodes that failed high.
+    pub beta_cutoffs: u64,
+    /// The number of nodes that failed high on the first move searched.
+    pub first_move_cutoffs: u64,
+}
+
+impl SearchStats {
+    /// The fraction of beta cutoffs that happened on the first move. With perfect move ordering this is 1.
+    pub fn first_move_cutoff_rate(&self) -> f64 {
+        if self.beta_cutoffs == 0 {
+            return 0.0;
+        }
+        self.first_move_cutoffs as f64 / self.beta_cutoffs as f64
+    }
+}
+
+/// A function called by the searcher after each completed iteration.
+pub type IterationCallback = Box<dyn FnMut(&SearchResult) + Send>;
+
+/// An iterative deepening alpha-beta searcher. The transposition table and move ordering
+/// heuristics are kept between searches.
+pub struct Searcher {
+    /// The transposition table, which may be shared with other searchers.
+    pub tt: Arc<TranspositionTable>,
+    pub heuristics: Heuristics,
+    /// The pawn hash table of this searcher. Unlike the transposition table it isn't shared.
+    pub pawn_table: PawnTable,
+    pub stats: SearchStats,
+    pub config: SearchConfig,
+    /// Set from another thread to abort the search as soon as possible.
+    pub stop: Arc<AtomicBool>,
+    /// The number of nodes searched by all searchers sharing this counter.
+    pub shared_nodes: Arc<AtomicU64>,
+    /// 0 for the main thread. Helper threads of a lazy SMP search skip some depths to diversify.
+    pub thread_id: usize,
+    /// The time reserved for communication with the GUI, subtracted from the clock.
+    pub move_overhead: Duration,
+    /// While set, the search ignores its time limits because it is thinking on the opponent's
+    /// time. Once cleared, the time spent pondering counts as used.
+    pub pondering: Arc<AtomicBool>,
+    /// The part of `stats.nodes` already added to `shared_nodes`.
+    flushed_nodes: u64,
+    limits: SearchLimits,
+    start: Instant,
+    /// Only the main thread manages the time; helpers stop when it does.
+    time_manager: Option<TimeManager>,
+    /// The nodes spent on each root move in the current iteration.
+    root_move_nodes: HashMap<Move, u64>,
+    /// Called with the result of every completed iteration, e.g. to print UCI info lines.
+    pub on_iteration: Option<IterationCallback>,
+    reductions: Box<[[i32; 64]; 64]>,
+    /// The triangular PV table. Row `ply` holds the best line found from that ply, in the
+    /// entries `ply..pv_length[ply]`.
+    pv_table: Box<[[Option<Move>; MAX_PLY]; MAX_PLY]>,
+    pv_length: [usize; MAX_PLY],
+    seldepth: usize,
+}
+
+impl Searcher {
+    /// Creates a searcher with a transposition table of `tt_size_mb` megabytes.
+    pub fn new(tt_size_mb: usize) -> Self {
+        Self::with_shared_state(Arc::new(TranspositionTable::new(tt_size_mb)), Arc::new(AtomicBool::new(false)), Arc::new(AtomicU64::new(0)))
+    }
+
+    /// Creates a searcher that shares its transposition table, stop flag and node counter with
+    /// other searchers, for searching the same position on several threads.
+    pub fn with_shared_state(tt: Arc<TranspositionTable>, stop: Arc<AtomicBool>, shared_nodes: Arc<AtomicU64>) -> Self {
+        Searcher {
+            tt,
+            heuristics: Heuristics::new(),
+            pawn_table: PawnTable::new(),
+            stats: SearchStats::default(),
+            config: SearchConfig::default(),
+            stop,
+            shared_nodes,
+            thread_id: 0,
+            move_overhead: DEFAULT_MOVE_OVERHEAD,
+            pondering: Arc::new(AtomicBool::new(false)),
+            flushed_nodes: 0,
+            limits: SearchLimits::default(),
+            start: Instant::now(),
+            time_manager: None,
+            root_move_nodes: HashMap::new(),
+            on_iteration: None,
+            reductions: build_reduction_table(),
+            pv_table: Box::new([[None; MAX_PLY]; MAX_PLY]),
+            pv_length: [0; MAX_PLY],
+            seldepth: 0,
+        }
+    }
+
+    /// Clears everything learned in previous searches, e.g. before a new game.
+    pub fn clear(&mut self) {
+        self.tt.clear();
+        self.heuristics.clear();
+        self.pawn_table.clear();
+    }
+
+    /// Searches the position with iterative deepening until a limit is reached or `stop` is set,
+    /// and returns the result of the last completed iteration.
+    pub fn search(&mut self, board: &mut Board, limits: SearchLimits) -> SearchResult {
+        self.stop.store(false, Ordering::Relaxed);
+        self.shared_nodes.store(0, Ordering::Relaxed);
+        self.iterative_deepening(board, limits)
+    }
+
+    /// The iterative deepening loop of `search`. Unlike `search`, this doesn't reset the shared
+    /// stop flag and node counter, so it can run on several threads at once.
+    pub fn iterative_deepening(&mut self, board: &mut Board, limits: SearchLimits) -> SearchResult {
+        self.limits = limits.clone();
+        self.start = Instant::now();
+        self.stats = SearchStats::default();
+        self.flushed_nodes = 0;
+        self.time_manager = if self.thread_id == 0 {
+            TimeManager::new(&limits, board.side_to_move, self.move_overhead)
+        } else {
+            None
+        };
+
+        let legal_moves: Vec<Move> = generate_legal_moves(board)
+            .into_iter()
+            .filter(|mv| limits.search_moves.is_empty() || limits.search_moves.contains(mv))
+            .collect();
+        let mut result = SearchResult {
+            // Make sure there is a move to play even if the first iteration is aborted.
+            best_move: legal_moves.first().copied(),
+            ..SearchResult::default()
+        };
+        let max_depth = limits.depth.unwrap_or(MAX_PLY as i32 - 1).min(MAX_PLY as i32 - 1);
+        for depth in 1..=max_depth {
+            // Helpers skip every other depth, offset by their id, so that the threads spread out
+            // over different depths instead of all searching the same tree in lockstep.
+            if self.thread_id > 0 && depth > 1 && depth < max_depth && (depth as usize + self.thread_id).is_multiple_of(2) {
+                continue;
+            }
+            self.seldepth = 0;
+            self.root_move_nodes.clear();
+            let iteration_start_nodes = self.stats.nodes;
+            let score = self.aspiration_search(board, depth, result.score);
+            // An aborted iteration is only partially searched, so its result can't be trusted.
+            if self.stop.load(Ordering::Relaxed) {
+                break;
+            }
+            let pv: Vec<Move> = self.pv_table[0][..self.pv_length[0]].iter().map_while(|mv| *mv).collect();
+            result = SearchResult {
+                best_move: pv.first().copied(),
+                score,
+                depth,
+                seldepth: self.seldepth,
+                nodes: self.flush_nodes(),
+                time: self.start.elapsed(),
+                hashfull: self.tt.hashfull(),
+                pv,
+            };
+            if let Some(callback) = self.on_iteration.as_mut() {
+                callback(&result);
+            }
+
+            if limits.mate.is_some_and(|moves| score >= MATE - (2 * moves as i32 - 1)) {
+                break;
+            }
+            if let Some(time_manager) = self.time_manager.as_mut() {
+                let iteration_nodes = (self.stats.nodes - iteration_start_nodes).max(1);
+                let best_move_nodes = result.best_move.and_then(|mv| self.root_move_nodes.get(&mv)).copied().unwrap_or(0);
+                time_manager.update(result.best_move, score, best_move_nodes as f64 / iteration_nodes as f64);
+                // With a single move to choose from there is nothing to think about.
+                let pondering = self.pondering.load(Ordering::Relaxed);
+                if !pondering && (time_manager.should_stop(self.start.elapsed()) || legal_moves.len() == 1) {
+                    self.stop.store(true, Ordering::Relaxed);
+                    break;
+                }
+            }
+        }
+        result.nodes = self.flush_nodes();
+        result.time = self.start.elapsed();
+        result
+    }
+
+    /// Adds the nodes searched since the last flush to the shared counter and returns its total.
+    fn flush_nodes(&mut self) -> u64 {
+        let new_nodes = self.stats.nodes - self.flushed_nodes;
+        self.flushed_nodes = self.stats.nodes;
+        self.shared_nodes.fetch_add(new_nodes, Ordering::Relaxed) + new_nodes
+    }
+
+    /// Searches the root with a narrow window around the previous iteration's score, which makes
+    /// most of the tree cheaper to search. If the score falls outside the window, the window is
+    /// widened on that side and the root is searched again.
+    fn aspiration_search(&mut self, board: &mut Board, depth: i32, previous_score: i32) -> i32 {
+        let mut delta = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) = if depth >= ASPIRATION_MIN_DEPTH && previous_score.abs() < MATE_IN_MAX {
+            (previous_score - delta, previous_score + delta)
+        } else {
+            (-INFINITY, INFINITY)
+        };
+        loop {
+            let score = self.negamax(board, depth, 0, alpha, beta);
+            if self.stop.load(Ordering::Relaxed) {
+                return score;
+            }
+            if score <= alpha {
+                // Failing low means the best move got worse; bring beta down too, since the
+                // true score is now known to be below the old window.
+                beta = (alpha + beta) / 2;
+                alpha = (score - delta).max(-INFINITY);
+            } else if score >= beta {
+                beta = (score + delta).min(INFINITY);
+            } else {
+                return score;
+            }
+            delta *= 2;
+            if delta > ASPIRATION_MAX_WINDOW {
+                alpha = -INFINITY;
+                beta = INFINITY;
+            }
+        }
+    }
+
+    /// Checks the node and time limits, setting `stop` once one is exceeded.
+    fn check_limits(&mut self) {
+        let total_nodes = self.flush_nodes();
+        if self.limits.nodes.is_some_and(|nodes| total_nodes >= nodes)
+            || (!self.pondering.load(Ordering::Relaxed)
+                && self.time_manager.as_ref().is_some_and(|time_manager| time_manager.out_of_time(self.start.elapsed())))
+        {
+            self.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn negamax(&mut self, board: &mut Board, mut depth: i32, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv_length[ply] = ply;
+        self.seldepth = self.seldepth.max(ply);
+        if ply > 0 && board.is_draw() {
+            return 0;
+        }
+        let in_check = board.in_check();
+        if in_check {
+            depth += 1;
+        }
+        if depth <= 0 || ply >= MAX_PLY - 1 {
+            return self.quiescence(board, ply, alpha, beta);
+        }
+
+        self.stats.nodes += 1;
+        if self.stats.nodes.is_multiple_of(1024) {
+            self.check_limits();
+        }
+        if self.stop.load(Ordering::Relaxed) {
+            return 0;
+        }
+
+        let pv_node = beta - alpha > 1;
+        let tt_entry = self.tt.probe(board.hash);
+        if let Some(entry) = tt_entry {
+            let score = score_from_tt(entry.score, ply);
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            // PV nodes are always searched, since a cutoff would end the principal variation here.
+            if ply > 0 && !pv_node && entry.depth >= depth && usable {
+                return score;
+            }
+        }
+        let static_eval = evaluate(board, &mut self.pawn_table);
+
+        if !pv_node && !in_check && ply > 0 {
+            // Reverse futility pruning: if the static evaluation beats beta by a margin that grows
+            // with depth, the opponent is unlikely to recover in the few plies left.
+            if self.config.reverse_futility_pruning
+                && depth <= RFP_MAX_DEPTH
+                && static_eval - RFP_MARGIN * depth >= beta
+                && static_eval.abs() < MATE_IN_MAX
+            {
+                return static_eval;
+            }
+
+            // Null move pruning: give the opponent a free move. If a reduced search still fails
+            // high, the position is good enough to cut off without searching any real move.
+            // Skipped with only pawns left, where zugzwang makes passing better than any move.
+            if self.config.null_move_pruning
+                && depth >= NMP_MIN_DEPTH
+                && static_eval >= beta
+                && !board.last_move_was_null()
+                && board.has_non_pawn_material(board.side_to_move)
+            {
+                let reduction = 3 + depth / 4 + ((static_eval - beta) / 200).min(3);
+                board.make_null_move();
+                let score = -self.negamax(board, depth - 1 - reduction, ply + 1, -beta, -beta + 1);
+                board.unmake_null_move();
+                if self.stop.load(Ordering::Relaxed) {
+                    return 0;
+                }
+                if score >= beta {
+                    // A mate found after passing is not a proven mate, so don't return it.
+                    return if score >= MATE_IN_MAX { beta } else { score };
+                }
+            }
+        }
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        let mut searched_quiets = Vec::new();
+        let mut move_count = 0;
+        let mut searched = 0;
+        let mut picker = MovePicker::new(board, tt_entry.and_then(|entry| entry.best_move), &self.heuristics, ply);
+
+        while let Some(mv) = picker.next(board, &self.heuristics) {
+            if ply == 0 && !self.limits.search_moves.is_empty() && !self.limits.search_moves.contains(&mv) {
+                continue;
+            }
+            let is_quiet = board.is_quiet(mv);
+            let gives_check = board.gives_check(mv);
+            move_count += 1;
+
+            // Quiet move pruning near the leaves, never before a move has been searched so that
+            // a mate score can't be returned by mistake. Checks and evasions are always searched,
+            // since the static evaluation says nothing about a mating attack.
+            if !pv_node && !in_check && !gives_check && is_quiet && best_score > -MATE_IN_MAX {
+                // Late move pruning: with good move ordering, late quiet moves rarely matter.
+                if self.config.late_move_pruning && depth <= LMP_MAX_DEPTH && move_count > 3 + depth * depth {
+                    continue;
+                }
+                // Futility pruning: a quiet move can't raise alpha if the evaluation plus a
+                // generous margin is still below it.
+                if self.config.futility_pruning
+                    && depth <= FP_MAX_DEPTH
+                    && static_eval + FP_MARGIN * depth <= alpha
+                {
+                    continue;
+                }
+            }
+
+            let nodes_before = self.stats.nodes;
+            board.make_move(mv);
+            searched += 1;
+
+            let score = if searched == 1 {
+                -self.negamax(board, depth - 1, ply + 1, -beta, -alpha)
+            } else {
+                // Late move reductions: search late quiet moves with a reduced depth first.
+                let reduction = if self.config.late_move_reductions
+                    && depth >= LMR_MIN_DEPTH
+                    && searched > 1 + pv_node as i32
+                    && is_quiet
+                    && !in_check
+                    && !gives_check
+                {
+                    let mut reduction = self.reductions[depth.min(63) as usize][searched.min(63) as usize];
+                    reduction -= pv_node as i32;
+                    reduction -= self.heuristics.history_score(board.side_to_move.opposite(), mv) / 8192;
+                    reduction.clamp(0, depth - 2)
+                } else {
+                    0
+                };
+
+                // Principal variation search: assuming the first move was the best, prove every
+                // other move is worse with a cheap null window search. Only moves that unexpectedly
+                // beat alpha are searched again, first without the reduction and then, in PV
+                // nodes, with the full window to get an exact score.
+                let mut score = -self.negamax(board, depth - 1 - reduction, ply + 1, -alpha - 1, -alpha);
+                if score > alpha && reduction > 0 {
+                    score = -self.negamax(board, depth - 1, ply + 1, -alpha - 1, -alpha);
+                }
+                if score > alpha && score < beta {
+                    score = -self.negamax(board, depth - 1, ply + 1, -beta, -alpha);
+                }
+                score
+            };
+            board.unmake_move();
+            if ply == 0 {
+                *self.root_move_nodes.entry(mv).or_insert(0) += self.stats.nodes - nodes_before;
+            }
+
+            if self.stop.load(Ordering::Relaxed) {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+                if score > alpha {
+                    alpha = score;
+                    self.update_pv(ply, mv);
+                }
+            }
+            if alpha >= beta {
+                self.stats.beta_cutoffs += 1;
+                if searched == 1 {
+                    self.stats.first_move_cutoffs += 1;
+                }
+                if is_quiet {
+                    self.heuristics.update_quiet(board, ply, depth, mv, &searched_quiets);
+                }
+                break;
+            }
+            if is_quiet {
+                searched_quiets.push(mv);
+            }
+        }
+
+        if move_count == 0 {
+            return if in_check { -MATE + ply as i32 } else { 0 };
+        }
+        if searched == 0 {
+            // Every legal move was pruned, so the position is at most worth alpha.
+            return alpha;
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.tt.store(board.hash, best_move, score_to_tt(best_score, ply), depth, bound);
+        best_score
+    }
+
+    /// Makes `mv` followed by the best line of the child node the best line of this node.
+    fn update_pv(&mut self, ply: usize, mv: Move) {
+        self.pv_table[ply][ply] = Some(mv);
+        let child_length = self.pv_length[ply + 1].max(ply + 1);
+        for next in ply + 1..child_length {
+            self.pv_table[ply][next] = self.pv_table[ply + 1][next];
+        }
+        self.pv_length[ply] = child_length;
+    }
+
+    /// Searches only captures and promotions until the position is quiet, so the static evaluation
+    /// is never taken in the middle of an exchange.
+    fn quiescence(&mut self, board: &mut Board, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv_length[ply] = ply;
+        self.seldepth = self.seldepth.max(ply);
+        self.stats.nodes += 1;
+        self.stats.qnodes += 1;
+        if self.stats.nodes.is_multiple_of(1024) {
+            self.check_limits();
+        }
+        if self.stop.load(Ordering::Relaxed) {
+            return 0;
+        }
+        if ply >= MAX_PLY - 1 {
+            return evaluate(board, &mut self.pawn_table);
+        }
+
+        let in_check = board.in_check();
+        let mut best_score = -INFINITY;
+        if !in_check {
+            // The side to move can usually do at least as well as the static evaluation by
+            // making a quiet move, so it serves as a lower bound ("standing pat").
+            best_score = evaluate(board, &mut self.pawn_table);
+            if best_score >= beta {
+                return best_score;
+            }
+            alpha = alpha.max(best_score);
+        }
+
+        // In check every evasion has to be considered, otherwise a mate could be missed.
+        let mut picker = if in_check {
+            MovePicker::new(board, None, &self.heuristics, ply)
+        } else {
+            MovePicker::new_noisy(board, None)
+        };
+        let mut move_count = 0;
+        while let Some(mv) = picker.next(board, &self.heuristics) {
+            move_count += 1;
+            // Captures that lose material can't raise the score above standing pat.
+            if !in_check && !see_ge(board, mv, 0) {
+                continue;
+            }
+            board.make_move(mv);
+            let score = -self.quiescence(board, ply + 1, -beta, -alpha);
+            board.unmake_move();
+
+            if score > best_score {
+                best_score = score;
+                if score > alpha {
+                    alpha = score;
+                }
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if in_check && move_count == 0 {
+            return -MATE + ply as i32;
+        }
+        best_score
+    }
+}
@@ -0,0 +1,267 @@
+use crate::board::{
+    board::Board,
+    movegen::{generate_pseudo_legal_moves, is_legal, is_pseudo_legal},
+    moves::Move,
+    piece::Color,
+};
+
+use super::{
+    see::{see_ge, SEE_VALUES},
+    MAX_PLY,
+};
+
+/// History scores are kept within [-HISTORY_MAX, HISTORY_MAX] by the gravity formula.
+pub const HISTORY_MAX: i32 = 16384;
+
+/// Scores a capture or promotion by Most Valuable Victim - Least Valuable Attacker: capturing the
+/// biggest piece comes first, and among equal victims the cheapest attacker is tried first. Uses the
+/// SEE piece values, so the ordering and the exchange evaluation agree on what a trade is worth.
+pub fn mvv_lva(board: &Board, mv: Move) -> i32 {
+    let victim = board.captured_piece(mv).map_or(0, |piece| SEE_VALUES[piece.index()]);
+    let attacker = SEE_VALUES[board.moved_piece(mv).index()];
+    let promotion = mv.promotion.map_or(0, |piece| SEE_VALUES[piece.index()]);
+    (victim + promotion) * 16 - attacker / 100
+}
+
+/// Returns the bonus given to a quiet move that caused a beta cutoff at the given depth.
+pub fn history_bonus(depth: i32) -> i32 {
+    (depth * depth * 16).min(1600)
+}
+
+/// Adds `bonus` to a history entry, scaled down as the entry approaches `HISTORY_MAX`. This "gravity"
+/// keeps the table bounded and lets old information decay as new cutoffs come in.
+fn apply_gravity(entry: &mut i32, bonus: i32) {
+    let bonus = bonus.clamp(-HISTORY_MAX, HISTORY_MAX);
+    *entry += bonus - *entry * bonus.abs() / HISTORY_MAX;
+}
+
+/// The move ordering heuristics that are learned during the search.
+#[derive(Clone)]
+pub struct Heuristics {
+    /// Two quiet moves per ply that recently caused a beta cutoff.
+    pub killers: [[Option<Move>; 2]; MAX_PLY],
+    /// The butterfly history, indexed as `history[color][from][to]`.
+    pub history: Box<[[[i32; 64]; 64]; 2]>,
+    /// The quiet move that refuted each previous move, indexed as `counter_moves[color][piece][to]`
+    /// of the previous move.
+    pub counter_moves: Box<[[[Option<Move>; 64]; 6]; 2]>,
+}
+
+impl Heuristics {
+    pub fn new() -> Self {
+        Heuristics {
+            killers: [[None; 2]; MAX_PLY],
+            history: Box::new([[[0; 64]; 64]; 2]),
+            counter_moves: Box::new([[[None; 64]; 6]; 2]),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    pub fn history_score(&self, color: Color, mv: Move) -> i32 {
+        self.history[color.index()][mv.from.index()][mv.to.index()]
+    }
+
+    /// Returns the counter move stored for the last move played on the board.
+    pub fn counter_move(&self, board: &Board) -> Option<Move> {
+        let previous = board.history.last()?.mv?;
+        let (piece, _) = board.piece_at(previous.to)?;
+        self.counter_moves[board.side_to_move.index()][piece.index()][previous.to.index()]
+    }
+
+    /// Updates the heuristics after the quiet move `best` caused a beta cutoff. The quiet moves that
+    /// were searched before it without causing a cutoff are penalized.
+    pub fn update_quiet(&mut self, board: &Board, ply: usize, depth: i32, best: Move, searched_quiets: &[Move]) {
+        let color = board.side_to_move.index();
+        let bonus = history_bonus(depth);
+        apply_gravity(&mut self.history[color][best.from.index()][best.to.index()], bonus);
+        for mv in searched_quiets.iter().filter(|mv| **mv != best) {
+            apply_gravity(&mut self.history[color][mv.from.index()][mv.to.index()], -bonus);
+        }
+
+        if ply < MAX_PLY && self.killers[ply][0] != Some(best) {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = Some(best);
+        }
+
+        if let Some(previous) = board.history.last().and_then(|state| state.mv) {
+            if let Some((piece, _)) = board.piece_at(previous.to) {
+                self.counter_moves[color][piece.index()][previous.to.index()] = Some(best);
+            }
+        }
+    }
+}
+
+impl Default for Heuristics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    TtMove,
+    GenerateCaptures,
+    Captures,
+    Killers,
+    CounterMove,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+/// Yields the legal moves of a position in the order they should be searched: the TT move, captures
+/// and promotions that don't lose material by MVV-LVA, the killer moves of the ply, the counter move
+/// to the previous move, the remaining quiet moves ranked by their history score, and finally the
+/// captures that lose material according to the static exchange evaluation.
+///
+/// Moves are generated lazily per stage, so a cutoff on the TT move never generates any moves.
+pub struct MovePicker {
+    stage: Stage,
+    tt_move: Option<Move>,
+    killers: [Option<Move>; 2],
+    counter_move: Option<Move>,
+    noisy_only: bool,
+    moves: Vec<(Move, i32)>,
+    bad_captures: Vec<Move>,
+    killer_index: usize,
+}
+
+impl MovePicker {
+    /// Creates a picker for all moves of the position.
+    pub fn new(board: &Board, tt_move: Option<Move>, heuristics: &Heuristics, ply: usize) -> Self {
+        let killers = if ply < MAX_PLY { heuristics.killers[ply] } else { [None; 2] };
+        MovePicker {
+            stage: Stage::TtMove,
+            tt_move: tt_move.filter(|mv| is_pseudo_legal(board, *mv)),
+            killers,
+            counter_move: heuristics.counter_move(board),
+            noisy_only: false,
+            moves: Vec::new(),
+            bad_captures: Vec::new(),
+            killer_index: 0,
+        }
+    }
+
+    /// Creates a picker that only yields captures and promotions, for the quiescence search.
+    pub fn new_noisy(board: &Board, tt_move: Option<Move>) -> Self {
+        MovePicker {
+            stage: Stage::TtMove,
+            tt_move: tt_move.filter(|mv| is_pseudo_legal(board, *mv) && !board.is_quiet(*mv)),
+            killers: [None; 2],
+            counter_move: None,
+            noisy_only: true,
+            moves: Vec::new(),
+            bad_captures: Vec::new(),
+            killer_index: 0,
+        }
+    }
+
+    /// Returns true if the move was, or will be, yielded by an earlier stage than the quiets.
+    fn is_special(&self, mv: Move) -> bool {
+        Some(mv) == self.tt_move || self.killers.contains(&Some(mv)) || Some(mv) == self.counter_move
+    }
+
+    /// Removes and returns the highest scored move left in the list. A selection sort is used
+    /// instead of sorting up front, since most nodes cut off after only a few moves.
+    fn pick_best(&mut self) -> Option<Move> {
+        let best = (0..self.moves.len()).max_by_key(|&i| self.moves[i].1)?;
+        Some(self.moves.swap_remove(best).0)
+    }
+
+    /// Returns the next legal move to search, or None when all moves have been yielded.
+    pub fn next(&mut self, board: &Board, heuristics: &Heuristics) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::TtMove => {
+                    self.stage = Stage::GenerateCaptures;
+                    if let Some(mv) = self.tt_move {
+                        if is_legal(board, mv) {
+                            return Some(mv);
+                        }
+                    }
+                }
+                Stage::GenerateCaptures => {
+                    self.moves = generate_pseudo_legal_moves(board)
+                        .into_iter()
+                        .filter(|mv| !board.is_quiet(*mv) && Some(*mv) != self.tt_move)
+                        .map(|mv| (mv, mvv_lva(board, mv)))
+                        .collect();
+                    self.stage = Stage::Captures;
+                }
+                Stage::Captures => match self.pick_best() {
+                    Some(mv) => {
+                        if !see_ge(board, mv, 0) {
+                            self.bad_captures.push(mv);
+                        } else if is_legal(board, mv) {
+                            return Some(mv);
+                        }
+                    }
+                    None => self.stage = if self.noisy_only { Stage::BadCaptures } else { Stage::Killers },
+                },
+                Stage::Killers => {
+                    if self.killer_index >= self.killers.len() {
+                        self.stage = Stage::CounterMove;
+                        continue;
+                    }
+                    let killer = self.killers[self.killer_index];
+                    self.killer_index += 1;
+                    if let Some(mv) = killer {
+                        if Some(mv) != self.tt_move && is_quiet_pseudo_legal(board, mv) && is_legal(board, mv) {
+                            return Some(mv);
+                        }
+                    }
+                }
+                Stage::CounterMove => {
+                    self.stage = Stage::GenerateQuiets;
+                    if let Some(mv) = self.counter_move {
+                        if Some(mv) != self.tt_move
+                            && !self.killers.contains(&Some(mv))
+                            && is_quiet_pseudo_legal(board, mv)
+                            && is_legal(board, mv)
+                        {
+                            return Some(mv);
+                        }
+                    }
+                }
+                Stage::GenerateQuiets => {
+                    let color = board.side_to_move;
+                    self.moves = generate_pseudo_legal_moves(board)
+                        .into_iter()
+                        .filter(|mv| board.is_quiet(*mv) && !self.is_special(*mv))
+                        .map(|mv| (mv, heuristics.history_score(color, mv)))
+                        .collect();
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => match self.pick_best() {
+                    Some(mv) => {
+                        if is_legal(board, mv) {
+                            return Some(mv);
+                        }
+                    }
+                    None => self.stage = Stage::BadCaptures,
+                },
+                Stage::BadCaptures => {
+                    // Bad captures were pushed best first, so yield them in that order.
+                    if self.bad_captures.is_empty() {
+                        self.stage = Stage::Done;
+                        continue;
+                    }
+                    let mv = self.bad_captures.remove(0);
+                    if is_legal(board, mv) {
+                        return Some(mv);
+                    }
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+}
+
+/// Returns true if the move is a pseudo-legal quiet move in this position.
+fn is_quiet_pseudo_legal(board: &Board, mv: Move) -> bool {
+    is_pseudo_legal(board, mv) && board.is_quiet(mv)
+}
@@ -0,0 +1,130 @@
+use crate::board::{
+    bitboard::BitBoard,
+    board::Board,
+    movegen::{attackers_to, bishop_attacks, rook_attacks},
+    moves::Move,
+    piece::Piece,
+    square::Square,
+};
+
+/// Piece values used by the static exchange evaluation, indexed by `Piece::index`. The king is
+/// given a huge value so that capturing it always ends the exchange.
+pub const SEE_VALUES: [i32; 6] = [100, 500, 300, 300, 900, 20000];
+
+/// The least valuable piece types first, the order in which attackers join the exchange.
+const ATTACKER_ORDER: [Piece; 6] = [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen, Piece::King];
+
+fn value(piece: Piece) -> i32 {
+    SEE_VALUES[piece.index()]
+}
+
+/// Statically evaluates the material balance of the exchange sequence started by `mv` on its
+/// destination square, assuming both sides always recapture with their least valuable attacker and
+/// may stop capturing whenever that is better for them. Sliders hidden behind other attackers
+/// (x-rays) join the exchange as the pieces in front of them capture.
+///
+/// Returns the expected material gain in centipawns for the side making the move. Quiet moves
+/// evaluate to the material lost if the moved piece is captured, or zero.
+///
+/// # Example
+///
+/// ```
+/// use knightbot::board::board::Board;
+/// use knightbot::search::see::see;
+///
+/// // The rook captures an undefended pawn.
+/// let board = Board::from_fen("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1").unwrap();
+/// assert_eq!(see(&board, "e1e5".parse().unwrap()), 100);
+/// ```
+pub fn see(board: &Board, mv: Move) -> i32 {
+    if board.is_castling(mv) {
+        return 0;
+    }
+    let to = mv.to;
+    let mut gain = [0i32; 32];
+    let mut depth = 0;
+
+    let mut occupancy = board.all_pieces & !BitBoard::from(mv.from);
+    gain[0] = board.captured_piece(mv).map_or(0, value);
+    if board.is_en_passant(mv) {
+        occupancy &= !BitBoard::from(Square::from_rank_file(mv.from.rank(), to.file()));
+    }
+    let mut victim = board.moved_piece(mv);
+    if let Some(promotion) = mv.promotion {
+        gain[0] += value(promotion) - value(Piece::Pawn);
+        victim = promotion;
+    }
+
+    let diagonal_sliders = board.white_bishops | board.black_bishops | board.white_queens | board.black_queens;
+    let straight_sliders = board.white_rooks | board.black_rooks | board.white_queens | board.black_queens;
+    let mut attackers = attackers_to(board, to, occupancy) & occupancy;
+    let mut side = board.side_to_move.opposite();
+
+    loop {
+        let side_attackers = attackers & board.color_pieces(side);
+        if side_attackers.is_empty() {
+            break;
+        }
+        let (piece, from) = ATTACKER_ORDER
+            .iter()
+            .find_map(|&piece| {
+                let candidates = side_attackers & board.pieces(piece, side);
+                (!candidates.is_empty()).then(|| (piece, candidates.lsb()))
+            })
+            .expect("attackers of the side are not empty");
+
+        // The king can only recapture if the other side has no attackers left.
+        if piece == Piece::King && !(attackers & board.color_pieces(side.opposite())).is_empty() {
+            break;
+        }
+
+        depth += 1;
+        gain[depth] = value(victim) - gain[depth - 1];
+
+        occupancy &= !BitBoard::from(from);
+        if matches!(piece, Piece::Pawn | Piece::Bishop | Piece::Queen) {
+            attackers |= bishop_attacks(to, occupancy) & diagonal_sliders;
+        }
+        if matches!(piece, Piece::Rook | Piece::Queen) {
+            attackers |= rook_attacks(to, occupancy) & straight_sliders;
+        }
+        attackers &= occupancy;
+
+        victim = piece;
+        side = side.opposite();
+        if depth == gain.len() - 1 {
+            break;
+        }
+    }
+
+    // Each side may choose not to continue the exchange, so propagate the best choice back up.
+    while depth > 0 {
+        gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+        depth -= 1;
+    }
+    gain[0]
+}
+
+/// Returns true if the static exchange evaluation of the move is at least `threshold`.
+///
+/// Most calls are decided without resolving the exchange: the move can't gain more than the piece
+/// it captures, and if it still reaches the threshold after losing the moved piece, no
+/// recapture can bring it below.
+pub fn see_ge(board: &Board, mv: Move, threshold: i32) -> bool {
+    if board.is_castling(mv) {
+        return threshold <= 0;
+    }
+    let mut balance = board.captured_piece(mv).map_or(0, value) - threshold;
+    let mut at_risk = board.moved_piece(mv);
+    if let Some(promotion) = mv.promotion {
+        balance += value(promotion) - value(Piece::Pawn);
+        at_risk = promotion;
+    }
+    if balance < 0 {
+        return false;
+    }
+    if balance - value(at_risk) >= 0 {
+        return true;
+    }
+    see(board, mv) >= threshold
+}
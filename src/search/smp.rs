@@ -0,0 +1,140 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::board::board::Board;
+
+use super::{tt::TranspositionTable, SearchConfig, SearchLimits, SearchResult, Searcher};
+
+/// The stack size of helper threads. The search recurses once per ply, so the default stack of
+/// spawned threads is too small for deep searches.
+const HELPER_STACK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Runs a lazy SMP search: every thread searches the same position with its own move ordering
+/// heuristics, and they cooperate only through the shared lock-free transposition table. Helper
+/// threads fill the table with results the main thread picks up, which makes it reach deeper.
+pub struct ThreadPool {
+    pub tt: Arc<TranspositionTable>,
+    pub stop: Arc<AtomicBool>,
+    /// Shared with the main searcher, see `Searcher::pondering`.
+    pub pondering: Arc<AtomicBool>,
+    nodes: Arc<AtomicU64>,
+    /// The main searcher followed by the helpers.
+    searchers: Vec<Searcher>,
+}
+
+impl ThreadPool {
+    /// Creates a pool of `threads` searchers sharing a transposition table of `tt_size_mb` megabytes.
+    pub fn new(threads: usize, tt_size_mb: usize) -> Self {
+        let mut pool = ThreadPool {
+            tt: Arc::new(TranspositionTable::new(tt_size_mb)),
+            stop: Arc::new(AtomicBool::new(false)),
+            pondering: Arc::new(AtomicBool::new(false)),
+            nodes: Arc::new(AtomicU64::new(0)),
+            searchers: Vec::new(),
+        };
+        pool.set_threads(threads);
+        pool
+    }
+
+    pub fn threads(&self) -> usize {
+        self.searchers.len()
+    }
+
+    /// Changes the number of search threads. Existing searchers keep their heuristics.
+    pub fn set_threads(&mut self, threads: usize) {
+        let threads = threads.max(1);
+        let config = self.searchers.first().map(|main| main.config).unwrap_or_default();
+        self.searchers.truncate(threads);
+        while self.searchers.len() < threads {
+            let mut searcher = Searcher::with_shared_state(self.tt.clone(), self.stop.clone(), self.nodes.clone());
+            searcher.thread_id = self.searchers.len();
+            searcher.config = config;
+            searcher.pondering = self.pondering.clone();
+            self.searchers.push(searcher);
+        }
+    }
+
+    /// Replaces the transposition table with an empty one of `size_mb` megabytes.
+    pub fn resize_tt(&mut self, size_mb: usize) {
+        self.tt = Arc::new(TranspositionTable::new(size_mb));
+        for searcher in &mut self.searchers {
+            searcher.tt = self.tt.clone();
+        }
+    }
+
+    /// Clears the transposition table and the heuristics of all threads, e.g. before a new game.
+    pub fn clear(&mut self) {
+        for searcher in &mut self.searchers {
+            searcher.clear();
+        }
+    }
+
+    /// Sets the search configuration of all threads.
+    pub fn set_config(&mut self, config: SearchConfig) {
+        for searcher in &mut self.searchers {
+            searcher.config = config;
+        }
+    }
+
+    /// Sets the time reserved for communication with the GUI. Only the main thread manages time.
+    pub fn set_move_overhead(&mut self, move_overhead: Duration) {
+        self.searchers[0].move_overhead = move_overhead;
+    }
+
+    /// The searcher running on the calling thread. Its `on_iteration` callback is the only one
+    /// called during a search.
+    pub fn main_searcher(&mut self) -> &mut Searcher {
+        &mut self.searchers[0]
+    }
+
+    /// Searches the position on all threads until the main thread reaches a limit or `stop` is
+    /// set, and returns the result of the thread that completed the deepest iteration.
+    pub fn search(&mut self, board: &Board, limits: SearchLimits) -> SearchResult {
+        self.stop.store(false, Ordering::Relaxed);
+        self.run(board, limits)
+    }
+
+    /// Like `search`, but doesn't reset the stop flag first, so that a stop requested from another
+    /// thread before the search started isn't lost.
+    pub fn run(&mut self, board: &Board, limits: SearchLimits) -> SearchResult {
+        self.nodes.store(0, Ordering::Relaxed);
+        let stop = self.stop.clone();
+        let (main, helpers) = self.searchers.split_first_mut().expect("the pool has at least one thread");
+
+        let (main_result, helper_results) = thread::scope(|scope| {
+            let handles: Vec<_> = helpers
+                .iter_mut()
+                .map(|helper| {
+                    let (mut board, limits) = (board.clone(), limits.clone());
+                    thread::Builder::new()
+                        .stack_size(HELPER_STACK_SIZE)
+                        .spawn_scoped(scope, move || helper.iterative_deepening(&mut board, limits))
+                        .expect("failed to spawn search thread")
+                })
+                .collect();
+
+            let main_result = main.iterative_deepening(&mut board.clone(), limits);
+            // The helpers run until the main thread is done.
+            stop.store(true, Ordering::Relaxed);
+            let helper_results: Vec<SearchResult> =
+                handles.into_iter().map(|handle| handle.join().expect("search thread panicked")).collect();
+            (main_result, helper_results)
+        });
+
+        let mut best = main_result;
+        for result in helper_results {
+            let deeper = result.depth > best.depth || (result.depth == best.depth && result.score > best.score);
+            if result.best_move.is_some() && deeper {
+                best = SearchResult { nodes: best.nodes, time: best.time, ..result };
+            }
+        }
+        best.nodes = self.nodes.load(Ordering::Relaxed);
+        best
+    }
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use crate::board::{moves::Move, piece::Color};
+
+use super::SearchLimits;
+
+/// The time reserved by default for communication with the GUI, per move.
+pub const DEFAULT_MOVE_OVERHEAD: Duration = Duration::from_millis(10);
+/// The number of moves assumed to be left in the game when the time control doesn't say.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+/// The soft limit grows at most to this multiple of the optimum before reaching the hard limit.
+const MAX_SOFT_SCALE: f64 = 3.0;
+/// The hard limit is at most this multiple of the optimum time.
+const HARD_LIMIT_SCALE: u32 = 4;
+/// Never plan to use more than this fraction of the remaining time on a single move.
+const MAX_TIME_FRACTION: f64 = 0.75;
+
+/// Decides how long to think about a move. The optimum time is spent on an average move; the soft
+/// limit scales it by how unclear the position looks and is checked between iterations, while
+/// the hard limit aborts the search in the middle of an iteration.
+#[derive(Clone, Debug)]
+pub struct TimeManager {
+    /// The time to spend on a move with a stable best move and score.
+    pub optimum: Duration,
+    /// The search is aborted once this much time has passed.
+    pub hard: Duration,
+    /// A fixed move time is always used in full.
+    fixed: bool,
+    previous_best_move: Option<Move>,
+    previous_score: Option<i32>,
+    /// Grows by one whenever the best move changes and decays every iteration, so recent changes
+    /// weigh the most.
+    best_move_changes: f64,
+    /// How much the score dropped since the previous iteration, in centipawns.
+    score_drop: i32,
+    /// The fraction of root nodes spent on the best move in the last iteration.
+    best_move_node_fraction: f64,
+}
+
+impl TimeManager {
+    /// Converts the time control in `limits` into time limits for `side`, with `move_overhead`
+    /// subtracted to account for the delay between the engine and the clock. Returns None if the
+    /// search isn't limited by time.
+    pub fn new(limits: &SearchLimits, side: Color, move_overhead: Duration) -> Option<Self> {
+        let (optimum, hard, fixed) = if let Some(movetime) = limits.movetime {
+            let time = movetime.saturating_sub(move_overhead).max(Duration::from_millis(1));
+            (time, time, true)
+        } else {
+            let (time, increment) = match side {
+                Color::White => (limits.wtime?, limits.winc.unwrap_or_default()),
+                Color::Black => (limits.btime?, limits.binc.unwrap_or_default()),
+            };
+            let moves_to_go = limits.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+            let available = time.saturating_sub(move_overhead).max(Duration::from_millis(1));
+            let max_time = available.mul_f64(MAX_TIME_FRACTION);
+            let optimum = (available / moves_to_go + increment * 3 / 4).min(max_time);
+            (optimum, (optimum * HARD_LIMIT_SCALE).min(max_time), false)
+        };
+        Some(TimeManager {
+            optimum,
+            hard,
+            fixed,
+            previous_best_move: None,
+            previous_score: None,
+            best_move_changes: 0.0,
+            score_drop: 0,
+            best_move_node_fraction: 0.0,
+        })
+    }
+
+    /// Records the result of a completed iteration. `best_move_node_fraction` is the fraction of
+    /// the root nodes that were spent searching the best move.
+    pub fn update(&mut self, best_move: Option<Move>, score: i32, best_move_node_fraction: f64) {
+        self.best_move_changes /= 2.0;
+        if self.previous_best_move.is_some() && best_move != self.previous_best_move {
+            self.best_move_changes += 1.0;
+        }
+        self.score_drop = self.previous_score.map_or(0, |previous| (previous - score).max(0));
+        self.previous_best_move = best_move;
+        self.previous_score = Some(score);
+        self.best_move_node_fraction = best_move_node_fraction;
+    }
+
+    /// The time after which no new iteration should be started, given what the previous
+    /// iterations found.
+    pub fn soft_limit(&self) -> Duration {
+        if self.fixed {
+            return self.hard;
+        }
+        // An unstable best move or a dropping score means the position needs more thought.
+        let instability = 1.0 + 0.5 * self.best_move_changes;
+        let score_drop = 1.0 + self.score_drop.min(100) as f64 / 200.0;
+        // A best move that takes up most of the nodes is unlikely to be overturned.
+        let dominance = (1.5 - self.best_move_node_fraction) * 1.3;
+        let scale = (instability * score_drop * dominance).min(MAX_SOFT_SCALE);
+        self.optimum.mul_f64(scale).min(self.hard)
+    }
+
+    /// Returns true if the search should not start another iteration after `elapsed`.
+    pub fn should_stop(&self, elapsed: Duration) -> bool {
+        elapsed >= self.soft_limit()
+    }
+
+    /// Returns true if the search must be aborted after `elapsed`.
+    pub fn out_of_time(&self, elapsed: Duration) -> bool {
+        elapsed >= self.hard
+    }
+}
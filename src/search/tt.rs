@@ -0,0 +1,144 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::board::moves::Move;
+
+use super::MATE_IN_MAX;
+
+/// How the stored score relates to the true score of the position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is exact (a PV node).
+    Exact,
+    /// The score is a lower bound (the search failed high).
+    Lower,
+    /// The score is an upper bound (the search failed low).
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TtEntry {
+    pub key: u64,
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub depth: i32,
+    pub bound: Bound,
+}
+
+impl TtEntry {
+    /// Packs everything but the key into 64 bits: the move in bits 0-15, the score in bits 16-31,
+    /// the depth in bits 32-39 and the bound in bits 40-41. Bit 42 is always set, so a used slot
+    /// never holds zero.
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        self.best_move.map_or(0, Move::pack) as u64
+            | (self.score as i16 as u16 as u64) << 16
+            | (self.depth.clamp(i8::MIN as i32, i8::MAX as i32) as i8 as u8 as u64) << 32
+            | bound << 40
+            | 1 << 42
+    }
+
+    fn unpack(key: u64, data: u64) -> TtEntry {
+        let bound = match (data >> 40) & 3 {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        };
+        TtEntry {
+            key,
+            best_move: Move::unpack(data as u16),
+            score: (data >> 16) as u16 as i16 as i32,
+            depth: (data >> 32) as u8 as i8 as i32,
+            bound,
+        }
+    }
+}
+
+/// One slot of the table. The key is stored XOR'ed with the data, so an entry torn by two threads
+/// writing at the same time fails verification on probe instead of returning mixed data.
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// A hash table of search results, indexed by the Zobrist hash of the position. The table is
+/// lock-free and can be shared between search threads.
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+}
+
+impl TranspositionTable {
+    /// Creates a table using roughly `size_mb` megabytes.
+    pub fn new(size_mb: usize) -> Self {
+        let count = (size_mb * 1024 * 1024 / std::mem::size_of::<Slot>()).max(1);
+        TranspositionTable {
+            slots: (0..count).map(|_| Slot { key: AtomicU64::new(0), data: AtomicU64::new(0) }).collect(),
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        // Multiply-shift maps the key uniformly onto the table without requiring a power of two size.
+        ((key as u128 * self.slots.len() as u128) >> 64) as usize
+    }
+
+    /// Returns the entry for the position, if one is stored.
+    pub fn probe(&self, key: u64) -> Option<TtEntry> {
+        let slot = &self.slots[self.index(key)];
+        let data = slot.data.load(Ordering::Relaxed);
+        let stored_key = slot.key.load(Ordering::Relaxed) ^ data;
+        (data != 0 && stored_key == key).then(|| TtEntry::unpack(key, data))
+    }
+
+    /// Stores a search result. Entries are always replaced, except that a move already known
+    /// for the same position is kept if the new result has none.
+    pub fn store(&self, key: u64, best_move: Option<Move>, score: i32, depth: i32, bound: Bound) {
+        let best_move = match self.probe(key) {
+            Some(old) if best_move.is_none() => old.best_move,
+            _ => best_move,
+        };
+        let data = TtEntry { key, best_move, score, depth, bound }.pack();
+        let slot = &self.slots[self.index(key)];
+        slot.key.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns how full the table is in permille, sampled from the first thousand entries.
+    pub fn hashfull(&self) -> u32 {
+        let sample = self.slots.len().min(1000);
+        let used = self.slots[..sample].iter().filter(|slot| slot.data.load(Ordering::Relaxed) != 0).count();
+        (used * 1000 / sample) as u32
+    }
+}
+
+/// Converts a mate score from "mate in n plies from the root" to "mate in n plies from this node",
+/// so that it stays correct when the entry is found at a different ply.
+pub fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_IN_MAX {
+        score + ply as i32
+    } else if score <= -MATE_IN_MAX {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+/// Reverses `score_to_tt`.
+pub fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_IN_MAX {
+        score - ply as i32
+    } else if score <= -MATE_IN_MAX {
+        score + ply as i32
+    } else {
+        score
+    }
+}
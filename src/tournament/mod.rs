@@ -0,0 +1,344 @@
+pub mod player;
+pub mod stats;
+
+use std::{
+    fmt,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::{
+        board::{Board, GameOutcome},
+        movegen::generate_legal_moves,
+        moves::Move,
+        piece::Color,
+    },
+    search::SearchLimits,
+};
+
+use player::{EngineSpec, Player, PlayerError};
+use stats::{MatchStats, Sprt, SprtVerdict};
+
+/// How long an engine may think beyond its allotted time before it is considered hung and loses
+/// the game. Under a clock it has lost on time by then anyway.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long each engine may think.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeControl {
+    /// A clock starting at `base` and growing by `increment` after every move. An engine that
+    /// runs out of time loses.
+    Fischer { base: Duration, increment: Duration },
+    /// A fixed time per move.
+    MoveTime(Duration),
+    /// A fixed number of nodes per move.
+    Nodes(u64),
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    /// Parses a clock as "<base>+<increment>" or "<base>", both in seconds, e.g. "10+0.1".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (base, increment) = s.split_once('+').unwrap_or((s, "0"));
+        let seconds = |value: &str| {
+            value.parse::<f64>().ok().and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        };
+        match (seconds(base), seconds(increment)) {
+            (Some(base), Some(increment)) => Ok(TimeControl::Fischer { base, increment }),
+            _ => Err(format!("invalid time control {s}")),
+        }
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeControl::Fischer { base, increment } => write!(f, "{}+{}", base.as_secs_f64(), increment.as_secs_f64()),
+            TimeControl::MoveTime(time) => write!(f, "{} ms per move", time.as_millis()),
+            TimeControl::Nodes(nodes) => write!(f, "{nodes} nodes per move"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchConfig {
+    /// The engines playing. Results are counted from the first engine's point of view.
+    pub engines: [EngineSpec; 2],
+    /// The starting positions, each played twice with colors reversed. Empty means the standard
+    /// starting position.
+    pub openings: Vec<Board>,
+    /// The number of games, played in pairs.
+    pub games: usize,
+    /// The number of games played at the same time.
+    pub concurrency: usize,
+    pub time_control: TimeControl,
+    pub hash_mb: usize,
+    /// Games still going after this many plies are adjudicated as draws.
+    pub max_plies: usize,
+    /// Stops the match early once the test reaches a verdict.
+    pub sprt: Option<Sprt>,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        MatchConfig {
+            engines: [EngineSpec::default(), EngineSpec::default()],
+            openings: Vec::new(),
+            games: 100,
+            concurrency: 1,
+            time_control: TimeControl::Fischer { base: Duration::from_secs(10), increment: Duration::from_millis(100) },
+            hash_mb: 16,
+            max_plies: 600,
+            sprt: None,
+        }
+    }
+}
+
+/// Why a game ended.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Termination {
+    Outcome(GameOutcome),
+    /// The game reached `MatchConfig::max_plies`.
+    MaxPlies,
+    /// The side to move ran out of time, or didn't answer at all.
+    TimeForfeit,
+    /// The side to move played the given illegal move.
+    IllegalMove(Move),
+    /// An engine crashed or misbehaved.
+    EngineFailure(String),
+}
+
+impl Termination {
+    /// True if an engine lost because it failed, so it has to be restarted.
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Termination::TimeForfeit | Termination::EngineFailure(_))
+    }
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Termination::Outcome(outcome) => write!(f, "{}", outcome.reason()),
+            Termination::MaxPlies => write!(f, "Adjudicated as a draw"),
+            Termination::TimeForfeit => write!(f, "Loss on time"),
+            Termination::IllegalMove(mv) => write!(f, "Illegal move {mv}"),
+            Termination::EngineFailure(error) => write!(f, "Engine failure: {error}"),
+        }
+    }
+}
+
+/// A finished game of a match.
+#[derive(Clone, Debug)]
+pub struct GameRecord {
+    /// The number of the game, from 0.
+    pub game: usize,
+    /// The index of the engine playing white in `MatchConfig::engines`.
+    pub white: usize,
+    pub start_fen: String,
+    pub moves: Vec<Move>,
+    /// White's points: 1.0, 0.5 or 0.0.
+    pub result: f64,
+    pub termination: Termination,
+}
+
+impl GameRecord {
+    /// The points scored by the first engine.
+    pub fn first_engine_points(&self) -> f64 {
+        if self.white == 0 {
+            self.result
+        } else {
+            1.0 - self.result
+        }
+    }
+
+    /// The result as written in PGN: "1-0", "0-1" or "1/2-1/2".
+    pub fn result_string(&self) -> &'static str {
+        match self.result {
+            result if result > 0.75 => "1-0",
+            result if result < 0.25 => "0-1",
+            _ => "1/2-1/2",
+        }
+    }
+}
+
+impl TimeControl {
+    /// The search limits for the side to move, with the clocks indexed by color.
+    fn limits(&self, clocks: &[Duration; 2]) -> SearchLimits {
+        match *self {
+            TimeControl::Fischer { increment, .. } => SearchLimits {
+                wtime: Some(clocks[Color::White.index()]),
+                btime: Some(clocks[Color::Black.index()]),
+                winc: Some(increment),
+                binc: Some(increment),
+                ..SearchLimits::default()
+            },
+            TimeControl::MoveTime(time) => SearchLimits { movetime: Some(time), ..SearchLimits::default() },
+            TimeControl::Nodes(nodes) => SearchLimits { nodes: Some(nodes), ..SearchLimits::default() },
+        }
+    }
+}
+
+/// Plays one game of the match. The engines play white and black in turn, so each opening is
+/// played once with either color.
+fn play_game(players: [&mut Player; 2], config: &MatchConfig, game: usize) -> GameRecord {
+    let mut board = match config.openings.len() {
+        0 => Board::new(),
+        openings => config.openings[game / 2 % openings].clone(),
+    };
+    let start_fen = board.to_fen();
+    let white = game % 2;
+    let engine_of = |color: Color| if color == Color::White { white } else { 1 - white };
+    let base = match config.time_control {
+        TimeControl::Fischer { base, .. } => base,
+        _ => Duration::ZERO,
+    };
+    let mut clocks = [base; 2];
+    let mut moves = Vec::new();
+
+    let (result, termination) = 'game: {
+        for color in [Color::White, Color::Black] {
+            if let Err(error) = players[engine_of(color)].new_game() {
+                break 'game (if color == Color::White { 0.0 } else { 1.0 }, Termination::EngineFailure(error.to_string()));
+            }
+        }
+        loop {
+            match board.outcome() {
+                Some(outcome @ GameOutcome::Checkmate(winner)) => {
+                    break (if winner == Color::White { 1.0 } else { 0.0 }, Termination::Outcome(outcome))
+                }
+                Some(outcome) => break (0.5, Termination::Outcome(outcome)),
+                None if moves.len() >= config.max_plies => break (0.5, Termination::MaxPlies),
+                None => {}
+            }
+
+            let us = board.side_to_move;
+            let loss = if us == Color::White { 0.0 } else { 1.0 };
+            let allotted = match config.time_control {
+                TimeControl::Fischer { .. } => clocks[us.index()],
+                TimeControl::MoveTime(time) => time,
+                TimeControl::Nodes(_) => Duration::ZERO,
+            };
+            let limits = config.time_control.limits(&clocks);
+            let start = Instant::now();
+            let reply = players[engine_of(us)].best_move(&start_fen, &moves, &board, limits, allotted + RESPONSE_TIMEOUT);
+            let elapsed = start.elapsed();
+            let mv = match reply {
+                Ok(mv) => mv,
+                Err(PlayerError::Timeout) => break (loss, Termination::TimeForfeit),
+                Err(error) => break (loss, Termination::EngineFailure(error.to_string())),
+            };
+            if let TimeControl::Fischer { increment, .. } = config.time_control {
+                if elapsed > clocks[us.index()] {
+                    break (loss, Termination::TimeForfeit);
+                }
+                clocks[us.index()] = clocks[us.index()] - elapsed + increment;
+            }
+            if !generate_legal_moves(&board).contains(&mv) {
+                break (loss, Termination::IllegalMove(mv));
+            }
+            board.make_move(mv);
+            moves.push(mv);
+        }
+    };
+    GameRecord { game, white, start_fen, moves, result, termination }
+}
+
+/// Plays a match between the two engines of the config, `concurrency` games at a time, and
+/// returns the results from the first engine's point of view. `progress` is called after each
+/// game with the game and the results so far.
+///
+/// With an SPRT, no new games are started once it reaches a verdict. The match fails if an engine
+/// can't be started; an engine that fails during a game loses it and is restarted.
+pub fn run_match(config: &MatchConfig, mut progress: impl FnMut(&GameRecord, &MatchStats)) -> Result<MatchStats, PlayerError> {
+    let next_game = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+    let mut stats = MatchStats::default();
+    let mut error = None;
+    thread::scope(|scope| {
+        for _ in 0..config.concurrency.max(1) {
+            let sender = sender.clone();
+            let (next_game, stop) = (&next_game, &stop);
+            scope.spawn(move || {
+                let mut players: [Option<Player>; 2] = [None, None];
+                while !stop.load(Ordering::Relaxed) {
+                    let game = next_game.fetch_add(1, Ordering::Relaxed);
+                    if game >= config.games {
+                        break;
+                    }
+                    for (player, spec) in players.iter_mut().zip(&config.engines) {
+                        if player.is_none() {
+                            match Player::start(spec, config.hash_mb) {
+                                Ok(started) => *player = Some(started),
+                                Err(error) => {
+                                    let _ = sender.send(Err(error));
+                                    return;
+                                }
+                            }
+                        }
+                    }
+                    let [Some(first), Some(second)] = &mut players else { unreachable!("both engines were started") };
+                    let record = play_game([first, second], config, game);
+                    if record.termination.is_failure() {
+                        // The engine that failed lost the game, and is restarted for the next one.
+                        let loser = if record.first_engine_points() == 0.0 { 0 } else { 1 };
+                        players[loser] = None;
+                    }
+                    if sender.send(Ok(record)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        // Only the threads hold senders now, so the loop ends when they are done.
+        drop(sender);
+
+        for message in receiver {
+            match message {
+                Ok(record) => {
+                    stats.add(record.first_engine_points());
+                    progress(&record, &stats);
+                    if config.sprt.is_some_and(|sprt| sprt.verdict(&stats) != SprtVerdict::Continue) {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                }
+                Err(start_error) => {
+                    stop.store(true, Ordering::Relaxed);
+                    error.get_or_insert(start_error);
+                }
+            }
+        }
+    });
+    match error {
+        Some(error) => Err(error),
+        None => Ok(stats),
+    }
+}
+
+/// A summary of a match: the results, the Elo difference with its 95% error bars and the state
+/// of the SPRT, if any.
+pub fn match_report(config: &MatchConfig, stats: &MatchStats) -> String {
+    let (elo, error) = stats.elo();
+    let mut report = format!(
+        "Score of {} vs {}: {stats}\nElo difference: {elo:.1} +/- {error:.1}",
+        config.engines[0], config.engines[1]
+    );
+    if let Some(sprt) = config.sprt {
+        let (lower, upper) = sprt.bounds();
+        report += &format!(
+            "\nSPRT ({}, {}): LLR {:.2} ({lower:.2}, {upper:.2}), {}",
+            sprt.elo0,
+            sprt.elo1,
+            sprt.llr(stats),
+            sprt.verdict(stats)
+        );
+    }
+    report
+}
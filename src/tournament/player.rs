@@ -0,0 +1,284 @@
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    str::FromStr,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::{board::Board, moves::Move},
+    eval::nnue::{Network, NetworkError},
+    search::{SearchConfig, SearchLimits, Searcher},
+};
+
+/// How long a UCI engine may take to start up or to answer "isready".
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a UCI engine gets to exit after "quit" before it is killed.
+const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub enum PlayerError {
+    Io(io::Error),
+    Network(NetworkError),
+    /// The engine didn't answer in time.
+    Timeout,
+    /// The engine exited or closed its output.
+    Exited,
+    /// The engine sent something that isn't valid UCI.
+    Protocol(String),
+}
+
+impl fmt::Display for PlayerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlayerError::Io(error) => write!(f, "{error}"),
+            PlayerError::Network(error) => write!(f, "{error}"),
+            PlayerError::Timeout => write!(f, "the engine didn't answer in time"),
+            PlayerError::Exited => write!(f, "the engine exited"),
+            PlayerError::Protocol(line) => write!(f, "unexpected output from the engine: {line}"),
+        }
+    }
+}
+
+impl std::error::Error for PlayerError {}
+
+/// An engine taking part in a match.
+///
+/// Specs are written as "knightbot" for this engine with the default settings, optionally
+/// followed by a colon and a comma-separated list of settings: "-nmp", "-lmr", "-rfp", "-fp" and
+/// "-lmp" disable a search technique and "eval=<file>" evaluates with an NNUE network, e.g.
+/// "knightbot:-lmr,eval=net.bin". Anything else is the path of a UCI engine to run as a child
+/// process; use "./knightbot" to run a binary of that name.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EngineSpec {
+    Internal { config: SearchConfig, eval_file: Option<PathBuf> },
+    Uci { command: PathBuf },
+}
+
+impl Default for EngineSpec {
+    fn default() -> Self {
+        EngineSpec::Internal { config: SearchConfig::default(), eval_file: None }
+    }
+}
+
+impl FromStr for EngineSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let settings = match s.strip_prefix("knightbot") {
+            Some("") => "",
+            Some(settings) if settings.starts_with(':') => &settings[1..],
+            _ => return Ok(EngineSpec::Uci { command: s.into() }),
+        };
+        let (mut config, mut eval_file) = (SearchConfig::default(), None);
+        for setting in settings.split(',').filter(|setting| !setting.is_empty()) {
+            match setting {
+                "-nmp" => config.null_move_pruning = false,
+                "-lmr" => config.late_move_reductions = false,
+                "-rfp" => config.reverse_futility_pruning = false,
+                "-fp" => config.futility_pruning = false,
+                "-lmp" => config.late_move_pruning = false,
+                _ => match setting.strip_prefix("eval=") {
+                    Some(path) => eval_file = Some(path.into()),
+                    None => return Err(format!("unknown engine setting {setting}")),
+                },
+            }
+        }
+        Ok(EngineSpec::Internal { config, eval_file })
+    }
+}
+
+impl fmt::Display for EngineSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EngineSpec::Internal { config, eval_file } => {
+                let flags = [
+                    (config.null_move_pruning, "-nmp"),
+                    (config.late_move_reductions, "-lmr"),
+                    (config.reverse_futility_pruning, "-rfp"),
+                    (config.futility_pruning, "-fp"),
+                    (config.late_move_pruning, "-lmp"),
+                ];
+                let mut settings: Vec<String> =
+                    flags.iter().filter(|(enabled, _)| !enabled).map(|(_, flag)| flag.to_string()).collect();
+                if let Some(path) = eval_file {
+                    settings.push(format!("eval={}", path.display()));
+                }
+                if settings.is_empty() {
+                    write!(f, "knightbot")
+                } else {
+                    write!(f, "knightbot:{}", settings.join(","))
+                }
+            }
+            EngineSpec::Uci { command } => write!(f, "{}", command.display()),
+        }
+    }
+}
+
+/// A UCI engine running as a child process. Its output is read on a separate thread so that
+/// waiting for an answer can time out.
+pub struct UciPlayer {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl UciPlayer {
+    /// Starts the engine and waits until it is ready.
+    pub fn start(command: &Path, hash_mb: usize) -> Result<UciPlayer, PlayerError> {
+        let mut child =
+            Command::new(command).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn().map_err(PlayerError::Io)?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut player = UciPlayer { child, stdin, lines };
+        player.send("uci")?;
+        player.wait_for("uciok", STARTUP_TIMEOUT)?;
+        player.send(&format!("setoption name Hash value {hash_mb}"))?;
+        player.send("isready")?;
+        player.wait_for("readyok", STARTUP_TIMEOUT)?;
+        Ok(player)
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), PlayerError> {
+        writeln!(self.stdin, "{command}").and_then(|_| self.stdin.flush()).map_err(|_| PlayerError::Exited)
+    }
+
+    /// Waits for a line whose first word is `token` and returns it.
+    fn wait_for(&self, token: &str, timeout: Duration) -> Result<String, PlayerError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(line) if line.split_whitespace().next() == Some(token) => return Ok(line),
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => return Err(PlayerError::Timeout),
+                Err(RecvTimeoutError::Disconnected) => return Err(PlayerError::Exited),
+            }
+        }
+    }
+}
+
+impl Drop for UciPlayer {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + QUIT_TIMEOUT;
+        while Instant::now() < deadline {
+            if !matches!(self.child.try_wait(), Ok(None)) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The "go" command for the limits of a search.
+fn go_command(limits: &SearchLimits) -> String {
+    let mut command = "go".to_string();
+    let durations = [
+        ("wtime", limits.wtime),
+        ("btime", limits.btime),
+        ("winc", limits.winc),
+        ("binc", limits.binc),
+        ("movetime", limits.movetime),
+    ];
+    for (name, duration) in durations {
+        if let Some(duration) = duration {
+            command += &format!(" {name} {}", duration.as_millis());
+        }
+    }
+    if let Some(nodes) = limits.nodes {
+        command += &format!(" nodes {nodes}");
+    }
+    command
+}
+
+/// A started engine, ready to play games.
+pub enum Player {
+    Internal { searcher: Box<Searcher>, network: Option<Arc<Network>> },
+    Uci(UciPlayer),
+}
+
+impl Player {
+    /// Starts the engine of the spec with a hash table of `hash_mb` megabytes.
+    pub fn start(spec: &EngineSpec, hash_mb: usize) -> Result<Player, PlayerError> {
+        match spec {
+            EngineSpec::Internal { config, eval_file } => {
+                let mut searcher = Box::new(Searcher::new(hash_mb));
+                searcher.config = *config;
+                let network = match eval_file {
+                    Some(path) => Some(Arc::new(Network::load(path).map_err(PlayerError::Network)?)),
+                    None => None,
+                };
+                Ok(Player::Internal { searcher, network })
+            }
+            EngineSpec::Uci { command } => Ok(Player::Uci(UciPlayer::start(command, hash_mb)?)),
+        }
+    }
+
+    /// Forgets everything about the previous game.
+    pub fn new_game(&mut self) -> Result<(), PlayerError> {
+        match self {
+            Player::Internal { searcher, .. } => {
+                searcher.clear();
+                Ok(())
+            }
+            Player::Uci(player) => {
+                player.send("ucinewgame")?;
+                player.send("isready")?;
+                player.wait_for("readyok", STARTUP_TIMEOUT).map(|_| ())
+            }
+        }
+    }
+
+    /// Asks for a move in `board`, the position after playing `moves` from `start_fen`. A UCI
+    /// engine that doesn't answer within `timeout` fails with `PlayerError::Timeout`. The move
+    /// is not checked for legality.
+    pub fn best_move(
+        &mut self,
+        start_fen: &str,
+        moves: &[Move],
+        board: &Board,
+        limits: SearchLimits,
+        timeout: Duration,
+    ) -> Result<Move, PlayerError> {
+        match self {
+            Player::Internal { searcher, network } => {
+                let mut board = board.clone();
+                board.set_network(network.clone());
+                let result = searcher.search(&mut board, limits);
+                result.best_move.ok_or_else(|| PlayerError::Protocol("no move in a position with legal moves".to_string()))
+            }
+            Player::Uci(player) => {
+                let mut position = format!("position fen {start_fen}");
+                if !moves.is_empty() {
+                    position += " moves";
+                    for mv in moves {
+                        position += &format!(" {mv}");
+                    }
+                }
+                player.send(&position)?;
+                player.send(&go_command(&limits))?;
+                let line = player.wait_for("bestmove", timeout)?;
+                let reply = line.split_whitespace().nth(1).unwrap_or_default();
+                reply.parse().map_err(|_| PlayerError::Protocol(line.clone()))
+            }
+        }
+    }
+}
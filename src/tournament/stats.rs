@@ -0,0 +1,151 @@
+use std::fmt;
+
+/// The z-score of a two-sided 95% confidence interval.
+const Z_95: f64 = 1.959964;
+
+/// The Elo difference at which the stronger side is expected to score `score` per game.
+pub fn score_to_elo(score: f64) -> f64 {
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+/// The expected score per game of a side that is `elo` points stronger.
+pub fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// The results of a match from the first engine's point of view.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MatchStats {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchStats {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Adds a game in which the first engine scored `points`: 1.0, 0.5 or 0.0.
+    pub fn add(&mut self, points: f64) {
+        if points > 0.75 {
+            self.wins += 1;
+        } else if points > 0.25 {
+            self.draws += 1;
+        } else {
+            self.losses += 1;
+        }
+    }
+
+    /// The mean score per game, or 0.5 before the first game.
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    /// The variance of the score of a single game, or 0 before the first game.
+    fn variance(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        let games = self.games() as f64;
+        let score = self.score();
+        let deviations = [(self.wins, 1.0), (self.draws, 0.5), (self.losses, 0.0)];
+        deviations.iter().map(|&(count, points)| count as f64 * (points - score).powi(2)).sum::<f64>() / games
+    }
+
+    /// The Elo difference between the engines and the half-width of its 95% confidence interval.
+    /// Both are infinite if one engine won or lost every game.
+    pub fn elo(&self) -> (f64, f64) {
+        if self.games() == 0 {
+            return (0.0, f64::INFINITY);
+        }
+        let score = self.score();
+        if score == 0.0 || score == 1.0 {
+            return (score_to_elo(score), f64::INFINITY);
+        }
+        let margin = Z_95 * (self.variance() / self.games() as f64).sqrt();
+        let lower = score_to_elo((score - margin).max(0.0));
+        let upper = score_to_elo((score + margin).min(1.0));
+        (score_to_elo(score), (upper - lower) / 2.0)
+    }
+}
+
+impl fmt::Display for MatchStats {
+    /// Formats the results like "12 - 8 - 30 [0.540] 50": wins, losses, draws, the score and the
+    /// number of games.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} - {} - {} [{:.3}] {}", self.wins, self.losses, self.draws, self.score(), self.games())
+    }
+}
+
+/// The state of a sequential probability ratio test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SprtVerdict {
+    /// The first engine is at most `elo0` points stronger: H0 was accepted.
+    H0,
+    /// The first engine is at least `elo1` points stronger: H1 was accepted.
+    H1,
+    /// More games are needed.
+    Continue,
+}
+
+impl fmt::Display for SprtVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SprtVerdict::H0 => write!(f, "H0 accepted"),
+            SprtVerdict::H1 => write!(f, "H1 accepted"),
+            SprtVerdict::Continue => write!(f, "no verdict yet"),
+        }
+    }
+}
+
+/// A sequential probability ratio test of the hypotheses that the first engine is `elo0` (H0) or
+/// `elo1` (H1) points stronger than the second, with false positive rate `alpha` and false
+/// negative rate `beta`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Sprt { elo0: 0.0, elo1: 5.0, alpha: 0.05, beta: 0.05 }
+    }
+}
+
+impl Sprt {
+    /// The log-likelihood ratio of H1 against H0, using the normal approximation of the score
+    /// distribution. It is 0 while the results don't vary, since nothing can be concluded yet.
+    pub fn llr(&self, stats: &MatchStats) -> f64 {
+        let variance = stats.variance();
+        if variance == 0.0 {
+            return 0.0;
+        }
+        let (score0, score1) = (elo_to_score(self.elo0), elo_to_score(self.elo1));
+        stats.games() as f64 * (score1 - score0) * (2.0 * stats.score() - score0 - score1) / (2.0 * variance)
+    }
+
+    /// The lower and upper bounds of the log-likelihood ratio, where H0 and H1 are accepted.
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    pub fn verdict(&self, stats: &MatchStats) -> SprtVerdict {
+        let llr = self.llr(stats);
+        let (lower, upper) = self.bounds();
+        if llr <= lower {
+            SprtVerdict::H0
+        } else if llr >= upper {
+            SprtVerdict::H1
+        } else {
+            SprtVerdict::Continue
+        }
+    }
+}
+
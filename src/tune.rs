@@ -0,0 +1,311 @@
+use std::{
+    fmt,
+    io::{self, BufRead},
+};
+
+use crate::{
+    board::{board::Board, fen::FenError, piece::{Color, Piece}, validate::PositionError},
+    eval::{trace::eval_trace_with, Param, Tracer, MAX_PHASE},
+};
+
+/// The source of `eval::params`, which `write_params` rewrites with tuned weights.
+const PARAMS_SOURCE: &str = include_str!("eval/params.rs");
+
+const ADAM_BETA1: f64 = 0.9;
+const ADAM_BETA2: f64 = 0.999;
+const ADAM_EPSILON: f64 = 1e-8;
+
+#[derive(Debug)]
+pub enum TuneErrorKind {
+    Io(io::Error),
+    InvalidFen(FenError),
+    /// The FEN describes a position that can't occur in a game.
+    InvalidPosition(PositionError),
+    /// The line has no game result, or one that can't be parsed.
+    InvalidResult(String),
+}
+
+/// An error in a file of labelled positions, at the given line (starting at 1).
+#[derive(Debug)]
+pub struct TuneError {
+    pub line: usize,
+    pub kind: TuneErrorKind,
+}
+
+impl fmt::Display for TuneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            TuneErrorKind::Io(error) => write!(f, "{error}"),
+            TuneErrorKind::InvalidFen(error) => write!(f, "{error}"),
+            TuneErrorKind::InvalidPosition(error) => write!(f, "invalid position: {error}"),
+            TuneErrorKind::InvalidResult(result) => write!(f, "invalid game result: {result}"),
+        }
+    }
+}
+
+impl std::error::Error for TuneError {}
+
+/// The offset of each group of weights in the flat weight vector, indexed like `Param::ALL`.
+fn param_offsets() -> [usize; Param::ALL.len()] {
+    let mut offsets = [0; Param::ALL.len()];
+    let mut offset = 0;
+    for (i, param) in Param::ALL.iter().enumerate() {
+        offsets[i] = offset;
+        offset += param.weights().len();
+    }
+    offsets
+}
+
+/// The weights of `eval::params` as one flat vector of middlegame and endgame values, ordered
+/// like `Param::ALL`.
+pub fn initial_weights() -> Vec<[f64; 2]> {
+    Param::ALL
+        .iter()
+        .flat_map(|param| param.weights())
+        .map(|score| [score.mg as f64, score.eg as f64])
+        .collect()
+}
+
+/// Counts the uses of each weight, white's positive and black's negative.
+struct CoefficientTracer {
+    offsets: [usize; Param::ALL.len()],
+    coefficients: Vec<i32>,
+}
+
+impl Tracer for CoefficientTracer {
+    fn record(&mut self, param: Param, index: usize, color: Color, count: i32) {
+        let offset = self.offsets[Param::ALL.iter().position(|&p| p == param).expect("every param is in Param::ALL")];
+        self.coefficients[offset + index] += if color == Color::White { count } else { -count };
+    }
+}
+
+/// A position labelled with the result of its game, reduced to the coefficients of the
+/// evaluation as a linear function of the weights.
+#[derive(Clone, Debug)]
+pub struct TuningPosition {
+    /// The non-zero coefficients and the index of their weight in the flat weight vector.
+    coefficients: Vec<(u32, i16)>,
+    /// The share of the middlegame values in the tapered evaluation, from 0 to 1.
+    middlegame: f64,
+    /// The result from white's point of view: 1 for a win, 0.5 for a draw and 0 for a loss.
+    pub result: f64,
+}
+
+impl TuningPosition {
+    pub fn new(board: &Board, result: f64) -> Self {
+        let mut tracer = CoefficientTracer { offsets: param_offsets(), coefficients: vec![0; initial_weights().len()] };
+        eval_trace_with(board, &mut tracer);
+        let coefficients = tracer
+            .coefficients
+            .iter()
+            .enumerate()
+            .filter(|(_, &coefficient)| coefficient != 0)
+            .map(|(index, &coefficient)| (index as u32, coefficient as i16))
+            .collect();
+        TuningPosition { coefficients, middlegame: board.phase.min(MAX_PHASE) as f64 / MAX_PHASE as f64, result }
+    }
+
+    /// The evaluation with the given weights, from white's point of view. This is the tapered
+    /// evaluation of the engine without rounding.
+    pub fn evaluate(&self, weights: &[[f64; 2]]) -> f64 {
+        let (mut mg, mut eg) = (0.0, 0.0);
+        for &(index, coefficient) in &self.coefficients {
+            let [weight_mg, weight_eg] = weights[index as usize];
+            mg += weight_mg * coefficient as f64;
+            eg += weight_eg * coefficient as f64;
+        }
+        mg * self.middlegame + eg * (1.0 - self.middlegame)
+    }
+}
+
+/// Parses a line of a file of labelled positions: a FEN followed by the result of the game, either
+/// in brackets as in "[1.0]", "[0.5]" or "[1-0]", as a final "1-0", "0-1" or "1/2-1/2", as an
+/// EPD "c9" operation, or as the last field of the "<fen> | <score> | <result>" lines written by
+/// `datagen`. Positions that fail `Board::validate` are rejected.
+pub fn parse_labelled_position(line: &str) -> Result<(Board, f64), TuneErrorKind> {
+    let line = line.trim();
+    let (fen, result) = if let Some((fen, result)) = line.split_once('[') {
+        (fen, result.trim_end_matches(']'))
+    } else if let Some((fen, fields)) = line.split_once('|') {
+        (fen, fields.rsplit('|').next().unwrap_or_default())
+    } else if let Some((fen, result)) = line.split_once("c9") {
+        (fen, result.trim().trim_end_matches(';').trim_matches('"'))
+    } else {
+        match line.rsplit_once(char::is_whitespace) {
+            Some((fen, result)) if ["1-0", "0-1", "1/2-1/2"].contains(&result) => (fen, result),
+            _ => return Err(TuneErrorKind::InvalidResult(line.to_string())),
+        }
+    };
+    let result = match result.trim() {
+        "1-0" => 1.0,
+        "0-1" => 0.0,
+        "1/2-1/2" => 0.5,
+        result => match result.parse::<f64>() {
+            Ok(result) if (0.0..=1.0).contains(&result) => result,
+            _ => return Err(TuneErrorKind::InvalidResult(result.to_string())),
+        },
+    };
+    let board = Board::from_fen(fen.trim().trim_end_matches(';')).map_err(TuneErrorKind::InvalidFen)?;
+    board.validate().map_err(TuneErrorKind::InvalidPosition)?;
+    Ok((board, result))
+}
+
+/// Reads all labelled positions of a file, skipping empty lines and lines starting with "#".
+pub fn read_positions<R: BufRead>(reader: R) -> Result<Vec<TuningPosition>, TuneError> {
+    let mut positions = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|error| TuneError { line: index + 1, kind: TuneErrorKind::Io(error) })?;
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let (board, result) = parse_labelled_position(&line).map_err(|kind| TuneError { line: index + 1, kind })?;
+        positions.push(TuningPosition::new(&board, result));
+    }
+    Ok(positions)
+}
+
+/// Maps an evaluation in centipawns to an expected result between 0 and 1.
+pub fn sigmoid(score: f64, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score / 400.0))
+}
+
+/// The mean squared error between the results and the results expected from the evaluation.
+pub fn loss(positions: &[TuningPosition], weights: &[[f64; 2]], k: f64) -> f64 {
+    let total: f64 = positions.iter().map(|position| (position.result - sigmoid(position.evaluate(weights), k)).powi(2)).sum();
+    total / positions.len().max(1) as f64
+}
+
+/// Finds the scaling constant of the sigmoid that best maps the evaluation to the results, so
+/// tuning doesn't just scale all weights up or down.
+pub fn optimal_k(positions: &[TuningPosition], weights: &[[f64; 2]]) -> f64 {
+    // The loss is unimodal in K, so a ternary search finds the minimum.
+    let (mut low, mut high) = (0.0, 10.0);
+    for _ in 0..100 {
+        let third = (high - low) / 3.0;
+        if loss(positions, weights, low + third) < loss(positions, weights, high - third) {
+            high -= third;
+        } else {
+            low += third;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// Texel tuning: minimizes the loss over all weights with full-batch Adam, starting from the
+/// current weights.
+pub struct Tuner {
+    /// The weights, ordered like `initial_weights`.
+    pub weights: Vec<[f64; 2]>,
+    pub k: f64,
+    pub learning_rate: f64,
+    momentum: Vec<[f64; 2]>,
+    velocity: Vec<[f64; 2]>,
+    epochs: i32,
+}
+
+impl Tuner {
+    /// Creates a tuner for the positions, fitting K to the current weights.
+    pub fn new(positions: &[TuningPosition], learning_rate: f64) -> Self {
+        let weights = initial_weights();
+        let k = optimal_k(positions, &weights);
+        let size = weights.len();
+        Tuner { weights, k, learning_rate, momentum: vec![[0.0; 2]; size], velocity: vec![[0.0; 2]; size], epochs: 0 }
+    }
+
+    /// Runs one epoch over all positions and returns the loss before the update.
+    pub fn epoch(&mut self, positions: &[TuningPosition]) -> f64 {
+        let mut gradient = vec![[0.0; 2]; self.weights.len()];
+        let mut total_loss = 0.0;
+        let scale = self.k * 10f64.ln() / 400.0;
+        for position in positions {
+            let expected = sigmoid(position.evaluate(&self.weights), self.k);
+            let error = expected - position.result;
+            total_loss += error * error;
+            // The derivative of the squared error with respect to the evaluation.
+            let slope = 2.0 * error * expected * (1.0 - expected) * scale;
+            for &(index, coefficient) in &position.coefficients {
+                let derivative = slope * coefficient as f64;
+                gradient[index as usize][0] += derivative * position.middlegame;
+                gradient[index as usize][1] += derivative * (1.0 - position.middlegame);
+            }
+        }
+
+        let count = positions.len().max(1) as f64;
+        self.epochs += 1;
+        for (i, weight) in self.weights.iter_mut().enumerate() {
+            for phase in 0..2 {
+                let g = gradient[i][phase] / count;
+                let m = &mut self.momentum[i][phase];
+                let v = &mut self.velocity[i][phase];
+                *m = ADAM_BETA1 * *m + (1.0 - ADAM_BETA1) * g;
+                *v = ADAM_BETA2 * *v + (1.0 - ADAM_BETA2) * g * g;
+                let m_hat = *m / (1.0 - ADAM_BETA1.powi(self.epochs));
+                let v_hat = *v / (1.0 - ADAM_BETA2.powi(self.epochs));
+                weight[phase] -= self.learning_rate * m_hat / (v_hat.sqrt() + ADAM_EPSILON);
+            }
+        }
+        total_loss / count
+    }
+}
+
+fn format_score([mg, eg]: [f64; 2]) -> String {
+    format!("s({}, {})", mg.round() as i32, eg.round() as i32)
+}
+
+/// Formats the initializer of a group of weights the way `eval::params` lays them out.
+fn format_initializer(param: Param, declaration: &str, weights: &[[f64; 2]]) -> String {
+    if param == Param::Pst {
+        let mut text = String::from("[\n");
+        for (piece, table) in Piece::ALL.iter().zip(weights.chunks(64)) {
+            text.push_str(&format!("    // {piece:?}\n    [\n"));
+            for rank in table.chunks(8) {
+                let squares: Vec<String> =
+                    rank.iter().map(|[mg, eg]| format!("s({:4}, {:4}),", mg.round() as i32, eg.round() as i32)).collect();
+                text.push_str(&format!("        {}\n", squares.join(" ")));
+            }
+            text.push_str("    ],\n");
+        }
+        text.push(']');
+        return text;
+    }
+    if param.weights().len() == 1 {
+        return format_score(weights[0]);
+    }
+
+    let scores: Vec<String> = weights.iter().map(|&score| format_score(score)).collect();
+    let line = format!("[{}]", scores.join(", "));
+    if declaration.len() + line.len() < 120 {
+        return line;
+    }
+    let mut text = String::from("[\n");
+    let mut current = String::from("    ");
+    for score in scores {
+        if current.len() + score.len() + 2 > 100 {
+            text.push_str(current.trim_end());
+            text.push('\n');
+            current = String::from("    ");
+        }
+        current.push_str(&score);
+        current.push_str(", ");
+    }
+    text.push_str(current.trim_end());
+    text.push_str("\n]");
+    text
+}
+
+/// Returns the source of `eval::params` with the given weights, ordered like `initial_weights`,
+/// rounded to whole centipawns. Comments and everything else are kept as they are.
+pub fn write_params(weights: &[[f64; 2]]) -> String {
+    let mut source = PARAMS_SOURCE.to_string();
+    let offsets = param_offsets();
+    for (param, offset) in Param::ALL.iter().zip(offsets) {
+        let group = &weights[offset..offset + param.weights().len()];
+        let start = source.find(&format!("pub const {}:", param.name())).expect("every param is declared in params.rs");
+        let equals = start + source[start..].find(" = ").expect("constants have an initializer") + 3;
+        let end = equals + source[equals..].find(";\n").expect("initializers end with a semicolon");
+        let initializer = format_initializer(*param, &source[start..equals], group);
+        source.replace_range(equals..end, &initializer);
+    }
+    source
+}
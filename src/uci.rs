@@ -0,0 +1,376 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io::{self, BufRead},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    board::{board::Board, fen::START_FEN, movegen::generate_legal_moves, moves::Move},
+    book::{Book, BookSelection},
+    eval::{nnue::Network, trace::eval_report},
+    search::{smp::ThreadPool, time::DEFAULT_MOVE_OVERHEAD, SearchLimits, SearchResult, MAX_PLY},
+};
+
+const ENGINE_NAME: &str = "KnightBot";
+const ENGINE_AUTHOR: &str = "the KnightBot developers";
+
+const DEFAULT_HASH_MB: usize = 16;
+const MAX_HASH_MB: usize = 65536;
+const MAX_THREADS: usize = 256;
+const MAX_MOVE_OVERHEAD_MS: u64 = 5000;
+
+/// The stack size of the search thread, see `smp::HELPER_STACK_SIZE`.
+const SEARCH_STACK_SIZE: usize = 16 * 1024 * 1024;
+
+/// A running search. The thread pool is moved into the search thread and handed back when the
+/// search is joined.
+struct ActiveSearch {
+    handle: JoinHandle<ThreadPool>,
+    /// Set by "stop" or "ponderhit" to let an infinite or pondering search report its best move.
+    release: Arc<AtomicBool>,
+}
+
+/// A chess engine speaking the Universal Chess Interface on stdin and stdout. Searches run on a
+/// separate thread, so commands like "stop" and "isready" are answered while searching.
+pub struct UciEngine {
+    board: Board,
+    /// The network set with the EvalFile option, given to every new position.
+    network: Option<Arc<Network>>,
+    /// The book set with the BookFile option, played from when OwnBook is on.
+    book: Option<Book>,
+    own_book: bool,
+    book_selection: BookSelection,
+    /// Set by the UCI_Chess960 option: castling moves are written as the king capturing its rook.
+    chess960: bool,
+    /// None while a search is running.
+    pool: Option<ThreadPool>,
+    search: Option<ActiveSearch>,
+    stop: Arc<AtomicBool>,
+    pondering: Arc<AtomicBool>,
+}
+
+impl Default for UciEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UciEngine {
+    pub fn new() -> Self {
+        let pool = ThreadPool::new(1, DEFAULT_HASH_MB);
+        UciEngine {
+            board: Board::new(),
+            network: None,
+            book: None,
+            own_book: false,
+            book_selection: BookSelection::Weighted,
+            chess960: false,
+            stop: pool.stop.clone(),
+            pondering: pool.pondering.clone(),
+            pool: Some(pool),
+            search: None,
+        }
+    }
+
+    /// Reads commands from stdin until "quit" or the end of the input.
+    pub fn run(&mut self) {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if !self.handle_command(&line) {
+                break;
+            }
+        }
+        self.stop_search();
+    }
+
+    /// Handles one line of input. Returns false if the engine should quit.
+    pub fn handle_command(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("uci") => {
+                println!("id name {ENGINE_NAME} {}", env!("CARGO_PKG_VERSION"));
+                println!("id author {ENGINE_AUTHOR}");
+                println!("option name Hash type spin default {DEFAULT_HASH_MB} min 1 max {MAX_HASH_MB}");
+                println!("option name Threads type spin default 1 min 1 max {MAX_THREADS}");
+                println!(
+                    "option name Move Overhead type spin default {} min 0 max {MAX_MOVE_OVERHEAD_MS}",
+                    DEFAULT_MOVE_OVERHEAD.as_millis()
+                );
+                println!("option name Ponder type check default false");
+                println!("option name Clear Hash type button");
+                println!("option name EvalFile type string default <empty>");
+                println!("option name OwnBook type check default false");
+                println!("option name BookFile type string default <empty>");
+                println!("option name BookSelection type combo default Weighted var Weighted var Best");
+                println!("option name UCI_Chess960 type check default false");
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("ucinewgame") => {
+                self.pool_mut().clear();
+                self.board = Board::new();
+                self.board.chess960 = self.chess960;
+                self.board.set_network(self.network.clone());
+            }
+            Some("position") => {
+                let args: Vec<&str> = tokens.collect();
+                match parse_position(&args, self.chess960) {
+                    Ok(board) => {
+                        self.stop_search();
+                        self.board = board;
+                        self.board.set_network(self.network.clone());
+                    }
+                    Err(error) => println!("info string {error}"),
+                }
+            }
+            Some("go") => {
+                let args: Vec<&str> = tokens.collect();
+                self.go(&args);
+            }
+            Some("stop") => self.stop_search(),
+            Some("ponderhit") => {
+                self.pondering.store(false, Ordering::Relaxed);
+                if let Some(search) = &self.search {
+                    search.release.store(true, Ordering::Relaxed);
+                }
+            }
+            Some("setoption") => {
+                let args: Vec<&str> = tokens.collect();
+                self.set_option(&args);
+            }
+            // Not part of UCI, but handy for debugging the evaluation from a GUI's console.
+            Some("eval") => println!("{}", eval_report(&self.board)),
+            // Prints a diagram of the current position, as several other engines do.
+            Some("d") => println!("{}", self.board),
+            Some("quit") => return false,
+            Some(command) => println!("info string unknown command: {command}"),
+            None => {}
+        }
+        true
+    }
+
+    /// The thread pool, after stopping a running search.
+    fn pool_mut(&mut self) -> &mut ThreadPool {
+        self.stop_search();
+        self.pool.as_mut().expect("the pool is returned when the search is joined")
+    }
+
+    /// Takes the thread pool out of the engine for a new search, after stopping a running one.
+    fn take_pool(&mut self) -> ThreadPool {
+        self.stop_search();
+        self.pool.take().expect("the pool is returned when the search is joined")
+    }
+
+    /// Stops the running search, which then prints its best move, and takes back the thread pool.
+    fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.stop.store(true, Ordering::Relaxed);
+            search.release.store(true, Ordering::Relaxed);
+            self.pool = Some(search.handle.join().expect("search thread panicked"));
+        }
+    }
+
+    fn go(&mut self, args: &[&str]) {
+        let (limits, infinite, ponder) = match parse_go(args, &self.board) {
+            Ok(go) => go,
+            Err(error) => {
+                println!("info string {error}");
+                return;
+            }
+        };
+        // Infinite and pondering searches must wait for "stop" or "ponderhit", so they always search.
+        if !infinite && !ponder {
+            if let Some(mv) = self.book_move().filter(|mv| limits.search_moves.is_empty() || limits.search_moves.contains(mv)) {
+                self.stop_search();
+                println!("info string book move");
+                println!("bestmove {mv}");
+                return;
+            }
+        }
+        let mut pool = self.take_pool();
+        let board = self.board.clone();
+        let release = Arc::new(AtomicBool::new(false));
+        self.stop.store(false, Ordering::Relaxed);
+        self.pondering.store(ponder, Ordering::Relaxed);
+
+        pool.main_searcher().on_iteration = Some(Box::new(|result: &SearchResult| println!("info {}", result.uci_info())));
+        let thread_release = release.clone();
+        let handle = thread::Builder::new()
+            .stack_size(SEARCH_STACK_SIZE)
+            .spawn(move || {
+                let result = pool.run(&board, limits);
+                // The best move of an infinite or pondering search may only be sent once the GUI
+                // asks for it.
+                if infinite || ponder {
+                    while !thread_release.load(Ordering::Relaxed) {
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                }
+                println!("{}", best_move_line(&result));
+                pool
+            })
+            .expect("failed to spawn search thread");
+        self.search = Some(ActiveSearch { handle, release });
+    }
+
+    fn set_option(&mut self, args: &[&str]) {
+        // setoption name <name, possibly several words> [value <value>]
+        let value_index = args.iter().position(|&arg| arg == "value");
+        let name_args: Vec<&str> =
+            args[..value_index.unwrap_or(args.len())].iter().skip_while(|&&arg| arg == "name").copied().collect();
+        let name = name_args.join(" ");
+        let value = value_index.map(|index| args[index + 1..].join(" "));
+        let parsed_value = value.as_deref().and_then(|value| value.parse::<u64>().ok());
+
+        match (name.to_lowercase().as_str(), parsed_value) {
+            ("hash", Some(mb)) => self.pool_mut().resize_tt((mb as usize).clamp(1, MAX_HASH_MB)),
+            ("threads", Some(threads)) => self.pool_mut().set_threads((threads as usize).clamp(1, MAX_THREADS)),
+            ("move overhead", Some(ms)) => {
+                self.pool_mut().set_move_overhead(Duration::from_millis(ms.min(MAX_MOVE_OVERHEAD_MS)))
+            }
+            ("clear hash", _) => self.pool_mut().clear(),
+            ("evalfile", _) => self.set_eval_file(value.as_deref().unwrap_or_default()),
+            ("ownbook", _) if matches!(value.as_deref(), Some("true" | "false")) => self.own_book = value.as_deref() == Some("true"),
+            ("bookfile", _) => self.set_book_file(value.as_deref().unwrap_or_default()),
+            ("bookselection", _) if matches!(value.as_deref(), Some("Weighted" | "Best")) => {
+                self.book_selection = if value.as_deref() == Some("Best") { BookSelection::Best } else { BookSelection::Weighted }
+            }
+            ("uci_chess960", _) if matches!(value.as_deref(), Some("true" | "false")) => {
+                self.chess960 = value.as_deref() == Some("true");
+                self.board.chess960 = self.chess960 || self.board.requires_chess960();
+            }
+            // The GUI decides when to ponder, there is nothing to set up.
+            ("ponder", _) => {}
+            _ => println!("info string unknown option or invalid value: {}", args.join(" ")),
+        }
+    }
+
+    /// Loads the network to evaluate with. An empty path or "<empty>" goes back to the
+    /// handcrafted evaluation.
+    fn set_eval_file(&mut self, path: &str) {
+        self.stop_search();
+        self.network = match path {
+            "" | "<empty>" => None,
+            path => match Network::load(path) {
+                Ok(network) => {
+                    println!("info string loaded network {path} with {} hidden neurons", network.hidden_size());
+                    Some(Arc::new(network))
+                }
+                Err(error) => {
+                    println!("info string can't load network {path}: {error}");
+                    return;
+                }
+            },
+        };
+        self.board.set_network(self.network.clone());
+    }
+
+    /// Loads the opening book. An empty path or "<empty>" removes it.
+    fn set_book_file(&mut self, path: &str) {
+        self.book = match path {
+            "" | "<empty>" => None,
+            path => match Book::load(path) {
+                Ok(book) => {
+                    println!("info string loaded book {path} with {} entries", book.len());
+                    Some(book)
+                }
+                Err(error) => {
+                    println!("info string can't load book {path}: {error}");
+                    None
+                }
+            },
+        };
+    }
+
+    /// The book move to play instead of searching, if the book is on and has one.
+    fn book_move(&self) -> Option<Move> {
+        if !self.own_book {
+            return None;
+        }
+        let random = RandomState::new().build_hasher().finish();
+        self.book.as_ref()?.probe(&self.board, self.book_selection, random)
+    }
+}
+
+/// Formats the "bestmove" line for a search result, with the expected reply as the move to ponder on.
+fn best_move_line(result: &SearchResult) -> String {
+    match (result.best_move, result.pv.get(1)) {
+        (Some(best), Some(ponder)) => format!("bestmove {best} ponder {ponder}"),
+        (Some(best), None) => format!("bestmove {best}"),
+        // UCI uses the null move when there is no legal move.
+        (None, _) => "bestmove 0000".to_string(),
+    }
+}
+
+/// Finds the legal move in the position matching a move in UCI notation.
+pub fn parse_uci_move(board: &Board, s: &str) -> Result<Move, String> {
+    let mv: Move = s.parse().map_err(|error| format!("invalid move {s}: {error}"))?;
+    generate_legal_moves(board).into_iter().find(|&legal| legal == mv).ok_or_else(|| format!("illegal move {s}"))
+}
+
+/// Parses the arguments of "position": "startpos" or "fen <fen>", optionally followed by
+/// "moves" and the moves played since. With `chess960` castling moves are written as the king
+/// capturing its rook even in standard positions; Chess960 positions always use that notation.
+/// Positions that fail `Board::validate` are rejected.
+pub fn parse_position(args: &[&str], chess960: bool) -> Result<Board, String> {
+    let moves_index = args.iter().position(|&arg| arg == "moves").unwrap_or(args.len());
+    let mut board = match args.first() {
+        Some(&"startpos") => Board::from_fen(START_FEN).map_err(|error| error.to_string())?,
+        Some(&"fen") => Board::from_fen(&args[1..moves_index].join(" ")).map_err(|error| error.to_string())?,
+        _ => return Err("position must be followed by startpos or fen".to_string()),
+    };
+    board.chess960 = chess960 || board.requires_chess960();
+    board.validate().map_err(|error| error.to_string())?;
+    for s in args.iter().skip(moves_index + 1) {
+        let mv = parse_uci_move(&board, s)?;
+        board.make_move(mv);
+    }
+    Ok(board)
+}
+
+/// Parses the arguments of "go" into search limits and whether the search is infinite and/or
+/// pondering. The moves after "searchmoves" are looked up in `board`; the list ends at the first
+/// argument that isn't a legal move.
+pub fn parse_go(args: &[&str], board: &Board) -> Result<(SearchLimits, bool, bool), String> {
+    let mut limits = SearchLimits::default();
+    let mut infinite = false;
+    let mut ponder = false;
+    let mut tokens = args.iter().peekable();
+    while let Some(&token) = tokens.next() {
+        let mut number = || -> Result<u64, String> {
+            let value = tokens.next().ok_or_else(|| format!("missing value for {token}"))?;
+            value.parse::<i64>().map(|value| value.max(0) as u64).map_err(|_| format!("invalid value for {token}: {value}"))
+        };
+        match token {
+            "wtime" => limits.wtime = Some(Duration::from_millis(number()?)),
+            "btime" => limits.btime = Some(Duration::from_millis(number()?)),
+            "winc" => limits.winc = Some(Duration::from_millis(number()?)),
+            "binc" => limits.binc = Some(Duration::from_millis(number()?)),
+            "movestogo" => limits.movestogo = Some(u32::try_from(number()?).unwrap_or(u32::MAX)),
+            "depth" => limits.depth = Some(i32::try_from(number()?).unwrap_or(i32::MAX)),
+            "nodes" => limits.nodes = Some(number()?),
+            // Longer mates are beyond the reach of the search anyway.
+            "mate" => limits.mate = Some(number()?.min(MAX_PLY as u64) as u32),
+            "movetime" => limits.movetime = Some(Duration::from_millis(number()?)),
+            "infinite" => infinite = true,
+            "ponder" => ponder = true,
+            "searchmoves" => {
+                while let Some(mv) = tokens.peek().and_then(|s| parse_uci_move(board, s).ok()) {
+                    limits.search_moves.push(mv);
+                    tokens.next();
+                }
+                if limits.search_moves.is_empty() {
+                    return Err("searchmoves must be followed by legal moves".to_string());
+                }
+            }
+            _ => return Err(format!("unknown go parameter: {token}")),
+        }
+    }
+    Ok((limits, infinite, ponder))
+}
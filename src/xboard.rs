@@ -0,0 +1,350 @@
+use std::{
+    io::{self, BufRead},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    board::{board::Board, moves::Move, piece::Color},
+    search::{smp::ThreadPool, SearchLimits, SearchResult, MATE, MATE_IN_MAX},
+    uci::parse_uci_move,
+};
+
+const ENGINE_NAME: &str = "KnightBot";
+const DEFAULT_HASH_MB: usize = 16;
+
+/// The stack size of the search thread, see `smp::HELPER_STACK_SIZE`.
+const SEARCH_STACK_SIZE: usize = 16 * 1024 * 1024;
+
+/// CECP reports mate scores as this value plus the number of moves to mate.
+const XBOARD_MATE_SCORE: i32 = 100000;
+
+/// The time control set by "level" or "st". XBoard's default is 40 moves in 5 minutes.
+#[derive(Clone, Copy, Debug)]
+struct TimeControl {
+    /// The number of moves per session, or 0 for the whole game.
+    moves_per_session: u32,
+    base: Duration,
+    increment: Duration,
+    /// A fixed time per move, set by "st".
+    move_time: Option<Duration>,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        TimeControl { moves_per_session: 40, base: Duration::from_secs(300), increment: Duration::ZERO, move_time: None }
+    }
+}
+
+/// A running search. The thread pool is moved into the search thread and handed back, together
+/// with the move the engine played, when the search is joined.
+struct ActiveSearch {
+    handle: JoinHandle<(ThreadPool, Option<Move>)>,
+    /// Set when the search result is no longer wanted, e.g. after "force" or "undo".
+    cancelled: Arc<AtomicBool>,
+}
+
+/// A chess engine speaking the Chess Engine Communication Protocol used by XBoard and WinBoard.
+/// It shares the board and search with the UCI front-end; the difference is that the engine
+/// keeps track of the game and decides itself when it is its turn to move.
+pub struct XBoardEngine {
+    board: Board,
+    /// None while a search is running.
+    pool: Option<ThreadPool>,
+    search: Option<ActiveSearch>,
+    stop: Arc<AtomicBool>,
+    /// The side the engine plays, or None in force mode.
+    engine_color: Option<Color>,
+    /// Whether to print thinking output.
+    post: bool,
+    time_control: TimeControl,
+    /// The clocks as last reported by "time" and "otim".
+    engine_time: Option<Duration>,
+    opponent_time: Option<Duration>,
+    /// The depth limit set by "sd".
+    depth: Option<i32>,
+}
+
+impl Default for XBoardEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl XBoardEngine {
+    pub fn new() -> Self {
+        let pool = ThreadPool::new(1, DEFAULT_HASH_MB);
+        XBoardEngine {
+            board: Board::new(),
+            stop: pool.stop.clone(),
+            pool: Some(pool),
+            search: None,
+            engine_color: Some(Color::Black),
+            post: false,
+            time_control: TimeControl::default(),
+            engine_time: None,
+            opponent_time: None,
+            depth: None,
+        }
+    }
+
+    /// Reads commands from stdin until "quit" or the end of the input.
+    pub fn run(&mut self) {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if !self.handle_command(&line) {
+                break;
+            }
+        }
+        self.cancel_search();
+    }
+
+    /// Handles one line of input. Returns false if the engine should quit.
+    pub fn handle_command(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        let Some(command) = tokens.next() else { return true };
+        let args: Vec<&str> = tokens.collect();
+        match command {
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer" | "name" | "draw" => {}
+            "protover" => println!(
+                "feature myname=\"{ENGINE_NAME} {}\" ping=1 setboard=1 usermove=1 time=1 colors=0 sigint=0 sigterm=0 reuse=1 analyze=0 done=1",
+                env!("CARGO_PKG_VERSION")
+            ),
+            "new" => {
+                self.cancel_search();
+                self.board = Board::new();
+                self.engine_color = Some(Color::Black);
+                self.depth = None;
+                self.engine_time = None;
+                self.opponent_time = None;
+                self.pool_mut().clear();
+            }
+            "force" | "result" => {
+                self.cancel_search();
+                self.engine_color = None;
+            }
+            "go" => {
+                self.cancel_search();
+                self.engine_color = Some(self.board.side_to_move);
+                self.think();
+            }
+            "playother" => {
+                self.cancel_search();
+                self.engine_color = Some(self.board.side_to_move.opposite());
+            }
+            "usermove" => match args.first() {
+                Some(mv) => self.user_move(mv),
+                None => println!("Error (missing move): usermove"),
+            },
+            "setboard" => {
+                self.cancel_search();
+                let board = Board::from_fen(&args.join(" ")).map_err(|error| error.to_string());
+                match board.and_then(|board| board.validate().map(|()| board).map_err(|error| error.to_string())) {
+                    Ok(board) => self.board = board,
+                    Err(error) => println!("tellusererror Illegal position: {error}"),
+                }
+            }
+            "level" => match parse_level(&args) {
+                Some(time_control) => self.time_control = time_control,
+                None => println!("Error (invalid time control): {line}"),
+            },
+            "st" => match args.first().and_then(|seconds| seconds.parse::<f64>().ok()) {
+                Some(seconds) if seconds > 0.0 => match Duration::try_from_secs_f64(seconds) {
+                    Ok(move_time) => self.time_control.move_time = Some(move_time),
+                    Err(_) => println!("Error (invalid move time): {line}"),
+                },
+                _ => println!("Error (invalid move time): {line}"),
+            },
+            "sd" => match args.first().and_then(|depth| depth.parse::<i32>().ok()) {
+                Some(depth) if depth > 0 => self.depth = Some(depth),
+                _ => println!("Error (invalid depth): {line}"),
+            },
+            "time" | "otim" => match args.first().and_then(|centiseconds| centiseconds.parse::<i64>().ok()) {
+                Some(centiseconds) => {
+                    let time = Some(Duration::from_millis((centiseconds.max(0) as u64).saturating_mul(10)));
+                    if command == "time" {
+                        self.engine_time = time;
+                    } else {
+                        self.opponent_time = time;
+                    }
+                }
+                None => println!("Error (invalid time): {line}"),
+            },
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "undo" | "remove" => {
+                self.cancel_search();
+                let plies = if command == "undo" { 1 } else { 2 };
+                if self.board.history.len() < plies {
+                    println!("Error (no moves to undo): {command}");
+                } else {
+                    for _ in 0..plies {
+                        self.board.unmake_move();
+                    }
+                }
+            }
+            "?" => self.stop_search(),
+            "ping" => {
+                // A pong must follow any move the engine makes in response to earlier commands.
+                self.finish_search();
+                println!("pong {}", args.first().unwrap_or(&""));
+            }
+            "quit" => return false,
+            // Without "usermove=1", moves are sent on their own.
+            _ if command.parse::<Move>().is_ok() => self.user_move(command),
+            _ => println!("Error (unknown command): {command}"),
+        }
+        true
+    }
+
+    /// Plays the opponent's move and starts thinking if it is now the engine's turn.
+    fn user_move(&mut self, s: &str) {
+        self.cancel_search();
+        match parse_uci_move(&self.board, s) {
+            Ok(mv) => self.board.make_move(mv),
+            Err(_) => {
+                println!("Illegal move: {s}");
+                return;
+            }
+        }
+        if let Some(outcome) = self.board.outcome() {
+            println!("{} {{{}}}", outcome.result(), outcome.reason());
+        } else if self.engine_color == Some(self.board.side_to_move) {
+            self.think();
+        }
+    }
+
+    /// The search limits for the engine's next move under the current time control.
+    fn limits(&self) -> SearchLimits {
+        let mut limits = SearchLimits { depth: self.depth, ..SearchLimits::default() };
+        if let Some(move_time) = self.time_control.move_time {
+            limits.movetime = Some(move_time);
+            return limits;
+        }
+        let engine_time = self.engine_time.unwrap_or(self.time_control.base);
+        let opponent_time = self.opponent_time.unwrap_or(self.time_control.base);
+        let (wtime, btime) = match self.board.side_to_move {
+            Color::White => (engine_time, opponent_time),
+            Color::Black => (opponent_time, engine_time),
+        };
+        let moves_per_session = self.time_control.moves_per_session;
+        SearchLimits {
+            wtime: Some(wtime),
+            btime: Some(btime),
+            winc: Some(self.time_control.increment),
+            binc: Some(self.time_control.increment),
+            movestogo: (moves_per_session > 0)
+                .then(|| moves_per_session - (self.board.fullmove_number.saturating_sub(1) as u32 % moves_per_session)),
+            ..limits
+        }
+    }
+
+    /// Starts searching for the engine's move on a separate thread. The move is printed and played
+    /// on the board when the search finishes, unless it is cancelled first.
+    fn think(&mut self) {
+        if let Some(outcome) = self.board.outcome() {
+            println!("{} {{{}}}", outcome.result(), outcome.reason());
+            return;
+        }
+        let limits = self.limits();
+        let mut pool = self.take_pool();
+        let mut board = self.board.clone();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.stop.store(false, Ordering::Relaxed);
+
+        pool.main_searcher().on_iteration = self
+            .post
+            .then(|| Box::new(|result: &SearchResult| println!("{}", thinking_line(result))) as Box<_>);
+        let thread_cancelled = cancelled.clone();
+        let handle = thread::Builder::new()
+            .stack_size(SEARCH_STACK_SIZE)
+            .spawn(move || {
+                let result = pool.run(&board, limits);
+                if thread_cancelled.load(Ordering::Relaxed) {
+                    return (pool, None);
+                }
+                if let Some(best_move) = result.best_move {
+                    println!("move {best_move}");
+                    board.make_move(best_move);
+                    if let Some(outcome) = board.outcome() {
+                        println!("{} {{{}}}", outcome.result(), outcome.reason());
+                    }
+                }
+                (pool, result.best_move)
+            })
+            .expect("failed to spawn search thread");
+        self.search = Some(ActiveSearch { handle, cancelled });
+    }
+
+    /// Waits for the running search to finish, plays the move it printed and takes back the
+    /// thread pool.
+    fn finish_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            let (pool, best_move) = search.handle.join().expect("search thread panicked");
+            self.pool = Some(pool);
+            if let Some(best_move) = best_move {
+                self.board.make_move(best_move);
+            }
+        }
+    }
+
+    /// Makes the engine move now with the best move found so far.
+    fn stop_search(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.finish_search();
+    }
+
+    /// Stops the running search without making a move, unless the move was already printed.
+    fn cancel_search(&mut self) {
+        if let Some(search) = &self.search {
+            search.cancelled.store(true, Ordering::Relaxed);
+        }
+        self.stop_search();
+    }
+
+    /// The thread pool, after cancelling a running search.
+    fn pool_mut(&mut self) -> &mut ThreadPool {
+        self.cancel_search();
+        self.pool.as_mut().expect("the pool is returned when the search is joined")
+    }
+
+    /// Takes the thread pool out of the engine for a new search.
+    fn take_pool(&mut self) -> ThreadPool {
+        self.cancel_search();
+        self.pool.take().expect("the pool is returned when the search is joined")
+    }
+}
+
+/// Formats a search result as CECP thinking output: depth, score, time in centiseconds, nodes and
+/// the principal variation.
+fn thinking_line(result: &SearchResult) -> String {
+    let score = if result.score >= MATE_IN_MAX {
+        XBOARD_MATE_SCORE + (MATE - result.score + 1) / 2
+    } else if result.score <= -MATE_IN_MAX {
+        -XBOARD_MATE_SCORE - (MATE + result.score) / 2
+    } else {
+        result.score
+    };
+    let pv: Vec<String> = result.pv.iter().map(Move::to_string).collect();
+    format!("{} {} {} {} {}", result.depth, score, result.time.as_millis() / 10, result.nodes, pv.join(" "))
+}
+
+/// Parses the arguments of "level": the moves per session, the base time in minutes, optionally
+/// with seconds as in "5:30", and the increment in seconds.
+fn parse_level(args: &[&str]) -> Option<TimeControl> {
+    let [moves, base, increment] = args else { return None };
+    let base_seconds = match base.split_once(':') {
+        Some((minutes, seconds)) => minutes.parse::<u64>().ok()?.checked_mul(60)?.checked_add(seconds.parse::<u64>().ok()?)?,
+        None => base.parse::<u64>().ok()?.checked_mul(60)?,
+    };
+    Some(TimeControl {
+        moves_per_session: moves.parse().ok()?,
+        base: Duration::from_secs(base_seconds),
+        increment: Duration::try_from_secs_f64(increment.parse::<f64>().ok()?.max(0.0)).ok()?,
+        move_time: None,
+    })
+}
@@ -0,0 +1,119 @@
+use knightbot::board::{board::Board, moves::Move};
+use knightbot::book::builder::{BookBuilder, BookBuilderConfig};
+use knightbot::book::{decode_move, encode_move, polyglot_key, Book, BookEntry, BookError, BookSelection, ENTRY_SIZE};
+use knightbot::pgn::PgnReader;
+
+fn mv(s: &str) -> Move {
+    s.parse().unwrap()
+}
+
+#[test]
+fn polyglot_keys_match_the_specification() {
+    // The examples from the Polyglot book format description.
+    let keys = [
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 0x463b96181691fc9c),
+        ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", 0x823c9b50fd114196),
+        ("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", 0x0756b94461c50fb0),
+        ("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2", 0x662fafb965db29d4),
+        ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", 0x22a48b5a8e47ff78),
+        ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3", 0x652a607ca3f242c1),
+        ("rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4", 0x00fdd303c946bdd9),
+        ("rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3", 0x3c8123ea7b067637),
+        ("rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4", 0x5c3f9b829b279560),
+    ];
+    for (fen, key) in keys {
+        assert_eq!(polyglot_key(&Board::from_fen(fen).unwrap()), key, "{fen}");
+    }
+
+    // The same positions reached by playing the moves.
+    let mut board = Board::new();
+    for (mv_str, (_, key)) in ["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"].iter().zip(&keys[1..]) {
+        board.make_move(mv(mv_str));
+        assert_eq!(polyglot_key(&board), *key, "after {mv_str}");
+    }
+}
+
+#[test]
+fn moves_are_encoded_as_in_polyglot() {
+    let board = Board::new();
+    assert_eq!(encode_move(&board, mv("e2e4")), 4 | 3 << 3 | 4 << 6 | 1 << 9);
+    assert_eq!(decode_move(&board, 4 | 3 << 3 | 4 << 6 | 1 << 9), Some(mv("e2e4")));
+    assert_eq!(decode_move(&board, 4 | 4 << 3 | 4 << 6 | 1 << 9), None);
+
+    // Castling is the king taking its own rook.
+    let castling = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    let e1h1 = 7 | 4 << 6;
+    assert_eq!(encode_move(&castling, mv("e1g1")), e1h1);
+    assert_eq!(decode_move(&castling, e1h1), Some(mv("e1g1")));
+    assert_eq!(decode_move(&castling, 4 << 6), Some(mv("e1c1")));
+
+    let promotion = Board::from_fen("8/P6k/8/8/8/8/8/K7 w - - 0 1").unwrap();
+    assert_eq!(encode_move(&promotion, mv("a7a8n")), 7 << 3 | 6 << 9 | 1 << 12);
+    assert_eq!(decode_move(&promotion, 7 << 3 | 6 << 9 | 4 << 12), Some(mv("a7a8q")));
+}
+
+#[test]
+fn books_are_read_and_probed() {
+    let board = Board::new();
+    let key = polyglot_key(&board);
+    let entry = |mv_str: &str, weight| BookEntry { key, mv: encode_move(&board, mv(mv_str)), weight, learn: 0 };
+    let other = BookEntry { key: key ^ 1, mv: 0, weight: 100, learn: 7 };
+    let book = Book::from_entries(vec![other, entry("d2d4", 10), entry("e2e4", 30)]);
+
+    let bytes = book.to_bytes();
+    assert_eq!(bytes.len(), 3 * ENTRY_SIZE);
+    let book = Book::from_bytes(&bytes).unwrap();
+    assert_eq!(book.len(), 3);
+    assert_eq!(book.moves(&board), [(mv("e2e4"), 30), (mv("d2d4"), 10)]);
+    assert_eq!(book.entries(key ^ 1), [other]);
+    assert!(book.entries(key ^ 2).is_empty());
+
+    assert_eq!(book.probe(&board, BookSelection::Best, 0), Some(mv("e2e4")));
+    // With weights 30 and 10, random numbers 0-29 pick e2e4 and 30-39 pick d2d4.
+    assert_eq!(book.probe(&board, BookSelection::Weighted, 29), Some(mv("e2e4")));
+    assert_eq!(book.probe(&board, BookSelection::Weighted, 30), Some(mv("d2d4")));
+    assert_eq!(book.probe(&board, BookSelection::Weighted, 40), Some(mv("e2e4")));
+    assert_eq!(book.probe(&Board::from_fen("8/8/8/8/8/8/8/K6k w - - 0 1").unwrap(), BookSelection::Best, 0), None);
+
+    assert!(matches!(Book::from_bytes(&bytes[1..]), Err(BookError::InvalidSize(47))));
+}
+
+#[test]
+fn books_are_built_from_pgn() {
+    let pgn = "\
+[Result \"1-0\"]
+
+1. e4 e5 2. Nf3 Nc6 1-0
+
+[Result \"1/2-1/2\"]
+
+1. e4 c5 2. Nf3 1/2-1/2
+
+[Result \"0-1\"]
+
+1. e4 e5 2. Nf3 Nf6 0-1
+
+[Result \"*\"]
+
+1. d4 d5 *
+";
+    let mut reader = PgnReader::new(pgn.as_bytes());
+    let mut builder = BookBuilder::new(BookBuilderConfig { min_games: 2, max_ply: 3 });
+    while let Some(game) = reader.read_game().unwrap() {
+        builder.add_game(&game);
+    }
+    let book = builder.build();
+
+    // 1. e4 was played in three finished games, winning one, drawing one and losing one.
+    assert_eq!(book.moves(&Board::new()), [(mv("e2e4"), 3)]);
+    let mut board = Board::new();
+    board.make_move(mv("e2e4"));
+    // 1... e5 scored a win and a loss for black; 1... c5 was played only once.
+    assert_eq!(book.moves(&board), [(mv("e7e5"), 2)]);
+    board.make_move(mv("e7e5"));
+    assert_eq!(book.moves(&board), [(mv("g1f3"), 2)]);
+    // Nc6 and Nf6 are past the maximum ply.
+    board.make_move(mv("g1f3"));
+    assert!(book.moves(&board).is_empty());
+    assert_eq!(book.len(), 3);
+}
@@ -0,0 +1,61 @@
+use std::io::BufReader;
+
+use knightbot::board::board::Board;
+use knightbot::datagen::{generate, read_packed_positions, DataFormat, DatagenConfig, PackedPosition, PACKED_SIZE};
+use knightbot::tune::{parse_labelled_position, read_positions};
+
+/// A configuration that plays short games, so the tests run quickly in debug builds.
+fn quick_config(format: DataFormat) -> DatagenConfig {
+    DatagenConfig { games: 3, threads: 2, nodes: 100, random_plies: 8, max_plies: 20, hash_mb: 1, seed: 7, format }
+}
+
+#[test]
+fn packed_positions_round_trip() {
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w Kq - 3 17",
+        "rnbqkb1r/pp1p1ppp/5n2/2pPp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 4",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 99 301",
+    ] {
+        let board = Board::from_fen(fen).unwrap();
+        let mut packed = PackedPosition::new(&board, -37, 0.5);
+        assert_eq!(packed.board().to_fen(), fen);
+        assert_eq!(packed.board().hash, board.hash, "{fen}");
+        assert_eq!((packed.score(), packed.result()), (-37, 0.5));
+        packed.set_result(1.0);
+        assert_eq!(packed.result(), 1.0);
+    }
+}
+
+#[test]
+fn binary_datagen_writes_quiet_positions() {
+    let mut output = Vec::new();
+    let mut updates = 0;
+    let summary = generate(&quick_config(DataFormat::Binary), &mut output, |_| updates += 1).unwrap();
+    assert_eq!((summary.games, updates), (3, 3));
+    assert_eq!(output.len(), summary.positions * PACKED_SIZE);
+
+    let positions = read_packed_positions(output.as_slice()).unwrap();
+    assert_eq!(positions.len(), summary.positions);
+    assert!(!positions.is_empty());
+    for position in positions {
+        assert!(!position.board().in_check());
+        assert!([0.0, 0.5, 1.0].contains(&position.result()));
+    }
+    assert!(read_packed_positions(&[0u8; PACKED_SIZE + 1][..]).is_err());
+}
+
+#[test]
+fn text_datagen_can_be_read_by_the_tuner() {
+    let mut output = Vec::new();
+    let summary = generate(&quick_config(DataFormat::Text), &mut output, |_| {}).unwrap();
+    let text = String::from_utf8(output).unwrap();
+    assert_eq!(text.lines().count(), summary.positions);
+    for line in text.lines() {
+        let (board, result) = parse_labelled_position(line).unwrap();
+        assert!(line.starts_with(&board.to_fen()));
+        assert!(line.ends_with(&format!(" | {result:.1}")));
+    }
+    let positions = read_positions(BufReader::new(text.as_bytes())).unwrap();
+    assert_eq!(positions.len(), summary.positions);
+}
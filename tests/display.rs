@@ -0,0 +1,39 @@
+use knightbot::board::{board::Board, display::Diagram, movegen::knight_attacks, square::Square};
+
+#[test]
+fn board_is_displayed_as_a_diagram() {
+    let board = Board::from_fen("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 2").unwrap();
+    let expected = "  +------------------------+
+8 | r  .  .  .  k  .  .  r |
+7 | .  .  .  .  .  .  .  . |
+6 | .  .  .  .  .  .  .  . |
+5 | .  .  .  p  P  .  .  . |
+4 | .  .  .  .  .  .  .  . |
+3 | .  .  .  .  .  .  .  . |
+2 | .  .  .  .  .  .  .  . |
+1 | R  .  .  .  K  .  .  R |
+  +------------------------+
+    a  b  c  d  e  f  g  h
+Side to move: white
+Castling: KQkq
+En passant: d6
+FEN: r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 2";
+    assert_eq!(board.to_string(), expected);
+}
+
+#[test]
+fn unicode_diagram_uses_chess_symbols() {
+    let diagram = format!("{:#}", Board::new());
+    assert!(diagram.contains("8 | ♜  ♞  ♝  ♛  ♚  ♝  ♞  ♜ |"));
+    assert!(diagram.contains("2 | ♙  ♙  ♙  ♙  ♙  ♙  ♙  ♙ |"));
+    assert!(diagram.contains("Side to move: white"));
+}
+
+#[test]
+fn highlighted_squares_are_bracketed() {
+    let board = Board::new();
+    let diagram = Diagram { highlight: knight_attacks(Square::G1), ..board.diagram() }.to_string();
+    assert!(diagram.contains("3 | .  .  .  .  . [.] . [.]|"));
+    assert!(diagram.contains("2 | P  P  P  P [P] P  P  P |"));
+    assert!(diagram.contains("1 | R  N  B  Q  K  B  N  R |"));
+}
@@ -0,0 +1,78 @@
+use knightbot::epd::{read_epd, run_suite, EpdEntry, EpdErrorKind};
+use knightbot::search::{smp::ThreadPool, SearchLimits};
+
+const SUITE: &str = r#"# Easy tactics
+6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id "mate.001"; c0 "back rank; Ra8 mates";
+
+4k3/8/2p5/1p6/8/3Q4/8/4K3 w - - am Qxb5; id "blunder.001";
+4k3/8/8/8/8/8/8/4K3 w - - id "no.moves"; hmvc 12; fmvn 40;
+"#;
+
+#[test]
+fn parse_operations() {
+    let entry = EpdEntry::parse(r#"r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - bm Bb5 Bc4; id "opening; \"test\"";"#);
+    let entry = entry.unwrap();
+    assert_eq!(entry.best_moves.len(), 2);
+    assert!(entry.avoid_moves.is_empty());
+    assert_eq!(entry.operation("bm").unwrap(), ["Bb5", "Bc4"]);
+    assert_eq!(entry.id(), Some(r#"opening; "test""#));
+    assert_eq!(entry.comment(), None);
+    let entry = EpdEntry::parse(r#"4k3/8/8/8/8/8/8/4K3 w - - c0 "a\\b \\";"#).unwrap();
+    assert_eq!(entry.comment(), Some(r"a\b \"));
+
+    let entry = EpdEntry::parse("4k3/8/8/8/8/8/8/4K3 w - - hmvc 12; fmvn 40; bm e1d2;").unwrap();
+    assert_eq!(entry.board.halfmove_clock, 12);
+    assert_eq!(entry.board.fullmove_number, 40);
+    assert_eq!(entry.best_moves[0].to_string(), "e1d2");
+}
+
+#[test]
+fn parse_errors() {
+    let parse = |line: &str| EpdEntry::parse(line).unwrap_err();
+    assert!(matches!(parse("4k3/8/8/8/8/8/8/4K3 x - - id \"x\";"), EpdErrorKind::InvalidFen(_)));
+    assert!(matches!(parse("4k3/8/8/8/8/8/8/4K3 w - - id \"x\""), EpdErrorKind::InvalidOperation(_)));
+    assert!(matches!(parse("4k3/8/8/8/8/8/8/4K3 w - - id \"x;"), EpdErrorKind::InvalidOperation(_)));
+    assert!(matches!(parse("4k3/8/8/8/8/8/8/4K3 w - - bm Qd1;"), EpdErrorKind::InvalidMove(_)));
+    assert!(matches!(parse("8/8/8/8/8/8/4P3/4K3 w - - bm e3;"), EpdErrorKind::InvalidPosition(_)));
+
+    let error = read_epd("4k3/8/8/8/8/8/8/4K3 w - - id \"a\";\n\n4k3/8/8/8/8/8/8/4K3 w - - bm Kd8;\n".as_bytes()).unwrap_err();
+    assert_eq!(error.line, 3);
+    assert_eq!(error.to_string(), "line 3: illegal move: Kd8");
+}
+
+#[test]
+fn solved_by() {
+    let entries = read_epd(SUITE.as_bytes()).unwrap();
+    assert_eq!(entries.len(), 3);
+    let mate = &entries[0];
+    assert_eq!(mate.comment(), Some("back rank; Ra8 mates"));
+    assert!(mate.is_solved_by(mate.board.parse_san("Ra8").unwrap()));
+    assert!(!mate.is_solved_by(mate.board.parse_san("Ra7").unwrap()));
+
+    let avoid = &entries[1];
+    assert!(!avoid.is_solved_by(avoid.board.parse_san("Qxb5").unwrap()));
+    assert!(avoid.is_solved_by(avoid.board.parse_san("Qe4+").unwrap()));
+
+    let nothing = &entries[2];
+    assert!(!nothing.is_solved_by(nothing.board.parse_san("Kd2").unwrap()));
+}
+
+#[test]
+fn run_suite_at_fixed_depth() {
+    let entries = read_epd(SUITE.as_bytes()).unwrap();
+    let mut pool = ThreadPool::new(1, 1);
+    let mut names = Vec::new();
+    let limits = SearchLimits { depth: Some(4), ..SearchLimits::default() };
+    let report = run_suite(&mut pool, &entries, limits, |result| names.push(result.name.clone()));
+
+    assert_eq!(names, ["mate.001", "blunder.001"]);
+    assert_eq!((report.solved(), report.failed(), report.skipped), (2, 0, 1));
+    assert_eq!(report.results[0].played, "Ra8#");
+    assert_eq!(report.results[0].expected, "bm Ra8#");
+    assert_eq!(report.results[1].expected, "am Qxb5");
+
+    let text = report.to_string();
+    assert_eq!(text.lines().count(), 3);
+    assert!(text.lines().next().unwrap().starts_with("mate.001     solved  played Ra8# "));
+    assert!(text.lines().last().unwrap().starts_with("Solved 2/2 (0 failed, 1 skipped) in "));
+}
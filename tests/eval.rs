@@ -0,0 +1,266 @@
+use std::process::Command;
+
+use knightbot::board::{
+    board::Board,
+    movegen::generate_legal_moves,
+    piece::{Color, Piece},
+};
+use knightbot::board::{bitboard::BitBoard, square::Square};
+use knightbot::eval::king_safety::{evaluate_king_safety, king_zone};
+use knightbot::eval::mobility::evaluate_mobility;
+use knightbot::eval::params::{
+    BACKWARD_PAWN, CANDIDATE_PAWN, CONNECTED_PAWN, DOUBLED_PAWN, ISOLATED_PAWN, KING_OPEN_FILE, KING_SEMI_OPEN_FILE, KING_ZONE_ATTACK,
+    KNIGHT_MOBILITY, PASSED_PAWN, PAWN_SHIELD, ROOK_MOBILITY,
+};
+use knightbot::eval::pawns::{evaluate_pawns, passed_pawn_mask, PawnTable};
+use knightbot::eval::trace::eval_trace;
+use knightbot::eval::{evaluate, Score, MAX_PHASE};
+
+const POSITIONS: [&str; 6] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "rnbqkb1r/pp1p1ppp/5n2/2pPp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 4",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 0 1",
+    "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w KQ - 2 8",
+    "6k1/8/8/8/8/8/4P3/4K3 w - - 0 1",
+];
+
+/// A small xorshift generator, so the move sequences are random but reproducible.
+fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+#[test]
+fn mirrored_positions_evaluate_equally() {
+    for fen in POSITIONS {
+        let board = Board::from_fen(fen).unwrap();
+        let mirrored = board.mirrored();
+        assert_eq!(mirrored.mirrored().to_fen(), board.to_fen());
+        assert_eq!(mirrored.psqt, -board.psqt, "{fen}");
+        assert_eq!(mirrored.phase, board.phase, "{fen}");
+        let mut pawn_table = PawnTable::new();
+        assert_eq!(evaluate_pawns(&mirrored).score, -evaluate_pawns(&board).score, "{fen}");
+        assert_eq!(evaluate(&mirrored, &mut pawn_table), evaluate(&board, &mut pawn_table), "{fen}");
+    }
+}
+
+#[test]
+fn incremental_terms_match_a_full_refresh() {
+    let mut state = 0x2545F4914F6CDD1D;
+    for fen in POSITIONS {
+        let mut board = Board::from_fen(fen).unwrap();
+        let start = (board.psqt, board.phase);
+        for _ in 0..60 {
+            let moves = generate_legal_moves(&board);
+            if moves.is_empty() {
+                break;
+            }
+            board.make_move(moves[next_random(&mut state) as usize % moves.len()]);
+            let mut refreshed = board.clone();
+            refreshed.update();
+            assert_eq!((board.psqt, board.phase), (refreshed.psqt, refreshed.phase), "{}", board.to_fen());
+            assert_eq!(board.pawn_hash, refreshed.pawn_hash, "{}", board.to_fen());
+        }
+        while !board.history.is_empty() {
+            board.unmake_move();
+        }
+        assert_eq!((board.psqt, board.phase), start, "{fen}");
+        assert_eq!(board.pawn_hash, board.compute_pawn_hash(), "{fen}");
+    }
+}
+
+#[test]
+fn evaluation_is_from_the_side_to_move() {
+    let mut pawn_table = PawnTable::new();
+    let board = Board::new();
+    assert_eq!(board.phase, MAX_PHASE);
+    assert_eq!(evaluate(&board, &mut pawn_table), 0);
+
+    // White is a queen up.
+    let white = Board::from_fen("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    let black = Board::from_fen("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    assert!(evaluate(&white, &mut pawn_table) > 800);
+    assert_eq!(evaluate(&black, &mut pawn_table), -evaluate(&white, &mut pawn_table));
+}
+
+#[test]
+fn tapering_blends_by_phase() {
+    let score = Score::new(100, -50);
+    assert_eq!(score.taper(MAX_PHASE), 100);
+    assert_eq!(score.taper(0), -50);
+    assert_eq!(score.taper(MAX_PHASE / 2), 25);
+    // Extra material from promotions doesn't push the phase past the middlegame.
+    assert_eq!(score.taper(MAX_PHASE + 8), 100);
+
+    // A king and pawn ending is scored with the endgame values only.
+    let board = Board::from_fen(POSITIONS[5]).unwrap();
+    assert_eq!(board.phase, 0);
+    let kings = evaluate_king_safety(&board, Color::White) - evaluate_king_safety(&board, Color::Black);
+    let total = board.psqt + evaluate_pawns(&board).score + kings;
+    assert_eq!(evaluate(&board, &mut PawnTable::new()), total.eg);
+}
+
+#[test]
+fn passed_pawn_masks_cover_the_path_to_promotion() {
+    let white = passed_pawn_mask(Square::E4, Color::White);
+    assert_eq!(white.count(), 12);
+    assert!(white.contains(Square::D5) && white.contains(Square::E8) && white.contains(Square::F5));
+    assert!(!white.contains(Square::E4) && !white.contains(Square::D4) && !white.contains(Square::G5));
+
+    let black = passed_pawn_mask(Square::A2, Color::Black);
+    assert_eq!(black, BitBoard::from(Square::A1) | BitBoard::from(Square::B1));
+    assert!(passed_pawn_mask(Square::H8, Color::White).is_empty());
+}
+
+#[test]
+fn pawn_structure_terms() {
+    let pawns = |fen: &str| evaluate_pawns(&Board::from_fen(fen).unwrap());
+
+    // A lone pawn is passed and isolated.
+    let entry = pawns("4k3/8/8/8/8/8/P7/4K3 w - - 0 1");
+    assert_eq!(entry.passed, [BitBoard::from(Square::A2), BitBoard::EMPTY]);
+    assert_eq!(entry.score, PASSED_PAWN[1] + ISOLATED_PAWN);
+
+    // Doubled and isolated: only the front pawn is passed.
+    let entry = pawns("4k3/8/8/8/8/P7/P7/4K3 w - - 0 1");
+    assert_eq!(entry.passed[0], BitBoard::from(Square::A3));
+    assert_eq!(entry.score, PASSED_PAWN[2] + ISOLATED_PAWN * 2 + DOUBLED_PAWN);
+
+    // A phalanx of passed pawns is connected.
+    let entry = pawns("4k3/8/8/3PP3/8/8/8/4K3 w - - 0 1");
+    assert_eq!(entry.score, (PASSED_PAWN[4] + CONNECTED_PAWN[4]) * 2);
+
+    // The c2 pawn defends b3 and d3, but they have advanced too far to defend it, and black's b4
+    // pawn controls its stop square.
+    let entry = pawns("4k3/8/8/8/1p6/1P1P4/2P5/4K3 w - - 0 1");
+    let c2 = BACKWARD_PAWN;
+    let b3 = CONNECTED_PAWN[2];
+    let d3 = PASSED_PAWN[2] + CONNECTED_PAWN[2];
+    let black_b4 = ISOLATED_PAWN;
+    assert_eq!(entry.passed, [BitBoard::from(Square::D3), BitBoard::EMPTY]);
+    assert_eq!(entry.score, c2 + b3 + d3 - black_b4);
+
+    // The c4 pawn has as many supporters on b3 and d4 as sentries on b5 and d6. Both black pawns
+    // are isolated, and b3 is neither connected nor backward.
+    let entry = pawns("4k3/8/3p4/1p6/2PP4/1P6/8/4K3 w - - 0 1");
+    let c4 = CANDIDATE_PAWN[3] + CONNECTED_PAWN[3];
+    let d4 = CONNECTED_PAWN[3];
+    assert_eq!(entry.passed, [BitBoard::EMPTY, BitBoard::EMPTY]);
+    assert_eq!(entry.score, c4 + d4 - ISOLATED_PAWN * 2);
+}
+
+#[test]
+fn pawn_table_returns_the_same_entries_as_a_fresh_evaluation() {
+    let mut state = 0x9E3779B97F4A7C15;
+    let mut pawn_table = PawnTable::new();
+    for fen in POSITIONS {
+        let mut board = Board::from_fen(fen).unwrap();
+        for _ in 0..40 {
+            let moves = generate_legal_moves(&board);
+            if moves.is_empty() {
+                break;
+            }
+            board.make_move(moves[next_random(&mut state) as usize % moves.len()]);
+            assert_eq!(pawn_table.probe(&board), evaluate_pawns(&board), "{}", board.to_fen());
+        }
+        while !board.history.is_empty() {
+            board.unmake_move();
+            assert_eq!(pawn_table.probe(&board), evaluate_pawns(&board), "{}", board.to_fen());
+        }
+    }
+}
+
+#[test]
+fn mobility_ignores_squares_controlled_by_enemy_pawns() {
+    let mobility = |fen: &str, color| evaluate_mobility(&Board::from_fen(fen).unwrap(), color);
+
+    assert_eq!(mobility("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1", Color::White), KNIGHT_MOBILITY[8]);
+    // The e6 pawn can be captured, but f5 and d5 are covered by it, and only f5 is a knight move.
+    assert_eq!(mobility("4k3/8/4p3/8/3N4/8/8/4K3 w - - 0 1", Color::White), KNIGHT_MOBILITY[7]);
+    // A cornered knight next to its own pawn.
+    assert_eq!(mobility("4k3/8/8/8/8/8/2P5/N3K3 w - - 0 1", Color::White), KNIGHT_MOBILITY[1]);
+    // The a1 rook is boxed in by its own king and pawn, apart from b1, c1 and d1.
+    assert_eq!(mobility("4k3/8/8/8/8/8/P7/R3K3 w - - 0 1", Color::White), ROOK_MOBILITY[3]);
+    assert_eq!(mobility("4k3/8/8/8/8/8/P7/R3K3 w - - 0 1", Color::Black), Score::ZERO);
+}
+
+#[test]
+fn king_zone_extends_towards_the_enemy() {
+    let white = king_zone(Square::G1, Color::White);
+    assert_eq!(white.count(), 9);
+    assert!(white.contains(Square::F1) && white.contains(Square::H3) && !white.contains(Square::G4));
+
+    let black = king_zone(Square::E5, Color::Black);
+    assert_eq!(black.count(), 12);
+    assert!(black.contains(Square::D3) && black.contains(Square::F6) && !black.contains(Square::E7));
+}
+
+#[test]
+fn king_safety_terms() {
+    let safety = |fen: &str| evaluate_king_safety(&Board::from_fen(fen).unwrap(), Color::White);
+
+    // A full pawn shield on the second rank.
+    assert_eq!(safety("6k1/8/8/8/8/8/5PPP/6K1 w - - 0 1"), PAWN_SHIELD[0] * 3);
+    // g3 is two ranks in front; the h-file is open and black's f-pawn makes that file semi-open.
+    assert_eq!(safety("6k1/5p2/8/8/8/6P1/8/6K1 w - - 0 1"), PAWN_SHIELD[1] + KING_SEMI_OPEN_FILE + KING_OPEN_FILE);
+
+    // One attacker is ignored, two count with every attacked zone square.
+    let shield = PAWN_SHIELD[0] * 3;
+    assert_eq!(safety("6k1/8/8/8/8/8/5PPP/q5K1 w - - 0 1"), shield);
+    let board = Board::from_fen("6k1/8/8/8/8/7n/5PPP/q5K1 w - - 0 1").unwrap();
+    let queen = KING_ZONE_ATTACK[Piece::Queen.index()];
+    let knight = KING_ZONE_ATTACK[Piece::Knight.index()];
+    // The queen attacks f1 (and g1, the king); the knight attacks f2, g1 and f4 of which f2 and g1
+    // are in the zone.
+    assert_eq!(evaluate_king_safety(&board, Color::White), shield + queen * 2 + knight * 2);
+}
+
+#[test]
+fn trace_terms_add_up_to_the_evaluation() {
+    let mut state = 0xD1B54A32D192ED03;
+    let mut pawn_table = PawnTable::new();
+    for fen in POSITIONS {
+        let mut board = Board::from_fen(fen).unwrap();
+        for _ in 0..30 {
+            let trace = eval_trace(&board);
+            let side = if board.side_to_move == Color::White { 1 } else { -1 };
+            assert_eq!(trace.score() * side, evaluate(&board, &mut pawn_table), "{}", board.to_fen());
+            assert_eq!(trace.material[0] + trace.psqt[0] - trace.material[1] - trace.psqt[1], board.psqt);
+
+            let mirrored = eval_trace(&board.mirrored());
+            for ((name, scores), (_, mirrored_scores)) in trace.terms().iter().zip(mirrored.terms()) {
+                assert_eq!(*scores, [mirrored_scores[1], mirrored_scores[0]], "{name} in {}", board.to_fen());
+            }
+
+            let moves = generate_legal_moves(&board);
+            if moves.is_empty() {
+                break;
+            }
+            board.make_move(moves[next_random(&mut state) as usize % moves.len()]);
+        }
+    }
+}
+
+#[test]
+fn trace_is_printed_as_a_table() {
+    let table = eval_trace(&Board::new()).to_string();
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 9);
+    assert!(lines[0].starts_with("Term") && lines[0].ends_with("Total"));
+    for (line, name) in lines[2..8].iter().zip(["Material", "Piece-square", "Pawns", "Mobility", "King safety", "Total"]) {
+        assert!(line.starts_with(name), "{line}");
+        assert!(line.ends_with("     0      0"), "{line}");
+    }
+    assert_eq!(lines[8], "Phase 24/24, evaluation +0 (white's point of view)");
+}
+
+#[test]
+fn eval_command_rejects_impossible_positions() {
+    let output = Command::new(env!("CARGO_BIN_EXE_knightbot")).args(["eval", "8/8/8/8/8/8/8/8", "w", "-", "-", "0", "1"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("invalid position: white has 0 kings\nusage: "), "{stderr}");
+}
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use knightbot::board::{board::Board, movegen::generate_legal_moves};
+use knightbot::eval::nnue::{scalar, Network, NetworkError, INPUTS, MAGIC, VERSION};
+use knightbot::eval::{evaluate, pawns::PawnTable};
+
+const POSITIONS: [&str; 5] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "rnbqkb1r/pp1p1ppp/5n2/2pPp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 4",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 0 1",
+    "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+];
+
+const HIDDEN: usize = 32;
+
+/// A small xorshift generator, so the networks and move sequences are random but reproducible.
+fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+/// A network file with small random weights.
+fn random_network_bytes(hidden: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(hidden as u32).to_le_bytes());
+    for _ in 0..INPUTS * hidden + hidden + 2 * hidden + 1 {
+        let value = (next_random(&mut state) % 65) as i16 - 32;
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+fn random_network() -> Arc<Network> {
+    Arc::new(Network::from_bytes(&random_network_bytes(HIDDEN, 0x9E3779B97F4A7C15)).unwrap())
+}
+
+#[test]
+fn network_files_are_read_and_written() {
+    let bytes = random_network_bytes(HIDDEN, 1);
+    let network = Network::from_bytes(&bytes).unwrap();
+    assert_eq!(network.hidden_size(), HIDDEN);
+    assert_eq!(network.to_bytes(), bytes);
+
+    let mut wrong_magic = bytes.clone();
+    wrong_magic[0] = b'X';
+    assert!(matches!(Network::from_bytes(&wrong_magic), Err(NetworkError::InvalidMagic)));
+    assert!(matches!(Network::from_bytes(&bytes[..bytes.len() - 1]), Err(NetworkError::InvalidSize { .. })));
+    assert!(matches!(Network::from_bytes(&random_network_bytes(10, 1)), Err(NetworkError::InvalidHiddenSize(10))));
+    let mut wrong_version = bytes;
+    wrong_version[4] = 9;
+    assert!(matches!(Network::from_bytes(&wrong_version), Err(NetworkError::UnsupportedVersion(9))));
+}
+
+#[test]
+fn incremental_accumulator_matches_a_full_refresh() {
+    let network = random_network();
+    let mut state = 0x2545F4914F6CDD1D;
+    for fen in POSITIONS {
+        let mut board = Board::from_fen(fen).unwrap();
+        board.set_network(Some(network.clone()));
+        let start = board.nnue.as_ref().unwrap().accumulator.clone();
+        for _ in 0..60 {
+            let moves = generate_legal_moves(&board);
+            if moves.is_empty() {
+                break;
+            }
+            board.make_move(moves[next_random(&mut state) as usize % moves.len()]);
+            let mut refreshed = board.clone();
+            refreshed.update();
+            assert_eq!(board.nnue.as_ref().unwrap().accumulator, refreshed.nnue.unwrap().accumulator, "{}", board.to_fen());
+        }
+        while !board.history.is_empty() {
+            board.unmake_move();
+        }
+        assert_eq!(board.nnue.unwrap().accumulator, start, "{fen}");
+    }
+}
+
+#[test]
+fn the_network_replaces_the_handcrafted_evaluation() {
+    let mut pawn_table = PawnTable::new();
+    let network = random_network();
+    for fen in POSITIONS {
+        let mut board = Board::from_fen(fen).unwrap();
+        let handcrafted = evaluate(&board, &mut pawn_table);
+        board.set_network(Some(network.clone()));
+        let nnue = board.nnue.as_ref().unwrap().evaluate(board.side_to_move);
+        assert_eq!(evaluate(&board, &mut pawn_table), nnue, "{fen}");
+        // Each side sees the board from its own point of view, so the mirrored position evaluates
+        // the same.
+        assert_eq!(evaluate(&board.mirrored(), &mut pawn_table), nnue, "{fen}");
+        board.set_network(None);
+        assert_eq!(evaluate(&board, &mut pawn_table), handcrafted, "{fen}");
+    }
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn avx2_matches_scalar() {
+    use knightbot::eval::nnue::avx2;
+
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    let mut state = 0x853C49E6748FEA9B;
+    let mut random_values = |range: u64, offset: i16| -> Vec<i16> {
+        (0..256).map(|_| (next_random(&mut state) % range) as i16 - offset).collect()
+    };
+    let values = random_values(1024, 384);
+    let weights = random_values(512, 256);
+
+    // SAFETY: AVX2 is available and the slices are 256 values long.
+    unsafe {
+        assert_eq!(avx2::dot_clipped(&values, &weights), scalar::dot_clipped(&values, &weights));
+        let (mut simd, mut plain) = (values.clone(), values.clone());
+        avx2::add_assign(&mut simd, &weights);
+        scalar::add_assign(&mut plain, &weights);
+        assert_eq!(simd, plain);
+        avx2::sub_assign(&mut simd, &weights);
+        scalar::sub_assign(&mut plain, &weights);
+        assert_eq!(simd, values);
+        assert_eq!(plain, values);
+    }
+}
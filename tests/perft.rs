@@ -0,0 +1,126 @@
+use knightbot::board::{
+    board::{Board, CastlingRights},
+    movegen::{generate_legal_moves, perft},
+    square::Square,
+};
+
+/// Known perft results, see https://www.chessprogramming.org/Perft_Results.
+const PERFT_SUITE: [(&str, u32, u64); 5] = [
+    ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 3, 8902),
+    ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 3, 97862),
+    ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 43238),
+    ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 3, 9467),
+    ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 3, 62379),
+];
+
+/// Chess960 positions with castling rights on various files, from the Chess960 perft suite.
+const CHESS960_PERFT_SUITE: [(&str, u32, u64); 8] = [
+    ("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", 3, 12189),
+    ("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", 3, 18002),
+    ("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", 3, 10471),
+    ("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9", 3, 13440),
+    ("1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9", 3, 31058),
+    ("qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9", 3, 26578),
+    ("q1bnrkr1/ppppp2p/2n2p2/4b1p1/2NP4/8/PPP1PPPP/QNB1RRKB w ge - 1 9", 3, 24566),
+    ("qbn1brkr/ppp1p1p1/2n4p/3p1p2/P7/6PP/QPPPPP2/1BNNBRKR w HFhf - 0 9", 3, 17054),
+];
+
+#[test]
+fn perft_suite() {
+    for (fen, depth, expected) in PERFT_SUITE {
+        let mut board = Board::from_fen(fen).unwrap();
+        assert_eq!(perft(&mut board, depth), expected, "perft({depth}) of {fen}");
+    }
+}
+
+#[test]
+fn make_unmake_restores_position() {
+    for (fen, depth, _) in PERFT_SUITE {
+        let mut board = Board::from_fen(fen).unwrap();
+        perft(&mut board, depth.min(2));
+        assert_eq!(board.to_fen(), fen);
+        assert_eq!(board.hash, board.compute_hash());
+    }
+}
+
+#[test]
+fn gives_check_matches_making_the_move() {
+    fn check(board: &mut Board, depth: u32) {
+        for mv in generate_legal_moves(board) {
+            let (gives_check, fen) = (board.gives_check(mv), board.to_fen());
+            board.make_move(mv);
+            assert_eq!(gives_check, board.in_check(), "{mv} in {fen}");
+            if depth > 1 {
+                check(board, depth - 1);
+            }
+            board.unmake_move();
+        }
+    }
+    for (fen, _, _) in PERFT_SUITE.iter().chain(&CHESS960_PERFT_SUITE) {
+        check(&mut Board::from_fen(fen).unwrap(), 2);
+    }
+}
+
+#[test]
+fn malformed_piece_placement_is_rejected() {
+    for placement in ["9/8/8/8/8/8/8/8", "08/8/8/8/8/8/8/8", "4k4/8/8/8/8/8/8/4K3", "k7/8/8/8/8/8/8/K26"] {
+        assert!(Board::from_fen(&format!("{placement} w - - 0 1")).is_err(), "{placement}");
+    }
+    // A run of digits long enough to overflow the file counter.
+    let fours = "4".repeat(70);
+    assert!(Board::from_fen(&format!("{fours}/8/8/8/8/8/8/8 w - - 0 1")).is_err());
+}
+
+#[test]
+fn chess960_perft_suite() {
+    for (fen, depth, expected) in CHESS960_PERFT_SUITE {
+        let mut board = Board::from_fen(fen).unwrap();
+        assert!(board.chess960, "{fen}");
+        assert_eq!(perft(&mut board, depth), expected, "perft({depth}) of {fen}");
+        assert_eq!(board.to_fen(), fen);
+        assert_eq!(board.hash, board.compute_hash());
+    }
+}
+
+#[test]
+fn chess960_castling_fields_are_parsed() {
+    // X-FEN names the outermost rooks with KQkq, Shredder-FEN names their files.
+    let xfen = Board::from_fen("1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/RR2K1R1 w KQkq - 0 1").unwrap();
+    assert!(xfen.chess960);
+    assert_eq!(xfen.castling_rooks, [Square::G1, Square::A1, Square::G8, Square::B8]);
+    assert_eq!(xfen.to_fen(), "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/RR2K1R1 w GAgb - 0 1");
+    assert_eq!(Board::from_fen(&xfen.to_fen()).unwrap().castling_rooks, xfen.castling_rooks);
+
+    // The standard setup stays in standard notation, whichever way its rights are written.
+    let shredder = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1").unwrap();
+    assert!(!shredder.chess960);
+    assert_eq!(shredder.castling_rights, CastlingRights::ALL);
+    assert_eq!(shredder.to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    // Rook files need a king on the back rank to tell the sides apart.
+    assert!(Board::from_fen("7k/8/8/8/8/8/4K3/R6R w A - 0 1").is_err());
+}
+
+#[test]
+fn chess960_castling_moves_king_and_rook() {
+    // The king on b1 castles queenside onto c1 and the rook on a1 goes to d1.
+    let fen = "r5kr/8/8/8/8/8/8/RK5R w HAh - 0 1";
+    let mut board = Board::from_fen(fen).unwrap();
+    let castling = "b1a1".parse().unwrap();
+    assert!(generate_legal_moves(&board).contains(&castling));
+    board.make_move(castling);
+    assert_eq!(board.to_fen(), "r5kr/8/8/8/8/8/8/2KR3R b h - 1 1");
+    assert_eq!(board.hash, board.compute_hash());
+    board.unmake_move();
+    assert_eq!(board.to_fen(), fen);
+
+    // Black castles kingside with the king staying on g8 and the rook going to f8.
+    let mut board = Board::from_fen("r5kr/8/8/8/8/8/8/RK5R b HAh - 0 1").unwrap();
+    let castling = "g8h8".parse().unwrap();
+    assert!(generate_legal_moves(&board).contains(&castling));
+    board.make_move(castling);
+    assert_eq!(board.to_fen(), "r4rk1/8/8/8/8/8/8/RK5R w HA - 1 2");
+
+    // The rook on b1 shields the king on c1 from the rook on a1, so it can't castle away.
+    let shielded = Board::from_fen("7k/8/8/8/8/8/8/rRK4R w B - 0 1").unwrap();
+    assert!(!generate_legal_moves(&shielded).contains(&"c1b1".parse().unwrap()));
+}
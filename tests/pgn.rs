@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use knightbot::board::board::Board;
+use knightbot::pgn::{Game, PgnErrorKind, PgnEval, PgnMove, PgnReader};
+
+const ANNOTATED: &str = r#"[Event "Casual \"blitz\" game"]
+[Site "?"]
+[Date "2024.01.02"]
+[Round "1"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+[ECO "C50"]
+
+{Opening comment} 1. e4 e5 2. Nf3 $1 Nc6 (2... d6 {Philidor} 3. d4 (3. Bc4) 3... exd4)
+3. Bc4!? Bc5 ; rest of line comment
+4. c3 {[%eval 0.35] [%clk 0:04:58.5] book} Nf6?? 5. Ng5 $6 O-O 6. Nxf7 Rxf7 7. Bxf7+ Kxf7 1-0
+"#;
+
+fn read_all(pgn: &str) -> Vec<Result<Game, knightbot::pgn::PgnError>> {
+    PgnReader::new(pgn.as_bytes()).collect()
+}
+
+fn sans(board: &Board, moves: &[PgnMove]) -> Vec<String> {
+    let mut board = board.clone();
+    moves
+        .iter()
+        .map(|pgn_move| {
+            let san = pgn_move.mv.to_san(&board);
+            board.make_move(pgn_move.mv);
+            san
+        })
+        .collect()
+}
+
+#[test]
+fn reads_annotated_game() {
+    let games = read_all(ANNOTATED);
+    assert_eq!(games.len(), 1);
+    let game = games[0].as_ref().unwrap();
+
+    assert_eq!(game.tag("Event"), Some("Casual \"blitz\" game"));
+    assert_eq!(game.tag("ECO"), Some("C50"));
+    assert_eq!(game.result, "1-0");
+    assert_eq!(game.comment.as_deref(), Some("Opening comment"));
+    assert_eq!(
+        sans(&Board::new(), &game.moves),
+        ["e4", "e5", "Nf3", "Nc6", "Bc4", "Bc5", "c3", "Nf6", "Ng5", "O-O", "Nxf7", "Rxf7", "Bxf7+", "Kxf7"]
+    );
+
+    // NAGs, including traditional suffix annotations.
+    assert_eq!(game.moves[2].nags, [1]);
+    assert_eq!(game.moves[4].nags, [5]);
+    assert_eq!(game.moves[7].nags, [4]);
+    assert_eq!(game.moves[8].nags, [6]);
+    // Comment commands are taken apart from the text.
+    assert_eq!(game.moves[5].comment.as_deref(), Some("rest of line comment"));
+    assert_eq!(game.moves[6].eval, Some(PgnEval::Centipawns(35)));
+    assert_eq!(game.moves[6].clock, Some(Duration::from_millis(298_500)));
+    assert_eq!(game.moves[6].comment.as_deref(), Some("book"));
+
+    // The variation replaces 2... Nc6 and contains a nested variation replacing 3. d4.
+    let variations = &game.moves[3].variations;
+    assert_eq!(variations.len(), 1);
+    let mut board = Board::new();
+    for pgn_move in &game.moves[..3] {
+        board.make_move(pgn_move.mv);
+    }
+    assert_eq!(sans(&board, &variations[0]), ["d6", "d4", "exd4"]);
+    assert_eq!(variations[0][0].comment.as_deref(), Some("Philidor"));
+    board.make_move(variations[0][0].mv);
+    assert_eq!(sans(&board, &variations[0][1].variations[0]), ["Bc4"]);
+
+    let final_position = game.final_position().unwrap();
+    assert_eq!(final_position.to_fen(), "r1bq4/pppp1kpp/2n2n2/2b1p3/4P3/2P5/PP1P1PPP/RNBQK2R w KQ - 0 8");
+}
+
+#[test]
+fn streams_several_games() {
+    let pgn = format!(
+        "{ANNOTATED}\n[Event \"Second\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 30\"]\n[SetUp \"1\"]\n\n30... Kd7 31. e4 *\n\n\
+         [Event \"No result\"]\n\n1. d4 d5\n[Event \"Last\"]\n\n1. c4 1/2-1/2\n"
+    );
+    let games: Vec<Game> = read_all(&pgn).into_iter().map(Result::unwrap).collect();
+    assert_eq!(games.len(), 4);
+    assert_eq!(games[1].tag("Event"), Some("Second"));
+    assert_eq!(games[1].result, "*");
+    assert_eq!(games[1].final_position().unwrap().to_fen(), "8/3k4/8/8/4P3/8/8/4K3 b - - 0 31");
+    // A game without a result ends where the next tag section starts.
+    assert_eq!(games[2].moves.len(), 2);
+    assert_eq!(games[2].result, "*");
+    assert_eq!(games[3].result, "1/2-1/2");
+    assert!(read_all("").is_empty());
+    assert!(read_all("\n\n  \n").is_empty());
+}
+
+#[test]
+fn reports_errors_with_positions_and_recovers() {
+    let pgn = "[Event \"Broken\"]\n\n1. e4 e5 2. Nf3 Nc6\n3. Bb5 a6 4. Bxe8 Nf6 1-0\n\n[Event \"Fine\"]\n\n1. d4 *\n";
+    let games = read_all(pgn);
+    assert_eq!(games.len(), 2);
+    let error = games[0].as_ref().unwrap_err();
+    assert_eq!((error.line, error.column), (4, 14));
+    assert!(matches!(error.kind, PgnErrorKind::InvalidMove(_)));
+    assert!(error.to_string().starts_with("line 4, column 14: illegal move: Bxe8"));
+    assert_eq!(games[1].as_ref().unwrap().tag("Event"), Some("Fine"));
+
+    let errors = [
+        ("[Event \"x\"]\n\n1. e4 (1. d4 d5\n", "unterminated variation"),
+        ("[Event \"x\"]\n\n1. e4 {never closed\n", "unterminated comment"),
+        ("[Event \"x\"]\n\n1. e4 e5) *\n", "unmatched )"),
+        ("[Event \"x\"]\n\n1. e4 (1. d4 1-0) *\n", "result inside a variation"),
+        ("[Event \"x\"]\n\n$3 1. e4 *\n", "NAG before any move"),
+        ("[Event x]\n\n1. e4 *\n", "malformed tag pair"),
+        ("[Event \"x\"]\n\n1. e4 & *\n", "unexpected character '&'"),
+        ("[FEN \"8/8/8\"]\n\n1. e4 *\n", "FEN is missing"),
+    ];
+    for (pgn, message) in errors {
+        let games = read_all(pgn);
+        let error = games[0].as_ref().unwrap_err();
+        assert!(error.to_string().contains(message), "{pgn:?} gave {error}");
+    }
+    let error = read_all("[Event \"x\"]\n[FEN \"8/8/8\"]\n\n*\n").remove(0).unwrap_err();
+    assert_eq!((error.line, error.column), (2, 6));
+
+    // A FEN that parses but can't occur in a game is rejected before any move is replayed.
+    let error = read_all("[Event \"x\"]\n[FEN \"8/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n1. e3 *\n").remove(0).unwrap_err();
+    assert_eq!((error.line, error.column), (2, 6));
+    assert!(matches!(error.kind, PgnErrorKind::InvalidPosition(_)));
+    assert!(error.to_string().ends_with("invalid position: black has 0 kings"), "{error}");
+}
+
+#[test]
+fn out_of_range_annotations_are_ignored() {
+    let games = read_all("1. e4 {[%clk inf] [%eval inf]} e5 {[%clk 1e300] [%eval NaN]} 2. Nf3 {[%eval 1e10] [%clk 0:00:05]} 1-0\n");
+    let game = games.into_iter().next().unwrap().unwrap();
+    assert_eq!(game.moves.len(), 3);
+    assert!(game.moves[..2].iter().all(|mv| mv.clock.is_none() && mv.eval.is_none()));
+    assert_eq!(game.moves[2].eval, None);
+    assert_eq!(game.moves[2].clock, Some(Duration::from_secs(5)));
+}
+
+#[test]
+fn writes_engine_games() {
+    let mut game = Game::new();
+    game.set_tag("Event", "Engine match");
+    game.set_tag("White", "KnightBot");
+    game.set_tag("Black", "KnightBot \"dev\"");
+    game.set_tag("TimeControl", "60+1");
+    let mut board = Board::new();
+    for (i, san) in ["e4", "c5", "Nf3", "d6"].into_iter().enumerate() {
+        let mv = board.parse_san(san).unwrap();
+        let mut pgn_move = PgnMove::new(mv);
+        pgn_move.eval = Some(PgnEval::from_score(25 - 10 * i as i32, board.side_to_move));
+        pgn_move.clock = Some(Duration::from_millis(60_000 - 1_500 * i as u64));
+        board.make_move(mv);
+        game.moves.push(pgn_move);
+    }
+    game.moves[3].eval = Some(PgnEval::Mate(-4));
+    game.moves[3].comment = Some("a {tricky} comment".to_string());
+    game.result = "0-1".to_string();
+
+    let expected = "[Event \"Engine match\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"KnightBot\"]\n\
+        [Black \"KnightBot \\\"dev\\\"\"]\n[Result \"0-1\"]\n[TimeControl \"60+1\"]\n\n\
+        1. e4 {[%eval 0.25] [%clk 0:01:00]} 1... c5 {[%eval -0.15] [%clk 0:00:58.5]}\n\
+        2. Nf3 {[%eval 0.05] [%clk 0:00:57]} 2... d6\n\
+        {[%eval #-4] [%clk 0:00:55.5] a {tricky) comment} 0-1\n\n";
+    assert_eq!(game.to_pgn(), expected);
+
+    let read = read_all(expected).remove(0).unwrap();
+    assert_eq!(read.moves[0].eval, Some(PgnEval::Centipawns(25)));
+    assert_eq!(read.moves[1].eval, Some(PgnEval::Centipawns(-15)));
+    assert_eq!(read.moves[3].eval, Some(PgnEval::Mate(-4)));
+    assert_eq!(read.moves[3].clock, Some(Duration::from_millis(55_500)));
+    assert_eq!(read.moves[3].comment.as_deref(), Some("a {tricky) comment"));
+}
+
+#[test]
+fn eval_from_search_scores() {
+    use knightbot::board::piece::Color;
+    use knightbot::search::MATE;
+    assert_eq!(PgnEval::from_score(-120, Color::White), PgnEval::Centipawns(-120));
+    assert_eq!(PgnEval::from_score(-120, Color::Black), PgnEval::Centipawns(120));
+    assert_eq!(PgnEval::from_score(MATE - 3, Color::White), PgnEval::Mate(2));
+    assert_eq!(PgnEval::from_score(MATE - 3, Color::Black), PgnEval::Mate(-2));
+    assert_eq!(PgnEval::from_score(-MATE + 4, Color::White), PgnEval::Mate(-2));
+    assert_eq!(PgnEval::Centipawns(-5).to_string(), "-0.05");
+}
+
+#[test]
+fn written_games_read_back_the_same() {
+    let game = read_all(ANNOTATED).remove(0).unwrap();
+    let pgn = game.to_pgn();
+    assert!(pgn.lines().all(|line| line.len() < 80), "{pgn}");
+    let movetext = pgn.split_whitespace().collect::<Vec<_>>().join(" ");
+    assert!(movetext.contains("(2... d6 {Philidor} 3. d4 (3. Bc4) 3... exd4) 3. Bc4 $5"), "{pgn}");
+    assert_eq!(read_all(&pgn).remove(0).unwrap(), game);
+
+    let fen_game = read_all("[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 30\"]\n\n30... Kd7 31. e4 Kd6 *\n").remove(0).unwrap();
+    let pgn = fen_game.to_pgn();
+    assert!(pgn.ends_with("\n30... Kd7 31. e4 Kd6 *\n\n"), "{pgn}");
+    assert_eq!(read_all(&pgn).remove(0).unwrap().moves, fen_game.moves);
+}
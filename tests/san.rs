@@ -0,0 +1,116 @@
+use knightbot::board::board::Board;
+use knightbot::board::fen::START_FEN;
+use knightbot::board::movegen::generate_legal_moves;
+use knightbot::board::moves::Move;
+use knightbot::board::san::SanError;
+
+fn san(fen: &str, uci: &str) -> String {
+    let board = Board::from_fen(fen).unwrap();
+    let mv: Move = uci.parse().unwrap();
+    assert!(generate_legal_moves(&board).contains(&mv), "{uci} is not legal in {fen}");
+    mv.to_san(&board)
+}
+
+fn parse(fen: &str, san: &str) -> Result<String, SanError> {
+    Board::from_fen(fen).unwrap().parse_san(san).map(|mv| mv.to_string())
+}
+
+#[test]
+fn formats_moves() {
+    assert_eq!(san(START_FEN, "e2e4"), "e4");
+    assert_eq!(san(START_FEN, "g1f3"), "Nf3");
+    // Captures, including en passant.
+    assert_eq!(san("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", "e4d5"), "exd5");
+    assert_eq!(san("rnbqkbnr/ppp2ppp/4p3/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3", "e5d6"), "exd6");
+    assert_eq!(san("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3", "f3e5"), "Nxe5");
+    // Castling.
+    assert_eq!(san("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1"), "O-O");
+    assert_eq!(san("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8"), "O-O-O");
+    // Promotions.
+    assert_eq!(san("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1", "e7e8q"), "e8=Q");
+    assert_eq!(san("3r2k1/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7d8n"), "exd8=N");
+}
+
+#[test]
+fn disambiguates_moves() {
+    // By file.
+    assert_eq!(san("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", "b1d2"), "Nbd2");
+    // By rank.
+    assert_eq!(san("4k3/8/8/8/R7/8/8/R3K3 w - - 0 1", "a1a2"), "R1a2");
+    // By both, with three queens attacking the same square.
+    assert_eq!(san("k7/8/8/8/8/2Q1Q3/8/2Q1K3 w - - 0 1", "c3d2"), "Qc3d2");
+    assert_eq!(san("k7/8/8/8/8/2Q1Q3/8/2Q1K3 w - - 0 1", "e3d2"), "Qed2");
+    // A pinned piece doesn't need to be told apart.
+    assert_eq!(san("4k3/4r3/8/8/8/8/4N3/1N2K3 w - - 0 1", "b1d2"), "Nd2");
+}
+
+#[test]
+fn marks_check_and_mate() {
+    assert_eq!(san("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8#");
+    assert_eq!(san("6k1/5pp1/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8+");
+    assert_eq!(san("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), "b8=Q+");
+    assert_eq!(san("r3k3/8/8/8/8/8/3K4/8 b q - 0 1", "e8c8"), "O-O-O+");
+}
+
+#[test]
+fn parses_common_variants() {
+    let castling = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    assert_eq!(parse(castling, "O-O").unwrap(), "e1g1");
+    assert_eq!(parse(castling, "0-0").unwrap(), "e1g1");
+    assert_eq!(parse(castling, "0-0-0").unwrap(), "e1c1");
+    assert_eq!(parse(castling, "O-O-O+").unwrap(), "e1c1");
+    // The king moving two squares is castling, not a king move.
+    assert!(matches!(parse(castling, "Kg1"), Err(SanError::IllegalMove(_))));
+
+    let mate = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+    for variant in ["Ra8#", "Ra8", "Ra8+", "Ra8!!", "Ra8#?!", "Rxa8", "R1a8", "Raa8", "Ra1a8"] {
+        assert_eq!(parse(mate, variant).unwrap(), "a1a8", "{variant}");
+    }
+    let promotion = "3r2k1/4P3/8/8/8/8/8/4K3 w - - 0 1";
+    assert_eq!(parse(promotion, "exd8=Q+").unwrap(), "e7d8q");
+    assert_eq!(parse(promotion, "exd8N").unwrap(), "e7d8n");
+    assert_eq!(parse(promotion, "e8=R").unwrap(), "e7e8r");
+    let en_passant = "rnbqkbnr/ppp2ppp/4p3/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3";
+    assert_eq!(parse(en_passant, "exd6 e.p.").unwrap(), "e5d6");
+    assert_eq!(parse(en_passant, "exd6").unwrap(), "e5d6");
+    // A lowercase b is a pawn on the b-file, not a bishop.
+    assert_eq!(parse(START_FEN, "b4").unwrap(), "b2b4");
+}
+
+#[test]
+fn rejects_invalid_moves() {
+    assert!(matches!(parse(START_FEN, "nf3"), Err(SanError::InvalidSyntax(_))));
+    assert!(matches!(parse(START_FEN, "qd4"), Err(SanError::InvalidSyntax(_))));
+    assert!(matches!(parse(START_FEN, ""), Err(SanError::InvalidSyntax(_))));
+    assert!(matches!(parse(START_FEN, "Nz3"), Err(SanError::InvalidSyntax(_))));
+    assert!(matches!(parse(START_FEN, "e5"), Err(SanError::IllegalMove(_))));
+    assert!(matches!(parse(START_FEN, "O-O"), Err(SanError::IllegalMove(_))));
+    // A pawn reaching the last rank must promote.
+    assert!(matches!(parse("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1", "e8"), Err(SanError::IllegalMove(_))));
+    assert!(matches!(parse("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", "Nd2"), Err(SanError::AmbiguousMove(_))));
+    assert!(matches!(parse("k7/8/8/8/8/2Q1Q3/8/2Q1K3 w - - 0 1", "Qcd2"), Err(SanError::AmbiguousMove(_))));
+}
+
+#[test]
+fn round_trips_every_legal_move() {
+    for fen in [
+        START_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "k7/8/8/8/8/2Q1Q3/8/2Q1K3 w - - 0 1",
+    ] {
+        let mut board = Board::from_fen(fen).unwrap();
+        for mv in generate_legal_moves(&board) {
+            let san = mv.to_san(&board);
+            assert_eq!(board.parse_san(&san), Ok(mv), "{san} in {fen}");
+            // The replies too, to cover the other side.
+            board.make_move(mv);
+            for reply in generate_legal_moves(&board) {
+                assert_eq!(board.parse_san(&reply.to_san(&board)), Ok(reply), "{fen} {mv} {reply}");
+            }
+            board.unmake_move();
+        }
+    }
+}
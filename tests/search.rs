@@ -0,0 +1,108 @@
+use knightbot::board::{board::Board, movegen::generate_legal_moves};
+use knightbot::search::smp::ThreadPool;
+use knightbot::search::tt::{Bound, TranspositionTable};
+use knightbot::search::{SearchConfig, SearchLimits, Searcher, MATE_IN_MAX};
+
+fn search(fen: &str, depth: i32, config: SearchConfig) -> (String, i32, u64) {
+    let mut board = Board::from_fen(fen).unwrap();
+    let mut searcher = Searcher::new(4);
+    searcher.config = config;
+    let result = searcher.search(&mut board, SearchLimits { depth: Some(depth), ..SearchLimits::default() });
+    (result.best_move.unwrap().to_string(), result.score, result.nodes)
+}
+
+#[test]
+fn finds_back_rank_mate_with_and_without_pruning() {
+    for config in [SearchConfig::NO_PRUNING, SearchConfig::default()] {
+        let (best_move, score, _) = search("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1", 4, config);
+        assert_eq!(best_move, "a1a8");
+        assert!(score >= MATE_IN_MAX);
+    }
+}
+
+#[test]
+fn finds_mate_in_two_with_pruning() {
+    let (best_move, score, _) = search("r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1", 5, SearchConfig::default());
+    assert_eq!(best_move, "f8c5");
+    assert!(score >= MATE_IN_MAX);
+}
+
+#[test]
+fn mating_checks_survive_every_pruning_setting() {
+    // Each technique on its own must keep the checks of the mating line, not just the defaults.
+    let fen = "r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1";
+    let default = SearchConfig::default();
+    let configs = [
+        SearchConfig { null_move_pruning: false, ..default },
+        SearchConfig { late_move_reductions: false, ..default },
+        SearchConfig { reverse_futility_pruning: false, ..default },
+        SearchConfig { futility_pruning: false, ..default },
+        SearchConfig { late_move_pruning: false, ..default },
+    ];
+    for config in configs {
+        let (best_move, score, _) = search(fen, 5, config);
+        assert_eq!(best_move, "f8c5", "{config:?}");
+        assert!(score >= MATE_IN_MAX, "{config:?}");
+    }
+}
+
+#[test]
+fn pruning_searches_fewer_nodes() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let (_, _, full_nodes) = search(fen, 4, SearchConfig::NO_PRUNING);
+    let (_, _, pruned_nodes) = search(fen, 4, SearchConfig::default());
+    assert!(pruned_nodes < full_nodes, "{pruned_nodes} >= {full_nodes}");
+}
+
+#[test]
+fn principal_variation_is_legal_and_ends_in_mate() {
+    let fen = "r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1";
+    let mut board = Board::from_fen(fen).unwrap();
+    let mut searcher = Searcher::new(4);
+    let result = searcher.search(&mut board, SearchLimits { depth: Some(5), ..SearchLimits::default() });
+
+    assert_eq!(result.pv.first(), result.best_move.as_ref());
+    assert_eq!(result.uci_score(), "mate 3");
+    for mv in &result.pv {
+        assert!(generate_legal_moves(&board).contains(mv), "{mv} is not legal in {}", board.to_fen());
+        board.make_move(*mv);
+    }
+    assert!(board.in_check());
+    assert!(generate_legal_moves(&board).is_empty());
+}
+
+#[test]
+fn principal_variation_survives_a_filled_transposition_table() {
+    let mut board = Board::new();
+    let mut searcher = Searcher::new(4);
+    let limits = SearchLimits { depth: Some(5), ..SearchLimits::default() };
+    searcher.search(&mut board, limits.clone());
+    // The second search finds every node of the first one in the table.
+    let result = searcher.search(&mut board, limits);
+    assert!(result.pv.len() >= 5, "{:?}", result.pv);
+}
+
+#[test]
+fn lazy_smp_finds_mate() {
+    let board = Board::from_fen("r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1").unwrap();
+    let mut pool = ThreadPool::new(3, 4);
+    // The helpers order moves differently, so their results in the shared table must not lose
+    // the mating checks either.
+    let result = pool.search(&board, SearchLimits { depth: Some(5), ..SearchLimits::default() });
+    assert_eq!(result.best_move.unwrap().to_string(), "f8c5");
+    assert!(result.score >= MATE_IN_MAX);
+    assert!(result.nodes > 0);
+}
+
+#[test]
+fn transposition_table_round_trips_entries() {
+    let tt = TranspositionTable::new(1);
+    let mv = "e7e8q".parse().unwrap();
+    tt.store(0xDEAD_BEEF, Some(mv), -MATE_IN_MAX - 3, 12, Bound::Lower);
+    let entry = tt.probe(0xDEAD_BEEF).unwrap();
+    assert_eq!(entry.best_move, Some(mv));
+    assert_eq!(entry.score, -MATE_IN_MAX - 3);
+    assert_eq!(entry.depth, 12);
+    assert_eq!(entry.bound, Bound::Lower);
+    assert!(tt.probe(0xDEAD_BEEE).is_none());
+}
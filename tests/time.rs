@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use knightbot::board::board::Board;
+use knightbot::board::piece::Color;
+use knightbot::search::time::TimeManager;
+use knightbot::search::{SearchLimits, Searcher};
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+fn manager(limits: &SearchLimits, side: Color) -> TimeManager {
+    TimeManager::new(limits, side, Duration::ZERO).expect("the search is limited by time")
+}
+
+#[test]
+fn no_time_control_is_unmanaged() {
+    assert!(TimeManager::new(&SearchLimits::default(), Color::White, ms(10)).is_none());
+    let depth_only = SearchLimits { depth: Some(10), nodes: Some(1000), ..SearchLimits::default() };
+    assert!(TimeManager::new(&depth_only, Color::White, ms(10)).is_none());
+    // Only the clock of the side to move matters.
+    let black_clock = SearchLimits { btime: Some(ms(60_000)), ..SearchLimits::default() };
+    assert!(TimeManager::new(&black_clock, Color::White, ms(10)).is_none());
+}
+
+#[test]
+fn fixed_move_time_is_used_in_full() {
+    let limits = SearchLimits { movetime: Some(ms(1000)), wtime: Some(ms(5000)), ..SearchLimits::default() };
+    let mut time_manager = manager(&limits, Color::White);
+    assert_eq!(time_manager.hard, ms(1000));
+    assert_eq!(time_manager.soft_limit(), ms(1000));
+    // Stability doesn't shorten a fixed move time.
+    time_manager.update("e2e4".parse().ok(), 30, 0.99);
+    assert!(!time_manager.should_stop(ms(999)));
+    assert!(time_manager.should_stop(ms(1000)));
+}
+
+#[test]
+fn sudden_death_spreads_time_over_the_game() {
+    let limits = SearchLimits { wtime: Some(ms(60_000)), btime: Some(ms(1000)), ..SearchLimits::default() };
+    let white = manager(&limits, Color::White);
+    let black = manager(&limits, Color::Black);
+    assert_eq!(white.optimum, ms(2000));
+    assert_eq!(white.hard, ms(8000));
+    assert!(black.optimum < white.optimum);
+    assert!(black.hard <= ms(750));
+}
+
+#[test]
+fn increment_adds_to_the_optimum() {
+    let without = manager(&SearchLimits { wtime: Some(ms(60_000)), ..SearchLimits::default() }, Color::White);
+    let with = manager(&SearchLimits { wtime: Some(ms(60_000)), winc: Some(ms(1000)), ..SearchLimits::default() }, Color::White);
+    assert_eq!(with.optimum, without.optimum + ms(750));
+    // The increment of the other side is ignored.
+    let other = manager(&SearchLimits { wtime: Some(ms(60_000)), binc: Some(ms(1000)), ..SearchLimits::default() }, Color::White);
+    assert_eq!(other.optimum, without.optimum);
+}
+
+#[test]
+fn moves_to_go_divides_the_remaining_time() {
+    let limits = SearchLimits { btime: Some(ms(40_000)), movestogo: Some(10), ..SearchLimits::default() };
+    let time_manager = manager(&limits, Color::Black);
+    assert_eq!(time_manager.optimum, ms(4000));
+    assert_eq!(time_manager.hard, ms(16_000));
+
+    // On the last move before the time control, the hard limit still leaves a reserve.
+    let last_move = manager(&SearchLimits { movestogo: Some(1), ..limits }, Color::Black);
+    assert_eq!(last_move.optimum, ms(30_000));
+    assert_eq!(last_move.hard, ms(30_000));
+}
+
+#[test]
+fn move_overhead_is_subtracted() {
+    let limits = SearchLimits { wtime: Some(ms(30_100)), movestogo: Some(10), ..SearchLimits::default() };
+    let time_manager = TimeManager::new(&limits, Color::White, ms(100)).unwrap();
+    assert_eq!(time_manager.optimum, ms(3000));
+    let movetime = SearchLimits { movetime: Some(ms(500)), ..SearchLimits::default() };
+    assert_eq!(TimeManager::new(&movetime, Color::White, ms(100)).unwrap().hard, ms(400));
+    // The limits never reach zero, even when the overhead exceeds the time left.
+    let flagging = SearchLimits { wtime: Some(ms(50)), ..SearchLimits::default() };
+    assert!(TimeManager::new(&flagging, Color::White, ms(100)).unwrap().hard > Duration::ZERO);
+}
+
+#[test]
+fn unstable_best_move_extends_the_soft_limit() {
+    let limits = SearchLimits { wtime: Some(ms(60_000)), ..SearchLimits::default() };
+    let mut stable = manager(&limits, Color::White);
+    let mut unstable = manager(&limits, Color::White);
+    for (iteration, mv) in ["e2e4", "d2d4", "e2e4", "g1f3"].into_iter().enumerate() {
+        stable.update("e2e4".parse().ok(), 20, 0.5);
+        unstable.update(mv.parse().ok(), 20, 0.5);
+        assert!(iteration == 0 || unstable.soft_limit() > stable.soft_limit());
+    }
+    assert!(unstable.soft_limit() <= unstable.hard);
+}
+
+#[test]
+fn score_drop_extends_the_soft_limit() {
+    let limits = SearchLimits { wtime: Some(ms(60_000)), ..SearchLimits::default() };
+    let mut time_manager = manager(&limits, Color::White);
+    time_manager.update("e2e4".parse().ok(), 50, 0.5);
+    let before = time_manager.soft_limit();
+    time_manager.update("e2e4".parse().ok(), -30, 0.5);
+    assert!(time_manager.soft_limit() > before);
+    time_manager.update("e2e4".parse().ok(), 40, 0.5);
+    assert_eq!(time_manager.soft_limit(), before);
+}
+
+#[test]
+fn dominant_best_move_stops_early() {
+    let limits = SearchLimits { wtime: Some(ms(60_000)), ..SearchLimits::default() };
+    let mut time_manager = manager(&limits, Color::White);
+    time_manager.update("e2e4".parse().ok(), 20, 0.95);
+    assert!(time_manager.soft_limit() < time_manager.optimum);
+    assert!(time_manager.should_stop(time_manager.optimum));
+}
+
+#[test]
+fn search_respects_the_clock() {
+    let mut board = Board::new();
+    let mut searcher = Searcher::new(4);
+    let limits = SearchLimits { wtime: Some(ms(1000)), movestogo: Some(10), ..SearchLimits::default() };
+    let result = searcher.search(&mut board, limits);
+    assert!(result.best_move.is_some());
+    assert!(result.time < ms(750), "searched for {:?}", result.time);
+}
+
+#[test]
+fn single_legal_move_is_played_immediately() {
+    let mut board = Board::from_fen("k7/8/8/8/8/8/1q6/K7 w - - 0 1").unwrap();
+    let mut searcher = Searcher::new(1);
+    let result = searcher.search(&mut board, SearchLimits { wtime: Some(ms(600_000)), ..SearchLimits::default() });
+    assert_eq!(result.best_move.unwrap().to_string(), "a1b2");
+    assert_eq!(result.depth, 1);
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use knightbot::board::board::Board;
+use knightbot::search::SearchConfig;
+use knightbot::tournament::player::{EngineSpec, PlayerError};
+use knightbot::tournament::stats::{elo_to_score, score_to_elo, MatchStats, Sprt, SprtVerdict};
+use knightbot::tournament::{match_report, run_match, MatchConfig, Termination, TimeControl};
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 0.05, "{actual} is not close to {expected}");
+}
+
+#[test]
+fn elo_is_estimated_with_error_bars() {
+    assert_close(score_to_elo(0.5), 0.0);
+    assert_close(score_to_elo(0.75), 190.85);
+    assert_close(elo_to_score(score_to_elo(0.3)), 0.3);
+
+    // A score of 0.7 over 100 games with a per-game variance of 0.16.
+    let stats = MatchStats { wins: 60, draws: 20, losses: 20 };
+    assert_eq!(stats.games(), 100);
+    assert_close(stats.score(), 0.7);
+    let (elo, error) = stats.elo();
+    assert_close(elo, 147.19);
+    let lower = score_to_elo(0.7 - 1.959964 * 0.04);
+    let upper = score_to_elo(0.7 + 1.959964 * 0.04);
+    assert_close(error, (upper - lower) / 2.0);
+    assert_eq!(stats.to_string(), "60 - 20 - 20 [0.700] 100");
+
+    let perfect = MatchStats { wins: 3, draws: 0, losses: 0 };
+    assert_eq!(perfect.elo(), (f64::INFINITY, f64::INFINITY));
+}
+
+#[test]
+fn sprt_accepts_the_hypothesis_the_results_support() {
+    let sprt = Sprt::default();
+    let (lower, upper) = sprt.bounds();
+    assert_close(lower, -2.944);
+    assert_close(upper, 2.944);
+
+    assert_eq!(sprt.verdict(&MatchStats::default()), SprtVerdict::Continue);
+    assert_eq!(sprt.verdict(&MatchStats { wins: 10, draws: 10, losses: 8 }), SprtVerdict::Continue);
+    assert_eq!(sprt.verdict(&MatchStats { wins: 3000, draws: 4000, losses: 2500 }), SprtVerdict::H1);
+    assert_eq!(sprt.verdict(&MatchStats { wins: 2500, draws: 4000, losses: 3000 }), SprtVerdict::H0);
+    assert!(sprt.llr(&MatchStats { wins: 2500, draws: 4000, losses: 2500 }) < 0.0);
+}
+
+#[test]
+fn engine_specs_are_parsed() {
+    assert_eq!("knightbot".parse(), Ok(EngineSpec::default()));
+    let spec: EngineSpec = "knightbot:-lmr,-nmp,eval=net.bin".parse().unwrap();
+    let config = SearchConfig { late_move_reductions: false, null_move_pruning: false, ..SearchConfig::default() };
+    assert_eq!(spec, EngineSpec::Internal { config, eval_file: Some("net.bin".into()) });
+    assert_eq!(spec.to_string(), "knightbot:-nmp,-lmr,eval=net.bin");
+    assert_eq!("./engine".parse(), Ok(EngineSpec::Uci { command: "./engine".into() }));
+    assert!("knightbot:-foo".parse::<EngineSpec>().is_err());
+
+    assert_eq!("10+0.1".parse(), Ok(TimeControl::Fischer { base: Duration::from_secs(10), increment: Duration::from_millis(100) }));
+    assert_eq!("60".parse(), Ok(TimeControl::Fischer { base: Duration::from_secs(60), increment: Duration::ZERO }));
+    assert!("fast".parse::<TimeControl>().is_err());
+}
+
+#[test]
+fn matches_are_played_against_uci_engines_with_paired_colors() {
+    let opening = Board::from_fen("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2").unwrap();
+    let config = MatchConfig {
+        engines: [EngineSpec::default(), EngineSpec::Uci { command: env!("CARGO_BIN_EXE_knightbot").into() }],
+        openings: vec![opening.clone()],
+        games: 2,
+        concurrency: 2,
+        time_control: TimeControl::Nodes(1000),
+        hash_mb: 1,
+        max_plies: 12,
+        sprt: Some(Sprt::default()),
+    };
+    let mut records = Vec::new();
+    let stats = run_match(&config, |record, _| records.push(record.clone())).unwrap();
+
+    assert_eq!(stats.games(), 2);
+    records.sort_by_key(|record| record.game);
+    assert_eq!(records.iter().map(|record| record.white).collect::<Vec<_>>(), [0, 1]);
+    for record in &records {
+        assert_eq!(record.start_fen, opening.to_fen());
+        assert!(!matches!(record.termination, Termination::TimeForfeit | Termination::EngineFailure(_) | Termination::IllegalMove(_)));
+    }
+    let report = match_report(&config, &stats);
+    assert!(report.starts_with(&format!("Score of knightbot vs {}: ", env!("CARGO_BIN_EXE_knightbot"))), "{report}");
+    assert!(report.contains("SPRT (0, 5): LLR"), "{report}");
+
+    let missing = MatchConfig { engines: [EngineSpec::default(), EngineSpec::Uci { command: "/nonexistent/engine".into() }], ..config };
+    assert!(matches!(run_match(&missing, |_, _| {}), Err(PlayerError::Io(_))));
+}
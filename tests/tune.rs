@@ -0,0 +1,76 @@
+use std::io::BufReader;
+
+use knightbot::board::board::Board;
+use knightbot::eval::trace::eval_trace;
+use knightbot::tune::{initial_weights, loss, parse_labelled_position, read_positions, write_params, TuneErrorKind, Tuner, TuningPosition};
+
+const LABELLED: &str = "\
+# Positions and the results of their games.
+rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 [0.5]
+r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 [1.0]
+rnbqkb1r/pp1p1ppp/5n2/2pPp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 4 1/2-1/2
+8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 0 1 0-1
+
+r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w KQ - 2 8 c9 \"1-0\";
+6k1/8/8/8/8/8/4P3/4K3 w - - 0 1 [1-0]
+4k3/8/8/8/8/8/8/2Q1K3 w - - 0 1 [1.0]
+4k3/8/8/3q4/8/8/8/4K3 b - - 0 1 [0.0]
+4k3/pppppppp/8/8/8/8/8/4K3 w - - 0 1 [0.0]
+";
+
+#[test]
+fn labelled_positions_are_parsed() {
+    let (board, result) = parse_labelled_position("4k3/8/8/8/8/8/8/2Q1K3 w - - 0 1 [0.5]").unwrap();
+    assert_eq!(board.to_fen(), "4k3/8/8/8/8/8/8/2Q1K3 w - - 0 1");
+    assert_eq!(result, 0.5);
+    assert_eq!(parse_labelled_position("4k3/8/8/8/8/8/8/2Q1K3 w - - 0 1 0-1").unwrap().1, 0.0);
+    assert_eq!(parse_labelled_position("4k3/8/8/8/8/8/8/2Q1K3 w - - c9 \"1-0\";").unwrap().1, 1.0);
+    assert!(parse_labelled_position("4k3/8/8/8/8/8/8/2Q1K3 w - - 0 1 [2.0]").is_err());
+    assert!(parse_labelled_position("4k3/8/8/8/8/8/8/2Q1K3 w - - 0 1").is_err());
+
+    let positions = read_positions(BufReader::new(LABELLED.as_bytes())).unwrap();
+    assert_eq!(positions.len(), 9);
+    let results: Vec<f64> = positions.iter().map(|position| position.result).collect();
+    assert_eq!(results, [0.5, 1.0, 0.5, 0.0, 1.0, 1.0, 1.0, 0.0, 0.0]);
+
+    let error = read_positions(BufReader::new("8/8/8 w - - 0 1 [1.0]\n".as_bytes())).unwrap_err();
+    assert_eq!(error.line, 1);
+    // A position the evaluation can't handle is reported instead of panicking later.
+    let error = read_positions(BufReader::new("4k3/8/8/8/8/8/8/4K3 w - - 0 1 [0.5]\n4k3/8/8/8/8/8/8/4RK2 w - - 0 1 [1.0]\n".as_bytes())).unwrap_err();
+    assert_eq!(error.line, 2);
+    assert!(matches!(error.kind, TuneErrorKind::InvalidPosition(_)));
+}
+
+#[test]
+fn the_linear_model_matches_the_evaluation() {
+    let weights = initial_weights();
+    for line in LABELLED.lines().filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        let (board, result) = parse_labelled_position(line).unwrap();
+        let position = TuningPosition::new(&board, result);
+        let expected = eval_trace(&board).score() as f64;
+        // The engine rounds the tapered score down, the model doesn't round at all.
+        assert!((position.evaluate(&weights) - expected).abs() < 1.0, "{line}");
+    }
+}
+
+#[test]
+fn tuning_reduces_the_loss() {
+    let positions = read_positions(BufReader::new(LABELLED.as_bytes())).unwrap();
+    let mut tuner = Tuner::new(&positions, 1.0);
+    let initial = loss(&positions, &tuner.weights, tuner.k);
+    for _ in 0..20 {
+        tuner.epoch(&positions);
+    }
+    assert!(loss(&positions, &tuner.weights, tuner.k) < initial);
+}
+
+#[test]
+fn the_current_weights_are_written_back_unchanged() {
+    assert_eq!(write_params(&initial_weights()), include_str!("../src/eval/params.rs"));
+
+    let mut weights = initial_weights();
+    weights[0] = [90.4, 101.6];
+    assert!(write_params(&weights).contains("pub const MATERIAL: [Score; 6] = [s(90, 102), s(477, 512)"));
+    let board = Board::new();
+    assert_eq!(TuningPosition::new(&board, 0.5).evaluate(&weights), 0.0);
+}
@@ -0,0 +1,309 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::Engine;
+
+use knightbot::board::board::Board;
+use knightbot::book::{encode_move, polyglot_key, Book, BookEntry};
+use knightbot::uci::{parse_go, parse_position};
+
+/// Returns the best move of the search and the info lines printed before it.
+fn best_move(engine: &mut Engine) -> (String, Vec<String>) {
+    let mut lines = engine.read_until("bestmove");
+    let best = lines.pop().unwrap();
+    (best.split_whitespace().nth(1).unwrap().to_string(), lines)
+}
+
+fn assert_legal(position: &str, mv: &str) {
+    let moves = if position.contains(" moves ") { vec![mv] } else { vec!["moves", mv] };
+    let args: Vec<&str> = position.split_whitespace().skip(1).chain(moves).collect();
+    assert!(parse_position(&args, false).is_ok(), "{mv} is not legal after {position}");
+}
+
+#[test]
+fn handshake() {
+    let mut engine = Engine::start();
+    engine.send("uci");
+    let lines = engine.read_until("uciok");
+    assert!(lines.iter().any(|line| line.starts_with("id name ")));
+    assert!(lines.iter().any(|line| line.starts_with("id author ")));
+    for option in ["Hash", "Threads", "Move Overhead", "Ponder", "Clear Hash", "EvalFile", "OwnBook", "BookFile", "BookSelection", "UCI_Chess960"] {
+        assert!(lines.iter().any(|line| line.starts_with(&format!("option name {option} type"))), "missing {option}");
+    }
+    engine.send("isready");
+    engine.read_until("readyok");
+    engine.quit();
+}
+
+#[test]
+fn depth_search_prints_info_lines() {
+    let mut engine = Engine::start();
+    let position = "position startpos moves e2e4 e7e5 g1f3";
+    engine.send("ucinewgame");
+    engine.send(position);
+    engine.send("go depth 5");
+    let (best, info) = best_move(&mut engine);
+    assert_legal(position, &best);
+
+    let depths: Vec<&str> = info.iter().filter_map(|line| line.split_whitespace().nth(2)).collect();
+    assert_eq!(depths, ["1", "2", "3", "4", "5"]);
+    for line in &info {
+        for field in [" depth ", " seldepth ", " score cp ", " nodes ", " nps ", " hashfull ", " time ", " pv "] {
+            assert!(line.contains(field), "{line:?} is missing {field:?}");
+        }
+    }
+    // The best move is the first move of the principal variation.
+    assert!(info.last().unwrap().contains(&format!(" pv {best}")));
+    engine.quit();
+}
+
+#[test]
+fn fen_position_and_mate_limit() {
+    let mut engine = Engine::start();
+    engine.send("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    engine.send("go mate 1");
+    let (best, info) = best_move(&mut engine);
+    assert_eq!(best, "a1a8");
+    assert!(info.last().unwrap().contains("score mate 1"));
+    engine.quit();
+}
+
+#[test]
+fn infinite_search_waits_for_stop() {
+    let mut engine = Engine::start();
+    engine.send("position startpos");
+    engine.send("go infinite");
+    engine.assert_silent("bestmove", Duration::from_millis(300));
+    // The engine stays responsive while searching.
+    engine.send("isready");
+    engine.read_until("readyok");
+    let start = Instant::now();
+    engine.send("stop");
+    let (best, _) = best_move(&mut engine);
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_legal("position startpos", &best);
+    engine.quit();
+}
+
+#[test]
+fn infinite_search_waits_for_stop_after_mate() {
+    // The search quickly runs out of depth here, but the best move must still wait for "stop".
+    let mut engine = Engine::start();
+    engine.send("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    engine.send("go infinite");
+    engine.assert_silent("bestmove", Duration::from_millis(300));
+    engine.send("stop");
+    assert_eq!(best_move(&mut engine).0, "a1a8");
+    engine.quit();
+}
+
+#[test]
+fn movetime_and_clock_limits() {
+    let mut engine = Engine::start();
+    engine.send("position startpos moves d2d4");
+    let start = Instant::now();
+    engine.send("go movetime 200");
+    let (best, _) = best_move(&mut engine);
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_legal("position startpos moves d2d4", &best);
+
+    engine.send("position startpos moves d2d4 d7d5");
+    let start = Instant::now();
+    engine.send("go wtime 2000 btime 2000 winc 10 binc 10");
+    let (best, _) = best_move(&mut engine);
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_legal("position startpos moves d2d4 d7d5", &best);
+
+    engine.send("go nodes 5000 movestogo 5 wtime 100000 btime 100000");
+    let (best, _) = best_move(&mut engine);
+    assert_legal("position startpos moves d2d4 d7d5", &best);
+    engine.quit();
+}
+
+#[test]
+fn ponderhit_switches_to_the_clock() {
+    let mut engine = Engine::start();
+    engine.send("setoption name Ponder value true");
+    engine.send("position startpos moves e2e4");
+    engine.send("go ponder wtime 1000 btime 1000");
+    // While pondering the time limits don't apply.
+    engine.assert_silent("bestmove", Duration::from_millis(1500));
+    engine.send("ponderhit");
+    let start = Instant::now();
+    let (best, _) = best_move(&mut engine);
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_legal("position startpos moves e2e4", &best);
+
+    // A ponder miss stops the search with "stop".
+    engine.send("go ponder wtime 1000 btime 1000");
+    engine.assert_silent("bestmove", Duration::from_millis(200));
+    engine.send("stop");
+    best_move(&mut engine);
+    engine.quit();
+}
+
+#[test]
+fn options_are_applied() {
+    let mut engine = Engine::start();
+    engine.send("setoption name Threads value 2");
+    engine.send("setoption name Hash value 4");
+    engine.send("setoption name Move Overhead value 50");
+    engine.send("setoption name Clear Hash");
+    engine.send("setoption name Foo value 1");
+    engine.send("isready");
+    let lines = engine.read_until("readyok");
+    assert!(lines.iter().any(|line| line.starts_with("info string")));
+    engine.send("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    engine.send("go depth 4");
+    assert_eq!(best_move(&mut engine).0, "a1a8");
+    engine.quit();
+}
+
+#[test]
+fn invalid_input_is_reported() {
+    let mut engine = Engine::start();
+    engine.send("position startpos moves e2e5");
+    engine.read_until("info string");
+    engine.send("position fen not a fen");
+    engine.read_until("info string");
+    engine.send("position fen 8/8/8/8/8/8/8/4K3 w - - 0 1");
+    engine.read_until("info string");
+    engine.send("go sideways");
+    engine.read_until("info string");
+    // Checkmated positions have no best move.
+    engine.send("position startpos moves f2f3 e7e5 g2g4 d8h4");
+    engine.send("go depth 3");
+    assert_eq!(best_move(&mut engine).0, "0000");
+    engine.quit();
+}
+
+#[test]
+fn position_parsing() {
+    let board = parse_position(&["startpos", "moves", "e2e4", "c7c5"], false).unwrap();
+    assert_eq!(board.to_fen(), "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2");
+    let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    let args: Vec<&str> = ["fen"].into_iter().chain(fen.split_whitespace()).chain(["moves", "e1g1"]).collect();
+    let board = parse_position(&args, false).unwrap();
+    assert_eq!(board.to_fen(), Board::from_fen("r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1").unwrap().to_fen());
+}
+
+#[test]
+fn go_parsing() {
+    let board = Board::new();
+    let (limits, infinite, ponder) = parse_go(&["searchmoves", "e2e4", "d2d4", "depth", "99999999999", "movestogo", "99999999999"], &board).unwrap();
+    assert_eq!(limits.search_moves.iter().map(ToString::to_string).collect::<Vec<_>>(), ["e2e4", "d2d4"]);
+    assert_eq!(limits.depth, Some(i32::MAX));
+    assert_eq!(limits.movestogo, Some(u32::MAX));
+    assert!(!infinite && !ponder);
+    assert!(parse_go(&["searchmoves", "e2e5"], &board).is_err());
+    assert!(parse_go(&["depth", "-3"], &board).unwrap().0.depth == Some(0));
+}
+
+#[test]
+fn searchmoves_restricts_the_root_moves() {
+    let mut engine = Engine::start();
+    // The rook mates on a8, but only the king may move.
+    engine.send("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    engine.send("go depth 4 searchmoves g1f2 g1g2");
+    let (best, info) = best_move(&mut engine);
+    assert!(best == "g1f2" || best == "g1g2", "{best}");
+    assert!(info.iter().all(|line| line.contains(&format!(" pv {best}"))));
+    engine.quit();
+}
+
+#[test]
+fn chess960_castling_is_written_as_king_takes_rook() {
+    let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    let position = |castling: &str, chess960| {
+        let args: Vec<&str> = ["fen"].into_iter().chain(fen.split_whitespace()).chain(["moves", castling]).collect();
+        parse_position(&args, chess960)
+    };
+    let castled = position("e1h1", true).unwrap();
+    assert_eq!(castled.to_fen(), "r3k2r/8/8/8/8/8/8/R4RK1 b ha - 1 1");
+    assert!(position("e1g1", true).is_err());
+    assert!(position("e1h1", false).is_err());
+
+    let mut engine = Engine::start();
+    engine.send("setoption name UCI_Chess960 value true");
+    engine.send(&format!("position fen {fen} moves e1h1"));
+    engine.send("eval");
+    let lines = engine.read_until("FEN: ");
+    assert_eq!(lines.last().unwrap(), "FEN: r3k2r/8/8/8/8/8/8/R4RK1 b ha - 1 1");
+    engine.quit();
+}
+
+#[test]
+fn turning_chess960_off_restores_standard_castling() {
+    let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    let mut engine = Engine::start();
+    engine.send("setoption name UCI_Chess960 value true");
+    engine.send(&format!("position fen {fen}"));
+    engine.send("setoption name UCI_Chess960 value false");
+    engine.send("eval");
+    assert_eq!(engine.read_until("FEN: ").last().unwrap(), &format!("FEN: {fen}"));
+    engine.send(&format!("position fen {fen} moves e1g1"));
+    engine.send("eval");
+    assert_eq!(engine.read_until("FEN: ").last().unwrap(), "FEN: r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+
+    // Positions that can't be played with standard castling stay in Chess960 mode.
+    let chess960_fen = "1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1";
+    engine.send(&format!("position fen {chess960_fen}"));
+    engine.send("eval");
+    assert_eq!(engine.read_until("FEN: ").last().unwrap(), &format!("FEN: {chess960_fen}"));
+    engine.quit();
+}
+
+#[test]
+fn eval_prints_the_breakdown_of_the_current_position() {
+    let mut engine = Engine::start();
+    engine.send("position startpos moves e2e4");
+    engine.send("eval");
+    let lines = engine.read_until("FEN: ");
+    assert!(lines[0].starts_with("8 r n b q k b n r ") && lines[0].ends_with("Total"));
+    assert!(lines.iter().any(|line| line.starts_with("4 . . . . P . . . ") && line.contains("Pawns")));
+    assert_eq!(lines.last().unwrap(), "FEN: rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+    engine.quit();
+}
+
+#[test]
+fn d_prints_a_diagram_of_the_current_position() {
+    let mut engine = Engine::start();
+    engine.send("position startpos moves e2e4");
+    engine.send("d");
+    let lines = engine.read_until("FEN: ");
+    assert!(lines.contains(&"4 | .  .  .  .  P  .  .  . |".to_string()));
+    assert!(lines.contains(&"Side to move: black".to_string()));
+    engine.quit();
+}
+
+#[test]
+fn own_book_plays_book_moves_without_searching() {
+    let board = Board::new();
+    let entry = BookEntry { key: polyglot_key(&board), mv: encode_move(&board, "d2d4".parse().unwrap()), weight: 1, learn: 0 };
+    let path = std::env::temp_dir().join(format!("knightbot-uci-book-{}.bin", std::process::id()));
+    std::fs::write(&path, Book::from_entries(vec![entry]).to_bytes()).unwrap();
+
+    let mut engine = Engine::start();
+    engine.send(&format!("setoption name BookFile value {}", path.display()));
+    engine.send("setoption name OwnBook value true");
+    engine.send("position startpos");
+    engine.send("go depth 3");
+    let (best, info) = best_move(&mut engine);
+    assert_eq!(best, "d2d4");
+    assert!(info.iter().any(|line| line == "info string book move"));
+
+    // Out of book the engine searches as usual.
+    engine.send("position startpos moves d2d4");
+    engine.send("go depth 3");
+    let (_, info) = best_move(&mut engine);
+    assert!(info.iter().any(|line| line.contains(" depth 3 ")));
+
+    engine.send("setoption name OwnBook value false");
+    engine.send("position startpos");
+    engine.send("go depth 3");
+    let (_, info) = best_move(&mut engine);
+    assert!(!info.iter().any(|line| line == "info string book move"));
+    engine.quit();
+    std::fs::remove_file(path).unwrap();
+}
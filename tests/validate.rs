@@ -0,0 +1,93 @@
+use knightbot::board::{
+    bitboard::BitBoard,
+    board::{Board, CastlingRights},
+    piece::Color,
+    square::Square,
+    validate::PositionError,
+};
+use knightbot::epd::{EpdEntry, EpdErrorKind};
+use knightbot::pgn::{PgnErrorKind, PgnReader};
+use knightbot::tune::{parse_labelled_position, TuneErrorKind};
+use knightbot::uci::parse_position;
+
+fn validate(fen: &str) -> Result<(), PositionError> {
+    Board::from_fen(fen).unwrap().validate()
+}
+
+#[test]
+fn legal_positions_are_valid() {
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        "4k3/8/8/8/8/8/8/4K2R b K - 0 1",
+    ] {
+        assert_eq!(validate(fen), Ok(()), "{fen}");
+    }
+}
+
+#[test]
+fn impossible_positions_are_rejected() {
+    assert_eq!(validate("8/8/8/8/8/8/8/4K3 w - - 0 1"), Err(PositionError::KingCount(Color::Black, 0)));
+    assert_eq!(validate("4k3/8/8/8/8/8/8/3KK3 w - - 0 1"), Err(PositionError::KingCount(Color::White, 2)));
+    assert_eq!(validate("4k2P/8/8/8/8/8/8/4K3 w - - 0 1"), Err(PositionError::PawnOnBackRank(Square::H8)));
+    assert_eq!(validate("4k3/8/8/8/8/8/8/p3K3 b - - 0 1"), Err(PositionError::PawnOnBackRank(Square::A1)));
+    // White to move could capture the black king.
+    assert_eq!(validate("4k2R/8/8/8/8/8/8/4K3 w - - 0 1"), Err(PositionError::OpponentInCheck));
+}
+
+#[test]
+fn castling_rights_and_en_passant_must_be_possible() {
+    // The rook is gone.
+    assert_eq!(validate("4k3/8/8/8/8/8/8/4K3 w K - 0 1"), Err(PositionError::InvalidCastlingRights(CastlingRights::WHITE_KINGSIDE)));
+    // The king left the back rank.
+    assert_eq!(validate("r7/4k3/8/8/8/8/8/4K3 w q - 0 1"), Err(PositionError::InvalidCastlingRights(CastlingRights::BLACK_QUEENSIDE)));
+    // In Chess960 the rook must still be on the side of the king it castles to.
+    let mut board = Board::from_fen("4k3/8/8/8/8/8/8/1R2K3 w B - 0 1").unwrap();
+    assert_eq!(board.validate(), Ok(()));
+    board.castling_rights = CastlingRights::WHITE_KINGSIDE;
+    board.castling_rooks[0] = Square::B1;
+    assert_eq!(board.validate(), Err(PositionError::InvalidCastlingRights(CastlingRights::WHITE_KINGSIDE)));
+
+    // The en passant square must be behind a pawn that just moved two squares.
+    assert_eq!(validate("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1"), Err(PositionError::InvalidEnPassant(Square::E3)));
+    assert_eq!(validate("4k3/8/8/8/8/8/8/4K3 b - e3 0 1"), Err(PositionError::InvalidEnPassant(Square::E3)));
+    assert_eq!(validate("4k3/8/8/8/4P3/8/4P3/4K3 b - e3 0 1"), Err(PositionError::InvalidEnPassant(Square::E3)));
+    assert_eq!(validate("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1"), Ok(()));
+}
+
+#[test]
+fn inconsistent_bitboards_are_rejected() {
+    let mut board = Board::new();
+    board.white_knights |= BitBoard::from(Square::E2);
+    assert_eq!(board.validate(), Err(PositionError::OverlappingPieces(Square::E2)));
+
+    let mut board = Board::new();
+    board.white_queens |= BitBoard::from(Square::D4);
+    assert_eq!(board.validate(), Err(PositionError::InconsistentOccupancy("white_pieces")));
+    board.white_pieces |= BitBoard::from(Square::D4);
+    assert_eq!(board.validate(), Err(PositionError::InconsistentOccupancy("all_pieces")));
+    board.all_pieces |= BitBoard::from(Square::D4);
+    assert_eq!(board.validate(), Err(PositionError::InconsistentOccupancy("tiles")));
+    board.update();
+    assert_eq!(board.validate(), Ok(()));
+}
+
+#[test]
+fn position_loaders_reject_impossible_positions() {
+    // Parses as FEN, but black has no king.
+    let fen = "8/8/8/8/8/8/4P3/4K3 w - - 0 1";
+    let args: Vec<&str> = ["fen"].into_iter().chain(fen.split_whitespace()).collect();
+    assert_eq!(parse_position(&args, false).unwrap_err(), "black has 0 kings");
+
+    let pgn = format!("[FEN \"{fen}\"]\n\n1. e3 *\n");
+    let error = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap_err();
+    assert!(matches!(error.kind, PgnErrorKind::InvalidPosition(PositionError::KingCount(Color::Black, 0))));
+
+    let error = EpdEntry::parse("8/8/8/8/8/8/4P3/4K3 w - - bm e3;").unwrap_err();
+    assert!(matches!(error, EpdErrorKind::InvalidPosition(PositionError::KingCount(Color::Black, 0))));
+
+    let error = parse_labelled_position(&format!("{fen} [0.5]")).unwrap_err();
+    assert!(matches!(error, TuneErrorKind::InvalidPosition(PositionError::KingCount(Color::Black, 0))));
+}
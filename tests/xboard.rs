@@ -0,0 +1,211 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::Engine;
+use knightbot::uci::parse_position;
+
+fn start() -> Engine {
+    let mut engine = Engine::start();
+    engine.send("xboard");
+    engine.send("protover 2");
+    engine.read_until("feature");
+    engine
+}
+
+/// Returns the move the engine played and the lines printed before it.
+fn engine_move(engine: &mut Engine) -> (String, Vec<String>) {
+    let mut lines = engine.read_until("move ");
+    let mv = lines.pop().unwrap();
+    (mv.split_whitespace().nth(1).unwrap().to_string(), lines)
+}
+
+fn assert_legal(moves: &[&str], mv: &str) {
+    let args: Vec<&str> = ["startpos", "moves"].iter().chain(moves).chain([&mv]).copied().collect();
+    assert!(parse_position(&args, false).is_ok(), "{mv} is not legal after {moves:?}");
+}
+
+#[test]
+fn handshake() {
+    let mut engine = Engine::start();
+    engine.send("xboard");
+    engine.send("protover 2");
+    let features = engine.read_until("feature");
+    let features = features.last().unwrap();
+    for feature in ["ping=1", "setboard=1", "usermove=1", "time=1", "done=1"] {
+        assert!(features.contains(feature), "missing {feature} in {features:?}");
+    }
+    engine.send("ping 7");
+    engine.read_until("pong 7");
+    engine.quit();
+}
+
+#[test]
+fn engine_plays_black_after_new() {
+    let mut engine = start();
+    engine.send("new");
+    engine.send("level 40 1 0");
+    engine.send("post");
+    engine.send("time 6000");
+    engine.send("otim 6000");
+    engine.send("usermove e2e4");
+    let (reply, thinking) = engine_move(&mut engine);
+    assert_legal(&["e2e4"], &reply);
+    assert!(!thinking.is_empty());
+    for line in &thinking {
+        // depth score time nodes pv
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        assert!(fields.len() >= 5, "{line:?}");
+        assert!(fields[..4].iter().all(|field| field.parse::<i64>().is_ok()), "{line:?}");
+    }
+
+    // The pong comes after the engine's move, and the engine keeps playing black.
+    engine.send("usermove d2d4");
+    let (second, _) = engine_move(&mut engine);
+    assert_legal(&["e2e4", &reply, "d2d4"], &second);
+    engine.send("ping 2");
+    engine.read_until("pong 2");
+    engine.quit();
+}
+
+#[test]
+fn force_mode_and_go() {
+    let mut engine = start();
+    engine.send("new");
+    engine.send("force");
+    engine.send("usermove e2e4");
+    engine.send("usermove e7e5");
+    engine.send("nopost");
+    engine.send("sd 3");
+    // No move is made in force mode.
+    engine.assert_silent("move ", Duration::from_millis(300));
+    engine.send("go");
+    let (mv, thinking) = engine_move(&mut engine);
+    assert_legal(&["e2e4", "e7e5"], &mv);
+    assert!(thinking.is_empty(), "{thinking:?}");
+    engine.quit();
+}
+
+#[test]
+fn mate_is_played_and_reported() {
+    let mut engine = start();
+    engine.send("new");
+    engine.send("force");
+    engine.send("setboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    engine.send("sd 4");
+    engine.send("post");
+    engine.send("go");
+    let (mv, thinking) = engine_move(&mut engine);
+    assert_eq!(mv, "a1a8");
+    assert_eq!(thinking.last().unwrap().split_whitespace().nth(1), Some("100001"));
+    engine.read_until("1-0 {White mates}");
+    engine.quit();
+}
+
+#[test]
+fn user_move_ending_the_game_is_reported() {
+    let mut engine = start();
+    engine.send("new");
+    engine.send("setboard k7/8/8/2Q5/8/8/8/K7 w - - 0 1");
+    engine.send("usermove c5b6");
+    engine.read_until("1/2-1/2 {Stalemate}");
+    engine.send("ping 1");
+    let lines = engine.read_until("pong 1");
+    assert!(!lines.iter().any(|line| line.starts_with("move ")));
+    engine.quit();
+}
+
+#[test]
+fn undo_and_remove() {
+    let mut engine = start();
+    engine.send("new");
+    engine.send("force");
+    engine.send("usermove e2e4");
+    engine.send("usermove e7e5");
+    engine.send("usermove g1f3");
+    engine.send("undo");
+    // White is to move again, so a black move is illegal.
+    engine.send("usermove b8c6");
+    engine.read_until("Illegal move: b8c6");
+    engine.send("remove");
+    engine.send("usermove e7e5");
+    engine.read_until("Illegal move: e7e5");
+    engine.send("usermove e2e4");
+    engine.send("remove");
+    engine.send("remove");
+    engine.read_until("Error (no moves to undo): remove");
+    engine.quit();
+}
+
+#[test]
+fn time_controls() {
+    let mut engine = start();
+    engine.send("new");
+    engine.send("force");
+    engine.send("st 0.2");
+    let start = Instant::now();
+    engine.send("go");
+    let (mv, _) = engine_move(&mut engine);
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_legal(&[], &mv);
+
+    // "?" makes the engine move immediately.
+    engine.send("force");
+    engine.send("level 0 10:30 5");
+    engine.send("st 100");
+    let start = Instant::now();
+    engine.send("go");
+    thread_sleep(200);
+    engine.send("?");
+    let (second, _) = engine_move(&mut engine);
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_legal(&[&mv], &second);
+
+    // A level without st uses the clock.
+    engine.send("force");
+    engine.send("level 0 0:05 0");
+    engine.send("time 300");
+    engine.send("otim 300");
+    let start = Instant::now();
+    engine.send("go");
+    let (third, _) = engine_move(&mut engine);
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_legal(&[&mv, &second], &third);
+    engine.quit();
+}
+
+#[test]
+fn invalid_input_is_reported() {
+    let mut engine = start();
+    engine.send("new");
+    engine.send("usermove e2e5");
+    engine.read_until("Illegal move: e2e5");
+    engine.send("frobnicate");
+    engine.read_until("Error (unknown command): frobnicate");
+    engine.send("setboard 8/8/8 w - - 0 1");
+    engine.read_until("tellusererror Illegal position");
+    engine.send("level 40");
+    engine.read_until("Error (invalid time control)");
+    // Times too large for a Duration are rejected instead of crashing the engine.
+    engine.send("level 40 5 inf");
+    engine.read_until("Error (invalid time control)");
+    engine.send("level 40 307445734561825861 0");
+    engine.read_until("Error (invalid time control)");
+    engine.send("st inf");
+    engine.read_until("Error (invalid move time)");
+    engine.send("st 1e300");
+    engine.read_until("Error (invalid move time)");
+    // Clock times too large for a Duration are clamped, and the engine still plays.
+    engine.send("sd 1");
+    engine.send("time 9223372036854775807");
+    engine.send("otim 9223372036854775807");
+    engine.send("go");
+    engine_move(&mut engine);
+    engine.send("ping 1");
+    engine.read_until("pong 1");
+    engine.quit();
+}
+
+fn thread_sleep(millis: u64) {
+    std::thread::sleep(Duration::from_millis(millis));
+}